@@ -37,31 +37,43 @@ fn main() {
         .expect("OUT_DIR should be set by cargo")
         .into();
 
-    let template_dir = manifest_dir.join("templates").join("new");
-    let dst_file = out_dir.join("template.zip");
-
-    println!(
-        "Creating template zip: template_dir '{}', destination archive '{}'",
-        template_dir.display(),
-        dst_file.display()
-    );
-
-    std::process::exit(
-        match zip_dir(&template_dir, &dst_file, CompressionMethod::Stored) {
-            Ok(_) => {
-                println!(
-                    "done: {} written to {}",
-                    template_dir.display(),
-                    dst_file.display()
-                );
-                0
-            }
-            Err(e) => {
-                eprintln!("Error: {:?}", e);
-                1
-            }
-        },
-    );
+    let templates_dir = manifest_dir.join("templates").join("new");
+
+    std::process::exit(match zip_templates(&templates_dir, &out_dir) {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            1
+        }
+    });
+}
+
+/// Creates a `template-{name}.zip` archive in `out_dir` for every template directory.
+fn zip_templates(templates_dir: &PathBuf, out_dir: &PathBuf) -> Result<()> {
+    for entry in std::fs::read_dir(templates_dir)? {
+        let template_dir = entry?.path();
+        if !template_dir.is_dir() {
+            continue;
+        }
+        let name = template_dir
+            .file_name()
+            .expect("template directories have a name")
+            .to_string_lossy();
+        let dst_file = out_dir.join(format!("template-{}.zip", name));
+
+        println!(
+            "Creating template zip: template_dir '{}', destination archive '{}'",
+            template_dir.display(),
+            dst_file.display()
+        );
+        zip_dir(&template_dir, &dst_file, CompressionMethod::Stored)?;
+        println!(
+            "done: {} written to {}",
+            template_dir.display(),
+            dst_file.display()
+        );
+    }
+    Ok(())
 }
 
 fn zip_dir(src_dir: &PathBuf, dst_file: &PathBuf, method: CompressionMethod) -> Result<()> {
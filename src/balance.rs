@@ -0,0 +1,201 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Parsing of balances given either as raw integers or in units of the token of the chain.
+
+use std::str::FromStr;
+
+use anyhow::Result;
+
+/// The metric prefixes accepted in front of the token symbol, and their decimal exponents.
+const PREFIXES: [(&str, i32); 6] = [
+    ("k", 3),
+    ("m", -3),
+    ("u", -6),
+    ("µ", -6),
+    ("n", -9),
+    ("p", -12),
+];
+
+/// A balance argument, e.g. `--value 1000000`, `--value 1.5UNIT` or `--value 100mUNIT`.
+///
+/// Raw integers are the balance in the smallest unit of the chain. A balance with a token symbol
+/// is converted using the number of decimals of the token, see [`BalanceArg::to_raw`]. The symbol
+/// must be upper case, so that it can be told apart from a lower case metric prefix.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BalanceArg {
+    Raw(u128),
+    Units {
+        /// The digits before the decimal point.
+        integer: String,
+        /// The digits after the decimal point.
+        fraction: String,
+        /// The decimal exponent of the metric prefix, `0` if there is none.
+        exponent: i32,
+        symbol: String,
+    },
+}
+
+impl FromStr for BalanceArg {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let input = input.trim().replace('_', "");
+        if let Ok(raw) = input.parse::<u128>() {
+            return Ok(BalanceArg::Raw(raw));
+        }
+        let number_len = input
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or_else(|| input.len());
+        let (number, unit) = input.split_at(number_len);
+        let mut parts = number.splitn(2, '.');
+        let integer = parts.next().unwrap_or_default().to_string();
+        let fraction = parts.next().unwrap_or_default().to_string();
+        if (integer.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+            anyhow::bail!(
+                "Invalid balance '{}', expected an integer or an amount of the token, e.g. \
+                1.5UNIT or 100mUNIT",
+                input
+            )
+        }
+        let (exponent, symbol) = PREFIXES
+            .iter()
+            .find(|(prefix, _)| unit.starts_with(prefix))
+            .map_or((0, unit), |(prefix, exponent)| {
+                (*exponent, &unit[prefix.len()..])
+            });
+        if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_uppercase()) {
+            anyhow::bail!(
+                "Invalid unit '{}' of balance '{}', expected an optional prefix (k, m, u, n, p) \
+                followed by the upper case token symbol, e.g. mUNIT",
+                unit,
+                input
+            )
+        }
+        Ok(BalanceArg::Units {
+            integer,
+            fraction,
+            exponent,
+            symbol: symbol.to_string(),
+        })
+    }
+}
+
+impl BalanceArg {
+    /// Returns whether converting the balance requires the number of decimals of the token.
+    pub fn has_units(&self) -> bool {
+        matches!(self, BalanceArg::Units { .. })
+    }
+
+    /// Returns the balance in the smallest unit of the chain, whose token has `decimals` decimals.
+    ///
+    /// Fails if the balance overflows or is more precise than the smallest unit.
+    pub fn to_raw(&self, decimals: u8) -> Result<u128> {
+        let (integer, fraction, exponent) = match self {
+            BalanceArg::Raw(raw) => return Ok(*raw),
+            BalanceArg::Units {
+                integer,
+                fraction,
+                exponent,
+                ..
+            } => (integer, fraction.trim_end_matches('0'), *exponent),
+        };
+        let shift = i32::from(decimals) + exponent - fraction.len() as i32;
+        if shift < 0 {
+            anyhow::bail!(
+                "Balance {} is more precise than the {} decimals of the token",
+                self,
+                decimals
+            )
+        }
+        let digits = format!("{}{}", integer, fraction);
+        let overflow = || anyhow::anyhow!("Balance {} is too large", self);
+        let mantissa = if digits.is_empty() {
+            0
+        } else {
+            digits.parse::<u128>().map_err(|_| overflow())?
+        };
+        10u128
+            .checked_pow(shift as u32)
+            .and_then(|factor| mantissa.checked_mul(factor))
+            .ok_or_else(overflow)
+    }
+}
+
+impl std::fmt::Display for BalanceArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BalanceArg::Raw(raw) => write!(f, "{}", raw),
+            BalanceArg::Units {
+                integer,
+                fraction,
+                exponent,
+                symbol,
+            } => {
+                let prefix = PREFIXES
+                    .iter()
+                    .find(|(_, prefix_exponent)| prefix_exponent == exponent)
+                    .map_or("", |(prefix, _)| *prefix);
+                if fraction.is_empty() {
+                    write!(f, "{}{}{}", integer, prefix, symbol)
+                } else {
+                    write!(f, "{}.{}{}{}", integer, fraction, prefix, symbol)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BalanceArg;
+
+    fn to_raw(input: &str, decimals: u8) -> anyhow::Result<u128> {
+        input.parse::<BalanceArg>()?.to_raw(decimals)
+    }
+
+    #[test]
+    fn raw_integers_are_kept() {
+        assert_eq!(to_raw("1000000", 12).unwrap(), 1_000_000);
+        assert_eq!(to_raw("1_000_000", 12).unwrap(), 1_000_000);
+        assert!(!"42".parse::<BalanceArg>().unwrap().has_units());
+    }
+
+    #[test]
+    fn units_are_converted_with_decimals() {
+        assert_eq!(to_raw("1.5UNIT", 12).unwrap(), 1_500_000_000_000);
+        assert_eq!(to_raw("100mUNIT", 12).unwrap(), 100_000_000_000);
+        assert_eq!(to_raw("2kDOT", 10).unwrap(), 20_000_000_000_000);
+        assert_eq!(to_raw(".25UNIT", 2).unwrap(), 25);
+        assert_eq!(to_raw("1.50UNIT", 1).unwrap(), 15);
+        assert!("1.5UNIT".parse::<BalanceArg>().unwrap().has_units());
+    }
+
+    #[test]
+    fn invalid_balances_are_rejected() {
+        assert!("UNIT".parse::<BalanceArg>().is_err());
+        assert!("1.5unit".parse::<BalanceArg>().is_err());
+        assert!("1.2.3UNIT".parse::<BalanceArg>().is_err());
+        assert!("-1".parse::<BalanceArg>().is_err());
+
+        let err = to_raw("1.5pUNIT", 12).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Balance 1.5pUNIT is more precise than the 12 decimals of the token"
+        );
+        assert!(to_raw("1000000000000000000000000000000UNIT", 12).is_err());
+    }
+}
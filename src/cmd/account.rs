@@ -0,0 +1,184 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::convert::TryFrom;
+
+use anyhow::Result;
+use serde::Serialize;
+use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+
+use crate::HexData;
+
+/// The networks whose SS58 addresses are printed, with their address prefixes.
+const NETWORKS: [(&str, u8); 3] = [("substrate", 42), ("polkadot", 0), ("kusama", 2)];
+
+/// An account rendered in the formats accepted by the different commands.
+#[derive(Debug, Serialize)]
+pub struct AccountResult {
+    /// The public key, hex encoded, as accepted by `--target` of the gateway calls.
+    pub public_key: String,
+    /// The raw bytes of the `AccountId32`.
+    pub account_id: Vec<u8>,
+    /// The SS58 prefix of the supplied address, `None` if it was given as hex.
+    pub prefix: Option<u8>,
+    pub addresses: Vec<Ss58Address>,
+}
+
+/// The SS58 address of the account on a network.
+#[derive(Debug, Serialize)]
+pub struct Ss58Address {
+    pub network: String,
+    pub prefix: u8,
+    pub address: String,
+}
+
+impl std::fmt::Display for AccountResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Public key (hex): {}", self.public_key)?;
+        write!(f, "Account id:       {:?}", self.account_id)?;
+        for address in &self.addresses {
+            write!(
+                f,
+                "\nSS58 address ({}, prefix {}): {}",
+                address.network, address.prefix, address.address
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts an account given either as SS58 address or as hex encoded public key into the other
+/// formats. No connection to a node is required.
+///
+/// If the address uses a prefix other than the ones of the well known networks, it is listed as
+/// well.
+pub fn execute(input: &str) -> Result<AccountResult> {
+    let (account, prefix) = parse_account(input)?;
+    let mut networks = NETWORKS
+        .iter()
+        .map(|(network, prefix)| (network.to_string(), *prefix))
+        .collect::<Vec<_>>();
+    if let Some(prefix) = prefix.filter(|p| !NETWORKS.iter().any(|(_, known)| known == p)) {
+        networks.push(("custom".to_string(), prefix));
+    }
+    let addresses = networks
+        .into_iter()
+        .map(|(network, prefix)| {
+            Ok(Ss58Address {
+                address: ss58_address(&account, prefix)?,
+                network,
+                prefix,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let account_id: &[u8] = account.as_ref();
+    Ok(AccountResult {
+        public_key: format!("0x{}", hex::encode(account_id)),
+        account_id: account_id.to_vec(),
+        prefix,
+        addresses,
+    })
+}
+
+/// Parses an SS58 address, returning its prefix as well, or a hex encoded 32 byte public key.
+fn parse_account(input: &str) -> Result<(AccountId32, Option<u8>)> {
+    if input.starts_with("0x") {
+        let bytes = hex::decode(input.trim_start_matches("0x"))
+            .map_err(|err| anyhow::anyhow!("Invalid hex encoded public key: {}", err))?;
+        if bytes.len() != 32 {
+            anyhow::bail!("Public key must be 32 bytes, got {}", bytes.len())
+        }
+        let mut account = [0u8; 32];
+        account.copy_from_slice(&bytes);
+        return Ok((AccountId32::from(account), None));
+    }
+    let (account, format) = AccountId32::from_ss58check_with_version(input).map_err(|err| {
+        anyhow::anyhow!(
+            "Invalid account '{}', expected an SS58 address or a 0x prefixed public key: {:?}",
+            input,
+            err
+        )
+    })?;
+    Ok((account, Some(format.into())))
+}
+
+/// Renders the account as SS58 address with the given prefix.
+pub fn ss58_address(account: &AccountId32, prefix: u8) -> Result<String> {
+    let format = Ss58AddressFormat::try_from(prefix)
+        .map_err(|_| anyhow::anyhow!("Unsupported SS58 prefix {}", prefix))?;
+    Ok(account.to_ss58check_with_version(format))
+}
+
+/// Converts hex encoded bytes to an account id, checking that they are 32 bytes long.
+pub fn account_from_hex(name: &str, data: &HexData) -> Result<AccountId32> {
+    if data.0.len() != 32 {
+        anyhow::bail!("{} account must be 32 bytes, got {}", name, data.0.len())
+    }
+    let mut account = [0u8; 32];
+    account.copy_from_slice(&data.0);
+    Ok(AccountId32::from(account))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{account_from_hex, execute};
+    use crate::HexData;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const ALICE_PUBLIC_KEY: &str =
+        "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+    #[test]
+    fn ss58_address_is_converted_to_public_key() {
+        let result = execute(ALICE).unwrap();
+        assert_eq!(result.public_key, ALICE_PUBLIC_KEY);
+        assert_eq!(result.prefix, Some(42));
+        assert_eq!(result.account_id.len(), 32);
+        assert_eq!(result.account_id[0], 0xd4);
+    }
+
+    #[test]
+    fn public_key_is_converted_to_ss58_addresses() {
+        let result = execute(ALICE_PUBLIC_KEY).unwrap();
+        assert_eq!(result.prefix, None);
+        let address = |network: &str| {
+            result
+                .addresses
+                .iter()
+                .find(|address| address.network == network)
+                .map(|address| address.address.clone())
+        };
+        assert_eq!(address("substrate").as_deref(), Some(ALICE));
+        assert_eq!(
+            address("polkadot").as_deref(),
+            Some("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5")
+        );
+    }
+
+    #[test]
+    fn invalid_accounts_are_rejected() {
+        assert!(execute("0x1234").is_err());
+        assert!(execute("not an address").is_err());
+    }
+
+    #[test]
+    fn target_account_must_be_32_bytes() {
+        let err = account_from_hex("target", &"0x0102".parse::<HexData>().unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "target account must be 32 bytes, got 2");
+        let account = account_from_hex("target", &HexData(vec![1; 32])).unwrap();
+        assert_eq!(AsRef::<[u8]>::as_ref(&account), &[1; 32][..]);
+    }
+}
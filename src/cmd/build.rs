@@ -15,25 +15,160 @@
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-    fs::metadata,
-    io::{self, Write},
-    path::PathBuf,
-    process::Command,
+    fs::{self, metadata},
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Instant,
 };
 
 use crate::{
+    cmd::{check_env, metadata::METADATA_FILE},
     crate_metadata::CrateMetadata,
+    reporter::Reporter,
     util,
     workspace::{ManifestPath, Profile, Workspace},
-    UnstableFlags, Verbosity,
+    Features, LockFlags, UnstableFlags, Verbosity,
 };
 use anyhow::{Context, Result};
 use colored::Colorize;
 use parity_wasm::elements::{External, MemoryType, Module, Section};
+use serde::Serialize;
 
 /// This is the maximum number of pages available for a contract to allocate.
 const MAX_MEMORY_PAGES: u32 = 16;
 
+/// The optimization level `wasm-opt` is run with, as in its `-O<level>` flags.
+///
+/// Defaults to `3`. Level `0` skips the post processing and the optimization altogether, i.e. the
+/// raw wasm file produced by cargo is the result of the build.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptimizationPasses {
+    Zero,
+    One,
+    Two,
+    Three,
+    Four,
+    /// Optimize for size.
+    S,
+    /// Optimize aggressively for size.
+    Z,
+}
+
+impl Default for OptimizationPasses {
+    fn default() -> Self {
+        OptimizationPasses::Three
+    }
+}
+
+impl OptimizationPasses {
+    /// Returns whether the post processing and the optimization are skipped.
+    pub fn is_skipped(self) -> bool {
+        self == OptimizationPasses::Zero
+    }
+}
+
+impl std::str::FromStr for OptimizationPasses {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "0" => Ok(OptimizationPasses::Zero),
+            "1" => Ok(OptimizationPasses::One),
+            "2" => Ok(OptimizationPasses::Two),
+            "3" => Ok(OptimizationPasses::Three),
+            "4" => Ok(OptimizationPasses::Four),
+            "s" => Ok(OptimizationPasses::S),
+            "z" => Ok(OptimizationPasses::Z),
+            _ => anyhow::bail!(
+                "Unknown optimization passes '{}', expected one of 0, 1, 2, 3, 4, s, z",
+                input
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for OptimizationPasses {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self {
+            OptimizationPasses::Zero => "0",
+            OptimizationPasses::One => "1",
+            OptimizationPasses::Two => "2",
+            OptimizationPasses::Three => "3",
+            OptimizationPasses::Four => "4",
+            OptimizationPasses::S => "s",
+            OptimizationPasses::Z => "z",
+        };
+        write!(f, "{}", level)
+    }
+}
+
+/// The format of the diagnostics of the compiler, as in cargo's `--message-format`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageFormat {
+    /// Human readable diagnostics, warnings are printed after a successful build.
+    Human,
+    /// The json messages of cargo are forwarded to stdout as they are emitted, e.g. for editors
+    /// to render the diagnostics inline.
+    Json,
+}
+
+impl Default for MessageFormat {
+    fn default() -> Self {
+        MessageFormat::Human
+    }
+}
+
+impl std::str::FromStr for MessageFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => anyhow::bail!(
+                "Unknown message format '{}', expected one of human, json",
+                input
+            ),
+        }
+    }
+}
+
+/// Result of a successful contract build.
+#[derive(Debug, Serialize)]
+pub struct BuildResult {
+    /// Path to the resulting wasm file, ready for deploying.
+    pub dest_wasm: PathBuf,
+    /// Size in bytes of the wasm file produced by cargo.
+    pub original_size: u64,
+    /// Size in bytes of the resulting wasm file, after post processing and optimization.
+    pub optimized_size: u64,
+    /// Duration of the whole build in milliseconds.
+    pub build_duration_ms: u64,
+    /// Hash of the resulting wasm file, hex encoded, as computed by the contracts pallet.
+    pub code_hash: String,
+}
+
+/// Computes the blake2 256 hash of the wasm code, the same way the contracts pallet does.
+pub fn code_hash(wasm: &[u8]) -> [u8; 32] {
+    use blake2::digest::{Update as _, VariableOutput as _};
+    let mut output = [0u8; 32];
+    let mut blake2 = blake2::VarBlake2b::new_keyed(&[], 32);
+    blake2.update(wasm);
+    blake2.finalize_variable(|result| output.copy_from_slice(result));
+    output
+}
+
+/// Returns the hash of the wasm file, hex encoded with a `0x` prefix.
+fn wasm_file_hash(path: &Path) -> Result<String> {
+    let wasm = fs::read(path).context(format!("Reading {}", path.display()))?;
+    let hash = code_hash(&wasm)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    Ok(format!("0x{}", hash))
+}
+
 /// Builds the project in the specified directory, defaults to the current directory.
 ///
 /// Uses [`cargo-xbuild`](https://github.com/rust-osdev/cargo-xbuild) for maximum optimization of
@@ -48,12 +183,30 @@ const MAX_MEMORY_PAGES: u32 = 16;
 /// user-defined settings will be preserved.
 ///
 /// To disable this and use the original `Cargo.toml` as is then pass the `-Z original_manifest` flag.
-fn build_cargo_project(
+///
+/// The supplied cargo `command` is either `build`, or `check` to only type check the contract.
+/// If `debug` is set the contract is compiled in the dev profile instead of the release profile.
+///
+/// The contract is always compiled without its default features, since they enable `std`, the
+/// supplied `features` are activated on top.
+///
+/// With `MessageFormat::Json` cargo is invoked with `--message-format=json`, and its messages are
+/// written to stdout unaltered.
+///
+/// The `lock` flags are forwarded to cargo, to fail instead of updating `Cargo.lock` or
+/// accessing the network.
+pub(crate) fn build_cargo_project(
     crate_metadata: &CrateMetadata,
+    command: &str,
+    debug: bool,
     verbosity: Option<Verbosity>,
+    message_format: MessageFormat,
+    lock: LockFlags,
     unstable_options: UnstableFlags,
+    features: &Features,
 ) -> Result<()> {
     util::assert_channel()?;
+    check_env::preflight()?;
 
     // set RUSTFLAGS, read from environment var by cargo-xbuild
     std::env::set_var(
@@ -61,7 +214,7 @@ fn build_cargo_project(
         "-C link-arg=-z -C link-arg=stack-size=65536 -C link-arg=--import-memory",
     );
 
-    let verbosity = verbosity.map(|v| match v {
+    let xbuild_verbosity = verbosity.map(|v| match v {
         Verbosity::Verbose => xargo_lib::Verbosity::Verbose,
         Verbosity::Quiet => xargo_lib::Verbosity::Quiet,
     });
@@ -69,13 +222,28 @@ fn build_cargo_project(
     let xbuild = |manifest_path: &ManifestPath| {
         let manifest_path = Some(manifest_path);
         let target = Some("wasm32-unknown-unknown");
-        let target_dir = &crate_metadata.cargo_meta.target_directory;
-        let other_args = [
-            "--no-default-features",
-            "--release",
-            &format!("--target-dir={}", target_dir.to_string_lossy()),
-        ];
-        let args = xargo_lib::Args::new(target, manifest_path, verbosity, &other_args)
+        let target_dir = &crate_metadata.target_directory;
+        let target_dir_arg = format!("--target-dir={}", target_dir.to_string_lossy());
+        let features_arg = features.features_arg();
+        let mut other_args = vec!["--no-default-features", &target_dir_arg];
+        if let Some(features_arg) = &features_arg {
+            other_args.push(features_arg);
+        }
+        other_args.extend(lock.cargo_args());
+        if !debug {
+            other_args.push("--release");
+        }
+        // the compiler output is streamed if verbose, otherwise it is buffered so that it can be
+        // included in the error if the build fails, and the warnings are parsed from the json
+        // messages on stdout, which can only be captured on unix. The json messages requested
+        // by the caller are passed through instead.
+        let verbose = matches!(verbosity, Some(Verbosity::Verbose));
+        let passthrough = message_format == MessageFormat::Json;
+        let json_messages = cfg!(unix) && !verbose && !passthrough;
+        if json_messages || passthrough {
+            other_args.push("--message-format=json");
+        }
+        let args = xargo_lib::Args::new(target, manifest_path, xbuild_verbosity, &other_args)
             .map_err(|e| anyhow::anyhow!("{}", e))
             .context("Creating xargo args")?;
 
@@ -85,32 +253,58 @@ fn build_cargo_project(
             panic_immediate_abort: true,
         };
 
-        let exit_status = xargo_lib::build(args, "build", Some(config))
-            .map_err(|e| anyhow::anyhow!("{}", e))
-            .context("Building with xbuild")?;
+        let run = || {
+            xargo_lib::build(args, command, Some(config))
+                .map_err(|e| anyhow::anyhow!("{}", e))
+                .context(format!("Running {} with xbuild", command))
+        };
+        let (exit_status, messages, output) = if verbose {
+            (run(), CompilerMessages::default(), String::new())
+        } else if passthrough {
+            let (exit_status, output) = util::capture_stderr(run)?;
+            (exit_status, CompilerMessages::default(), output)
+        } else if json_messages {
+            let (captured, output) = util::capture_stderr(|| util::capture_stdout(run))?;
+            let (exit_status, stdout) = captured?;
+            (exit_status, parse_compiler_messages(&stdout), output)
+        } else {
+            let (exit_status, output) = util::capture_stderr(run)?;
+            (exit_status, CompilerMessages::default(), output)
+        };
+        let exit_status = exit_status?;
         if !exit_status.success() {
-            anyhow::bail!("xbuild failed with status {}", exit_status)
+            anyhow::bail!(
+                "xbuild {} failed with status {}\n{}{}",
+                command,
+                exit_status,
+                messages.errors.concat(),
+                output.trim_end()
+            )
+        }
+        if !matches!(verbosity, Some(Verbosity::Quiet)) {
+            eprint!("{}", output);
+            for warning in &messages.warnings {
+                eprint!("{}", warning.yellow());
+            }
         }
-
-        println!("exit_status {:?}", exit_status);
 
         Ok(())
     };
 
     if unstable_options.original_manifest {
-        println!(
-            "{} {}",
-            "warning:".yellow().bold(),
+        Reporter::new(verbosity).warning(
             "with 'original-manifest' enabled, the contract binary may not be of optimal size."
-                .bold()
+                .bold(),
         );
         xbuild(&crate_metadata.manifest_path)?;
     } else {
         Workspace::new(&crate_metadata.cargo_meta, &crate_metadata.root_package.id)?
             .with_root_package_manifest(|manifest| {
-                manifest
-                    .with_removed_crate_type("rlib")?
-                    .with_profile_release_defaults(Profile::default_contract_release())?;
+                manifest.with_removed_crate_type("rlib")?;
+                Ok(())
+            })?
+            .with_workspace_manifest(|manifest| {
+                manifest.with_profile_release_defaults(Profile::default_contract_release())?;
                 Ok(())
             })?
             .using_temp(xbuild)?;
@@ -122,6 +316,42 @@ fn build_cargo_project(
     Ok(())
 }
 
+/// The diagnostics of the compiler, parsed from the output of cargo with `--message-format=json`.
+#[derive(Debug, Default, PartialEq)]
+struct CompilerMessages {
+    /// The rendered warnings, which do not fail the build.
+    warnings: Vec<String>,
+    /// The rendered errors and notes, as well as any output which is not a json message.
+    errors: Vec<String>,
+}
+
+/// Separates the warnings from the other compiler messages in cargo's json output.
+///
+/// Messages other than those of the compiler, e.g. about the artifacts, are skipped.
+fn parse_compiler_messages(stdout: &str) -> CompilerMessages {
+    let mut messages = CompilerMessages::default();
+    for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
+        let json = match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(json) => json,
+            Err(_) => {
+                messages.errors.push(format!("{}\n", line));
+                continue;
+            }
+        };
+        if json["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &json["message"];
+        let rendered = message["rendered"].as_str().unwrap_or_default().to_string();
+        if message["level"] == "warning" {
+            messages.warnings.push(rendered)
+        } else {
+            messages.errors.push(rendered)
+        }
+    }
+    messages
+}
+
 /// Ensures the wasm memory import of a given module has the maximum number of pages.
 ///
 /// Iterates over the import section, finds the memory import entry if any and adjusts the maximum
@@ -199,15 +429,18 @@ fn post_process_wasm(crate_metadata: &CrateMetadata) -> Result<()> {
 ///
 /// This step depends on the `wasm-opt` tool being installed. If it is not the build will still
 /// succeed, and the user will be encouraged to install it for further optimizations.
-fn optimize_wasm(crate_metadata: &CrateMetadata) -> Result<()> {
+fn optimize_wasm(
+    crate_metadata: &CrateMetadata,
+    optimization_passes: OptimizationPasses,
+    reporter: Reporter,
+) -> Result<()> {
     // check `wasm-opt` installed
     if which::which("wasm-opt").is_err() {
-        println!(
-            "{}",
+        reporter.warning(
             "wasm-opt is not installed. Install this tool on your system in order to \n\
-             reduce the size of your contract's Wasm binary. \n\
-             See https://github.com/WebAssembly/binaryen#tools"
-                .bright_yellow()
+             reduce the size of your contract's Wasm binary, e.g. with \n\
+             `apt install binaryen` or `brew install binaryen`. \n\
+             See https://github.com/WebAssembly/binaryen#tools",
         );
         return Ok(());
     }
@@ -217,30 +450,57 @@ fn optimize_wasm(crate_metadata: &CrateMetadata) -> Result<()> {
 
     let output = Command::new("wasm-opt")
         .arg(crate_metadata.dest_wasm.as_os_str())
-        .arg("-O3") // execute -O3 optimization passes (spends potentially a lot of time optimizing)
+        // higher levels spend potentially a lot of time optimizing
+        .arg(format!("-O{}", optimization_passes))
         .arg("-o")
         .arg(optimized.as_os_str())
         .output()?;
 
     if !output.status.success() {
-        // Dump the output streams produced by wasm-opt into the stdout/stderr.
-        io::stdout().write_all(&output.stdout)?;
-        io::stderr().write_all(&output.stderr)?;
+        // both output streams of wasm-opt go to stderr, stdout only carries the result
+        for stream in &[&output.stdout, &output.stderr] {
+            let stream = String::from_utf8_lossy(stream);
+            if !stream.trim().is_empty() {
+                reporter.error(stream.trim_end());
+            }
+        }
         anyhow::bail!("wasm-opt optimization failed");
     }
 
-    let original_size = metadata(&crate_metadata.dest_wasm)?.len() as f64 / 1000.0;
-    let optimized_size = metadata(&optimized)?.len() as f64 / 1000.0;
-    println!(
-        " Original wasm size: {:.1}K, Optimized: {:.1}K",
-        original_size, optimized_size
-    );
-
     // overwrite existing destination wasm file with the optimised version
     std::fs::rename(&optimized, &crate_metadata.dest_wasm)?;
     Ok(())
 }
 
+/// The options of [`execute`]. The defaults build an optimized release wasm, as a plain
+/// `cargo contract build` does.
+#[derive(Clone, Default)]
+pub struct BuildOptions {
+    pub verbosity: Option<Verbosity>,
+    /// Build the contract with debug assertions and without optimizing it for size.
+    pub debug: bool,
+    pub optimization_passes: OptimizationPasses,
+    /// Run the post processing and optimization even if the wasm did not change, see
+    /// [`execute_with_metadata`].
+    pub force: bool,
+    /// The format the diagnostics of the compiler are rendered in, see [`build_cargo_project`].
+    pub message_format: MessageFormat,
+    /// The directory the artifacts are written to, instead of the cargo target directory.
+    pub target_dir: Option<PathBuf>,
+    /// Fail the build if the resulting wasm file, i.e. the one which would be uploaded to the
+    /// chain, is larger than that many bytes.
+    pub max_code_size: Option<u64>,
+    /// The command run after a successful build, defaulting to the `post-build` of the manifest,
+    /// see [`run_post_build`].
+    pub post_build: Option<String>,
+    /// The toolchain the contract is built with, as with `cargo +<toolchain>`, instead of the
+    /// default toolchain of the project.
+    pub toolchain: Option<String>,
+    pub lock: LockFlags,
+    pub unstable_options: UnstableFlags,
+    pub features: Features,
+}
+
 /// Executes build of the smart-contract which produces a wasm binary that is ready for deploying.
 ///
 /// It does so by invoking `cargo build` and then post processing the final binary.
@@ -248,62 +508,636 @@ fn optimize_wasm(crate_metadata: &CrateMetadata) -> Result<()> {
 /// # Note
 ///
 /// Collects the contract crate's metadata using the supplied manifest (`Cargo.toml`) path. Use
-/// [`execute_build_with_metadata`] if an instance is already available.
-pub(crate) fn execute(
-    manifest_path: &ManifestPath,
-    verbosity: Option<Verbosity>,
-    unstable_options: UnstableFlags,
-) -> Result<PathBuf> {
-    let crate_metadata = CrateMetadata::collect(manifest_path)?;
-    execute_with_metadata(&crate_metadata, verbosity, unstable_options)
+/// [`execute_with_metadata`] if an instance is already available.
+pub fn execute(manifest_path: &ManifestPath, options: BuildOptions) -> Result<BuildResult> {
+    let _toolchain = options
+        .toolchain
+        .as_deref()
+        .map(util::select_toolchain)
+        .transpose()?;
+    let mut crate_metadata = CrateMetadata::collect_locked(manifest_path, options.lock)?;
+    if let Some(target_dir) = &options.target_dir {
+        crate_metadata = crate_metadata.with_target_directory(target_dir)?;
+    }
+    let result = execute_with_metadata(
+        &crate_metadata,
+        options.verbosity,
+        options.debug,
+        options.optimization_passes,
+        options.force,
+        options.message_format,
+        options.lock,
+        options.unstable_options,
+        &options.features,
+    )?;
+    if let Some(max_code_size) = options.max_code_size {
+        check_code_size(&result, max_code_size)?;
+    }
+    let post_build = options
+        .post_build
+        .as_deref()
+        .or_else(|| crate_metadata.post_build.as_deref());
+    if let Some(command) = post_build {
+        Reporter::new(options.verbosity).info(format_args!(
+            " {} {}",
+            "Running post-build hook".bright_green().bold(),
+            command
+        ));
+        run_post_build(command, &crate_metadata, &result)?;
+    }
+    Ok(result)
+}
+
+/// Runs the `command` of the post-build hook in a shell, in the directory of the manifest.
+///
+/// The paths of the artifacts are passed as environment variables:
+///
+/// - `CONTRACT_WASM`: the resulting wasm file.
+/// - `CONTRACT_METADATA`: the `metadata.json` in the target directory, which only exists once
+///   `generate-metadata` was run.
+///
+/// The stdout of the command is forwarded to stderr, like the status messages of the build, so
+/// that stdout only carries the result, e.g. the json messages of `--message-format json`.
+///
+/// A failing command fails the build.
+fn run_post_build(
+    command: &str,
+    crate_metadata: &CrateMetadata,
+    result: &BuildResult,
+) -> Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    if let Some(dir) = crate_metadata.manifest_path.directory() {
+        shell.current_dir(dir);
+    }
+    let mut child = shell
+        .arg(command)
+        .env("CONTRACT_WASM", &result.dest_wasm)
+        .env(
+            "CONTRACT_METADATA",
+            crate_metadata.target_directory.join(METADATA_FILE),
+        )
+        .stdout(Stdio::piped())
+        .spawn()
+        .context(format!("Running post-build hook `{}`", command))?;
+    if let Some(mut stdout) = child.stdout.take() {
+        io::copy(&mut stdout, &mut io::stderr())?;
+    }
+    let status = child
+        .wait()
+        .context(format!("Running post-build hook `{}`", command))?;
+    if !status.success() {
+        anyhow::bail!("Post-build hook `{}` failed with {}", command, status)
+    }
+    Ok(())
+}
+
+/// Fails if the resulting wasm file of the build is larger than `max_code_size` bytes.
+fn check_code_size(result: &BuildResult, max_code_size: u64) -> Result<()> {
+    if result.optimized_size > max_code_size {
+        anyhow::bail!(
+            "The contract wasm {} is {} ({} bytes), which exceeds the maximum code size of {} \
+            ({} bytes)",
+            result.dest_wasm.display(),
+            util::format_size(result.optimized_size),
+            result.optimized_size,
+            util::format_size(max_code_size),
+            max_code_size
+        )
+    }
+    Ok(())
+}
+
+/// Returns the crate metadata with the wasm paths of a debug build.
+///
+/// The resulting wasm file is named `{package_name}-debug.wasm`, so that it does not overwrite
+/// the one of a release build.
+pub(crate) fn debug_crate_metadata(crate_metadata: &CrateMetadata) -> CrateMetadata {
+    let mut crate_metadata = crate_metadata.clone();
+    // {target_dir}/wasm32-unknown-unknown/debug/{package_name}.wasm
+    let original_wasm = crate_metadata
+        .target_directory
+        .join("wasm32-unknown-unknown")
+        .join("debug")
+        .join(format!("{}.wasm", crate_metadata.package_name));
+    crate_metadata.original_wasm = original_wasm;
+    // {target_dir}/{package_name}-debug.wasm
+    let dest_wasm = format!("{}-debug.wasm", crate_metadata.package_name);
+    crate_metadata.dest_wasm.set_file_name(dest_wasm);
+    crate_metadata
+}
+
+/// Returns the crate metadata of a build which skips the post processing and optimization.
+///
+/// The resulting wasm file is the raw one produced by cargo, so that artifacts derived from the
+/// wasm, e.g. the metadata bundle, are based on exactly what the compiler emitted.
+pub(crate) fn raw_crate_metadata(crate_metadata: &CrateMetadata) -> CrateMetadata {
+    let mut crate_metadata = crate_metadata.clone();
+    crate_metadata.dest_wasm = crate_metadata.original_wasm.clone();
+    crate_metadata
+}
+
+/// Returns the path the fingerprint of the last post processing and optimization is stored at,
+/// next to the resulting wasm file.
+pub(crate) fn fingerprint_path(crate_metadata: &CrateMetadata) -> PathBuf {
+    crate_metadata.dest_wasm.with_extension("fingerprint")
+}
+
+/// Computes the fingerprint of the inputs of the post processing and optimization, hex encoded.
+///
+/// It covers the wasm file produced by cargo, the build settings, whether `wasm-opt` is installed
+/// and the version of cargo-contract.
+fn post_processing_fingerprint(
+    crate_metadata: &CrateMetadata,
+    debug: bool,
+    optimization_passes: OptimizationPasses,
+) -> Result<String> {
+    let original_wasm = &crate_metadata.original_wasm;
+    let mut inputs =
+        fs::read(original_wasm).context(format!("Reading {}", original_wasm.display()))?;
+    inputs.extend_from_slice(env!("CARGO_PKG_VERSION").as_bytes());
+    inputs.extend_from_slice(
+        format!(
+            "debug={},passes={},wasm-opt={}",
+            debug,
+            optimization_passes,
+            which::which("wasm-opt").is_ok()
+        )
+        .as_bytes(),
+    );
+    Ok(code_hash(&inputs)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
 }
 
 /// Executes build of the smart-contract which produces a wasm binary that is ready for deploying.
 ///
 /// It does so by invoking `cargo build` and then post processing the final binary.
 ///
+/// Cargo only recompiles the contract if its sources changed. If the wasm produced by cargo and
+/// the build settings are the same as in the last build, the post processing and optimization
+/// are skipped as well and the existing wasm file is returned, unless `force` is set.
+///
+/// A `debug` build is compiled in the dev profile and skips the wasm optimization. Otherwise
+/// `wasm-opt` is run with the `optimization_passes`. With `OptimizationPasses::Zero` the build
+/// stops once cargo produced the raw wasm file, skipping both the post processing and the
+/// optimization, and its path is returned.
+///
 /// # Note
 ///
 /// Uses the supplied `CrateMetadata`. If an instance is not available use [`execute_build`]
-pub(crate) fn execute_with_metadata(
+pub fn execute_with_metadata(
     crate_metadata: &CrateMetadata,
     verbosity: Option<Verbosity>,
+    debug: bool,
+    optimization_passes: OptimizationPasses,
+    force: bool,
+    message_format: MessageFormat,
+    lock: LockFlags,
     unstable_options: UnstableFlags,
-) -> Result<PathBuf> {
-    println!(
-        " {} {}",
-        "[1/3]".bold(),
-        "Building cargo project".bright_green().bold()
-    );
-    build_cargo_project(&crate_metadata, verbosity, unstable_options)?;
-    println!(
-        " {} {}",
-        "[2/3]".bold(),
-        "Post processing wasm file".bright_green().bold()
-    );
+    features: &Features,
+) -> Result<BuildResult> {
+    let started = Instant::now();
+    let skip_optimization = optimization_passes.is_skipped();
+    let reporter = Reporter::new(verbosity);
+    let mut crate_metadata = if debug {
+        debug_crate_metadata(crate_metadata)
+    } else {
+        crate_metadata.clone()
+    };
+    if skip_optimization {
+        crate_metadata = raw_crate_metadata(&crate_metadata);
+    }
+    let steps = match (skip_optimization, debug) {
+        (true, _) => 1,
+        (false, true) => 2,
+        (false, false) => 3,
+    };
+    reporter.step(1, steps, "Building cargo project");
+    build_cargo_project(
+        &crate_metadata,
+        "build",
+        debug,
+        verbosity,
+        message_format,
+        lock,
+        unstable_options,
+        features,
+    )?;
+    if skip_optimization {
+        let raw_size = metadata(&crate_metadata.original_wasm)?.len();
+        reporter.info(format_args!(
+            " Raw wasm size: {}, post processing and optimization skipped",
+            util::format_size(raw_size)
+        ));
+        return Ok(BuildResult {
+            dest_wasm: crate_metadata.original_wasm.clone(),
+            original_size: raw_size,
+            optimized_size: raw_size,
+            build_duration_ms: started.elapsed().as_millis() as u64,
+            code_hash: wasm_file_hash(&crate_metadata.original_wasm)?,
+        });
+    }
+
+    let fingerprint_path = fingerprint_path(&crate_metadata);
+    let fingerprint = post_processing_fingerprint(&crate_metadata, debug, optimization_passes)?;
+    let up_to_date = !force
+        && crate_metadata.dest_wasm.exists()
+        && fs::read_to_string(&fingerprint_path).ok().as_deref() == Some(fingerprint.as_str());
+    if up_to_date {
+        let optimized_size = metadata(&crate_metadata.dest_wasm)?.len();
+        reporter.info(format_args!(
+            " Wasm unchanged since the last build, skipping post processing and optimization. \
+             Pass --force to redo them. Wasm size: {}",
+            util::format_size(optimized_size)
+        ));
+        return Ok(BuildResult {
+            dest_wasm: crate_metadata.dest_wasm.clone(),
+            original_size: metadata(&crate_metadata.original_wasm)?.len(),
+            optimized_size,
+            build_duration_ms: started.elapsed().as_millis() as u64,
+            code_hash: wasm_file_hash(&crate_metadata.dest_wasm)?,
+        });
+    }
+    // a post processing which fails half way must not be taken as up to date
+    if fingerprint_path.exists() {
+        fs::remove_file(&fingerprint_path)
+            .context(format!("Removing {}", fingerprint_path.display()))?;
+    }
+    let store_fingerprint = || {
+        fs::write(&fingerprint_path, &fingerprint)
+            .context(format!("Writing {}", fingerprint_path.display()))
+    };
+
+    reporter.step(2, steps, "Post processing wasm file");
     post_process_wasm(&crate_metadata)?;
-    println!(
-        " {} {}",
-        "[3/3]".bold(),
-        "Optimizing wasm file".bright_green().bold()
-    );
-    optimize_wasm(&crate_metadata)?;
-    Ok(crate_metadata.dest_wasm.clone())
+    let post_processed_size = metadata(&crate_metadata.dest_wasm)?.len();
+    let original_size = metadata(&crate_metadata.original_wasm)?.len();
+    if debug {
+        reporter.info(format_args!(
+            " Wasm size: {}",
+            util::format_size(post_processed_size)
+        ));
+        store_fingerprint()?;
+        return Ok(BuildResult {
+            dest_wasm: crate_metadata.dest_wasm.clone(),
+            original_size,
+            optimized_size: post_processed_size,
+            build_duration_ms: started.elapsed().as_millis() as u64,
+            code_hash: wasm_file_hash(&crate_metadata.dest_wasm)?,
+        });
+    }
+    reporter.step(3, steps, "Optimizing wasm file");
+    optimize_wasm(&crate_metadata, optimization_passes, reporter)?;
+
+    let optimized_size = metadata(&crate_metadata.dest_wasm)?.len();
+    if reporter.is_verbose() {
+        reporter.verbose(format_args!(
+            " Wasm size after cargo build: {}, post processing: {}, optimization: {}",
+            util::format_size(original_size),
+            util::format_size(post_processed_size),
+            util::format_size(optimized_size)
+        ));
+    } else {
+        reporter.info(format_args!(
+            " Original wasm size: {}, Optimized: {}",
+            util::format_size(original_size),
+            util::format_size(optimized_size)
+        ));
+    }
+    store_fingerprint()?;
+
+    Ok(BuildResult {
+        dest_wasm: crate_metadata.dest_wasm.clone(),
+        original_size,
+        optimized_size,
+        build_duration_ms: started.elapsed().as_millis() as u64,
+        code_hash: wasm_file_hash(&crate_metadata.dest_wasm)?,
+    })
 }
 
-#[cfg(feature = "test-ci-only")]
 #[cfg(test)]
 mod tests {
-    use crate::{cmd, util::tests::with_tmp_dir, workspace::ManifestPath, UnstableFlags};
+    #[cfg(feature = "test-ci-only")]
+    use super::BuildOptions;
+    use super::OptimizationPasses;
+    #[cfg(feature = "test-ci-only")]
+    use crate::{
+        util::tests::{new_project, with_tmp_dir},
+        Features, LockFlags,
+    };
+    #[cfg(feature = "test-ci-only")]
+    use std::{fs, process::Command};
 
+    #[cfg(feature = "test-ci-only")]
     #[test]
     fn build_template() {
         with_tmp_dir(|path| {
-            cmd::new::execute("new_project", Some(path)).expect("new project creation failed");
-            let manifest_path =
-                ManifestPath::new(&path.join("new_project").join("Cargo.toml")).unwrap();
-            super::execute(&manifest_path, None, UnstableFlags::default()).expect("build failed");
+            let manifest_path = new_project(path);
+            let result = super::execute(&manifest_path, Default::default()).expect("build failed");
+
+            let wasm = fs::read(&result.dest_wasm)?;
+            let expected_hash = super::code_hash(&wasm)
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>();
+            assert_eq!(result.code_hash, format!("0x{}", expected_hash));
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn build_template_debug() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let result = super::execute(
+                &manifest_path,
+                BuildOptions {
+                    debug: true,
+                    ..Default::default()
+                },
+            )
+            .expect("build failed");
+            assert_eq!(
+                result.dest_wasm.file_name().unwrap().to_str(),
+                Some("new_project-debug.wasm")
+            );
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn build_template_locked() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let status = Command::new("cargo")
+                .arg("generate-lockfile")
+                .arg(manifest_path.cargo_arg())
+                .status()?;
+            assert!(status.success());
+            let lock_file = path.join("new_project").join("Cargo.lock");
+            let locked = fs::read_to_string(&lock_file)?;
+
+            super::execute(
+                &manifest_path,
+                BuildOptions {
+                    lock: LockFlags {
+                        locked: true,
+                        offline: false,
+                    },
+                    ..Default::default()
+                },
+            )
+            .expect("build with --locked failed");
+            assert_eq!(fs::read_to_string(&lock_file)?, locked);
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn build_template_to_target_dir() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let target_dir = path.join("shared").join("out");
+            let result = super::execute(
+                &manifest_path,
+                BuildOptions {
+                    target_dir: Some(target_dir.clone()),
+                    ..Default::default()
+                },
+            )
+            .expect("build failed");
+            assert_eq!(result.dest_wasm, target_dir.join("new_project.wasm"));
             Ok(())
         })
     }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn build_template_with_features() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let project = path.join("new_project");
+            let manifest = fs::read_to_string(project.join("Cargo.toml"))?;
+            fs::write(
+                project.join("Cargo.toml"),
+                manifest.replace("[features]\n", "[features]\ndummy = []\n"),
+            )?;
+            let mut lib = fs::read_to_string(project.join("lib.rs"))?;
+            lib.push_str(
+                "\n#[cfg(not(feature = \"dummy\"))]\ncompile_error!(\"dummy is not enabled\");\n",
+            );
+            fs::write(project.join("lib.rs"), lib)?;
+
+            let without_feature = super::execute(&manifest_path, Default::default());
+            assert!(without_feature.is_err());
+
+            let features = Features {
+                features: vec!["dummy".to_string()],
+                no_default_features: false,
+            };
+            super::execute(
+                &manifest_path,
+                BuildOptions {
+                    features,
+                    ..Default::default()
+                },
+            )
+            .expect("build with the dummy feature failed");
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn build_contract_in_workspace_member() {
+        with_tmp_dir(|path| {
+            let workspace_manifest = "[workspace]\nmembers = [\"new_project\"]\n";
+            fs::write(path.join("Cargo.toml"), workspace_manifest)?;
+            let manifest_path = new_project(path);
+            let result = super::execute(&manifest_path, Default::default()).expect("build failed");
+
+            // artifacts are written to the shared target directory of the workspace
+            let target_dir = path.canonicalize()?.join("target");
+            assert_eq!(result.dest_wasm, target_dir.join("new_project.wasm"));
+            assert!(result.dest_wasm.exists());
+            assert_eq!(
+                fs::read_to_string(path.join("Cargo.toml"))?,
+                workspace_manifest
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn compiler_warnings_are_separated_from_errors() {
+        let stdout = concat!(
+            r#"{"reason":"compiler-message","message":{"level":"warning","#,
+            r#""rendered":"warning: use of deprecated function `old`\n"}}"#,
+            "\n",
+            r#"{"reason":"compiler-artifact","target":{"name":"contract"}}"#,
+            "\n",
+            r#"{"reason":"compiler-message","message":{"level":"error","#,
+            r#""rendered":"error[E0425]: cannot find value `x`\n"}}"#,
+            "\n\nnot json\n",
+        );
+        let messages = super::parse_compiler_messages(stdout);
+        assert_eq!(
+            messages.warnings,
+            vec!["warning: use of deprecated function `old`\n"]
+        );
+        assert_eq!(
+            messages.errors,
+            vec!["error[E0425]: cannot find value `x`\n", "not json\n"]
+        );
+    }
+
+    #[test]
+    fn optimization_passes_are_parsed() {
+        assert_eq!(
+            "0".parse::<OptimizationPasses>().unwrap(),
+            OptimizationPasses::Zero
+        );
+        assert_eq!(
+            "z".parse::<OptimizationPasses>().unwrap(),
+            OptimizationPasses::Z
+        );
+        assert_eq!(OptimizationPasses::default().to_string(), "3");
+        assert!(OptimizationPasses::Zero.is_skipped());
+        assert!(!OptimizationPasses::S.is_skipped());
+        assert!("5".parse::<OptimizationPasses>().is_err());
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn build_template_skip_optimization() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let result = super::execute(
+                &manifest_path,
+                BuildOptions {
+                    optimization_passes: OptimizationPasses::Zero,
+                    ..Default::default()
+                },
+            )
+            .expect("build failed");
+
+            let target_dir = path.canonicalize()?.join("new_project").join("target");
+            let raw_wasm = target_dir
+                .join("wasm32-unknown-unknown")
+                .join("release")
+                .join("new_project.wasm");
+            assert_eq!(result.dest_wasm, raw_wasm);
+            assert_eq!(result.optimized_size, result.original_size);
+            // the post processed wasm was not written
+            assert!(!target_dir.join("new_project.wasm").exists());
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn build_template_exceeding_max_code_size() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let err = super::execute(
+                &manifest_path,
+                BuildOptions {
+                    max_code_size: Some(1),
+                    ..Default::default()
+                },
+            )
+            .expect_err("build should exceed the maximum code size");
+            assert!(err
+                .to_string()
+                .contains("which exceeds the maximum code size of 1B (1 bytes)"));
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn build_template_skips_post_processing_of_unchanged_wasm() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let build = |force| {
+                super::execute(
+                    &manifest_path,
+                    BuildOptions {
+                        force,
+                        ..Default::default()
+                    },
+                )
+            };
+
+            let first = build(false).expect("build failed");
+            let modified = || fs::metadata(&first.dest_wasm).and_then(|meta| meta.modified());
+            let built_at = modified()?;
+
+            let second = build(false).expect("build failed");
+            assert_eq!(modified()?, built_at, "the wasm should not be rewritten");
+            assert_eq!(second.code_hash, first.code_hash);
+
+            let forced = build(true).expect("build failed");
+            assert_eq!(forced.code_hash, first.code_hash);
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[cfg(unix)]
+    #[test]
+    fn build_template_runs_post_build_hook() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let build = |post_build: &str| {
+                super::execute(
+                    &manifest_path,
+                    BuildOptions {
+                        post_build: Some(post_build.to_string()),
+                        ..Default::default()
+                    },
+                )
+            };
+
+            let result = build("cp \"$CONTRACT_WASM\" hooked.wasm").expect("build failed");
+            let hooked = path.join("new_project").join("hooked.wasm");
+            assert_eq!(fs::read(hooked)?, fs::read(&result.dest_wasm)?);
+
+            let err = build("exit 3").expect_err("a failing hook should fail the build");
+            assert!(err
+                .to_string()
+                .starts_with("Post-build hook `exit 3` failed"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn code_size_within_limit_is_accepted() {
+        let result = super::BuildResult {
+            dest_wasm: "flipper.wasm".into(),
+            original_size: 4096,
+            optimized_size: 2048,
+            build_duration_ms: 0,
+            code_hash: String::new(),
+        };
+        assert!(super::check_code_size(&result, 2048).is_ok());
+        let err = super::check_code_size(&result, 2047).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The contract wasm flipper.wasm is 2.0KiB (2048 bytes), which exceeds the maximum \
+            code size of 2.0KiB (2047 bytes)"
+        );
+    }
 }
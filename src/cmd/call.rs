@@ -14,20 +14,73 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::path::Path;
+
 use anyhow::Result;
+use codec::Decode;
+use colored::Colorize;
+use sp_core::crypto::AccountId32;
 use subxt::{
     balances::Balances, contracts::*, contracts_gateway::*, runtime_gateway::*, system::System,
-    ClientBuilder, ContractsTemplateRuntime,
+    ContractsTemplateRuntime, Signer,
 };
 
+use super::{
+    events::{self, ExtrinsicInclusion},
+    retry, rpc, Transcoder,
+};
 use crate::{ExtrinsicOpts, HexData};
 
+/// The result of executing a contract via the runtime gateway.
+#[derive(Debug)]
+pub struct CallResult {
+    pub execution_stamp: ExecutionStampEmittable,
+    pub inclusion: ExtrinsicInclusion,
+}
+
+/// Whether a call of a regular contract is submitted as an extrinsic, or only dry run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CallMode {
+    /// Dry run the messages the contract metadata marks as read-only, submit all others.
+    Auto,
+    /// Dry run the call via RPC, without submitting a transaction.
+    DryRun,
+    /// Submit the call as an extrinsic.
+    Execute,
+}
+
+impl Default for CallMode {
+    fn default() -> Self {
+        CallMode::Auto
+    }
+}
+
+/// The result of calling a regular contract.
+#[derive(Debug)]
+pub struct ContractCallResult {
+    /// The return value of the message, decoded if the contract metadata is supplied.
+    pub output: String,
+    /// Where the extrinsic was included, `None` if the call was only dry run.
+    pub inclusion: Option<ExtrinsicInclusion>,
+}
+
+/// Fails if --offline is passed, since calls are not signed offline and would be submitted
+/// instead.
+fn reject_offline(extrinsic_opts: &ExtrinsicOpts) -> Result<()> {
+    if extrinsic_opts.offline.offline {
+        anyhow::bail!("A contract call cannot be signed with --offline")
+    }
+    Ok(())
+}
+
 /// Instantiate a contract stored at the supplied code hash.
-/// Returns the account id of the instantiated contract if successful.
+/// Returns the execution stamp of the phase if successful, together with where the extrinsic was
+/// included.
 ///
 /// Creates an extrinsic with the `Contracts::instantiate` Call, submits via RPC, then waits for
-/// the `ContractsEvent::Instantiated` event.
-pub(crate) fn execute_call<'a>(
+/// the `ContractsEvent::Instantiated` event. Connecting is retried on network errors, the
+/// extrinsic is submitted only once.
+pub fn execute_call<'a>(
     extrinsic_opts: &ExtrinsicOpts,
     requester: <ContractsTemplateRuntime as System>::AccountId,
     target_dest: <ContractsTemplateRuntime as System>::AccountId,
@@ -36,27 +89,39 @@ pub(crate) fn execute_call<'a>(
     value: <ContractsTemplateRuntime as Balances>::Balance,
     gas_limit: u64,
     data: HexData,
-) -> Result<subxt::runtime_gateway::ExecutionStampEmittable> {
+) -> Result<CallResult> {
+    reject_offline(extrinsic_opts)?;
+    let signer = extrinsic_opts.signer()?;
+    extrinsic_opts.reporter()?.verbose(format_args!(
+        "Call: RuntimeGateway::multistep_call {{ requester: {}, target_dest: {}, phase: {}, \
+         code: {} bytes, value: {}, gas_limit: {}, input_data: 0x{} }}",
+        requester,
+        target_dest,
+        phase,
+        code.len(),
+        value,
+        gas_limit,
+        hex::encode(&data.0)
+    ));
     async_std::task::block_on(async move {
-        let cli = ClientBuilder::<ContractsTemplateRuntime>::new()
-            .set_url(&extrinsic_opts.url.to_string())
-            .build()
-            .await?;
+        let cli = retry::with_retries(extrinsic_opts, || rpc::client(extrinsic_opts)).await?;
 
-        let signer = extrinsic_opts.signer()?;
-
-        let events = cli
-            .multistep_call_and_watch(
-                &signer,
-                requester,
-                target_dest,
-                phase, // phase = Execution
-                &code,
-                value,     // value
-                gas_limit, // gas_limit
-                &data.0,   // input data
-            )
-            .await?;
+        let spinner = extrinsic_opts
+            .reporter()?
+            .spinner("Waiting for the extrinsic to be included");
+        let submission = cli.multistep_call_and_watch(
+            &signer,
+            requester,
+            target_dest,
+            phase, // phase = Execution
+            &code,
+            value,     // value
+            gas_limit, // gas_limit
+            &data.0,   // input data
+        );
+        let result = retry::with_timeout(extrinsic_opts, submission).await?;
+        drop(spinner);
+        let events = events::decode_dispatch_error(extrinsic_opts, result).await?;
         let execution_stamp = match phase {
             0 => {
                 events
@@ -84,17 +149,22 @@ pub(crate) fn execute_call<'a>(
             }
             _ => Default::default(), // Phases should only be 0,1,2 at this point.
         };
+        let inclusion = events::inclusion(extrinsic_opts, &events).await?;
 
-        Ok(execution_stamp)
+        Ok(CallResult {
+            execution_stamp,
+            inclusion,
+        })
     })
 }
 
-/// Instantiate a contract stored at the supplied code hash.
-/// Returns the account id of the instantiated contract if successful.
+/// Executes a contract via the contracts gateway.
 ///
-/// Creates an extrinsic with the `Contracts::instantiate` Call, submits via RPC, then waits for
-/// the `ContractsEvent::Instantiated` event.
-pub(crate) fn execute_contract_call<'a>(
+/// Creates an extrinsic with the `ContractsGateway::multistep_call` Call, submits via RPC, then
+/// waits for the execution success event of the contracts gateway. A summary of the
+/// call is printed before submitting it, unless `--quiet` is passed. Connecting is retried on
+/// network errors, the extrinsic is submitted only once.
+pub fn execute_contract_call<'a>(
     extrinsic_opts: &ExtrinsicOpts,
     requester: <ContractsTemplateRuntime as System>::AccountId,
     target_dest: <ContractsTemplateRuntime as System>::AccountId,
@@ -103,28 +173,39 @@ pub(crate) fn execute_contract_call<'a>(
     value: <ContractsTemplateRuntime as Balances>::Balance,
     gas_limit: u64,
     data: HexData,
-    // ) -> Result<&'a [u8]> {
 ) -> Result<()> {
+    reject_offline(extrinsic_opts)?;
+    extrinsic_opts.reporter()?.info(format_args!(
+        "{} target: {}, requester: {}, phase: {}, code: {} bytes, value: {}, gas limit: {}",
+        "Calling contracts gateway".bright_blue().bold(),
+        target_dest,
+        requester,
+        phase,
+        code.len(),
+        value,
+        gas_limit
+    ));
     async_std::task::block_on(async move {
-        let cli = ClientBuilder::<ContractsTemplateRuntime>::new()
-            .set_url(&extrinsic_opts.url.to_string())
-            .build()
-            .await?;
+        let cli = retry::with_retries(extrinsic_opts, || rpc::client(extrinsic_opts)).await?;
 
         let signer = extrinsic_opts.signer()?;
 
-        let events = cli
-            .gateway_contract_exec_and_watch(
-                &signer,
-                requester,
-                target_dest,
-                phase, // phase = Execution
-                &code,
-                value,     // value
-                gas_limit, // gas_limit
-                &data.0,   // input data
-            )
-            .await?;
+        let spinner = extrinsic_opts
+            .reporter()?
+            .spinner("Waiting for the extrinsic to be included");
+        let submission = cli.gateway_contract_exec_and_watch(
+            &signer,
+            requester,
+            target_dest,
+            phase, // phase = Execution
+            &code,
+            value,     // value
+            gas_limit, // gas_limit
+            &data.0,   // input data
+        );
+        let result = retry::with_timeout(extrinsic_opts, submission).await?;
+        drop(spinner);
+        let events = events::decode_dispatch_error(extrinsic_opts, result).await?;
         let execution_success_event =
             events
                 .contracts_gateway_execution_success()?
@@ -132,13 +213,6 @@ pub(crate) fn execute_contract_call<'a>(
                     "Failed to find a MultistepExecutePhaseSuccess event"
                 ))?;
 
-        //  /Users/macio/projects/substrate.dev/cargo-contract/target/debug/cargo-contract contract call-runtime-gateway --data 00 --suri //Alice --target //Bob --requester //Charlie
-
-        // let instantiated = events
-        //     .instantiated()?
-        //     .ok_or(anyhow::anyhow!("Failed to find Instantiated event"))?;
-        println!("{:?}", events);
-
         log::info!(
             "multistep_call_and_watch execution_success_event execution_stamp {:?}",
             execution_success_event.execution_stamp
@@ -152,40 +226,113 @@ pub(crate) fn execute_contract_call<'a>(
 ///
 /// Creates an extrinsic with the `Contracts::instantiate` Call, submits via RPC, then waits for
 /// the `ContractsEvent::Instantiated` event.
-pub(crate) fn call_regular_contract<'a>(
+///
+/// If the contract metadata is supplied, the return value and the events emitted by the contract
+/// are decoded, otherwise the raw return value is shown.
+///
+/// Depending on the `mode`, the call is only dry run via the `contracts_call` RPC method instead,
+/// which returns the result without changing the state of the contract or paying fees. By default
+/// this is done for the messages which the metadata marks as read-only. Calls whose selector is not
+/// found in the metadata are executed.
+///
+/// Connecting and dry runs are retried on network errors, the extrinsic is submitted only once.
+pub fn call_regular_contract<'a>(
     extrinsic_opts: &ExtrinsicOpts,
     contract_dest: <ContractsTemplateRuntime as System>::AccountId,
     value: <ContractsTemplateRuntime as Balances>::Balance,
     gas_limit: u64,
     data: HexData,
-) -> Result<Vec<u8>> {
+    metadata_path: Option<&Path>,
+    mode: CallMode,
+) -> Result<ContractCallResult> {
+    reject_offline(extrinsic_opts)?;
+    let transcoder = metadata_path.map(Transcoder::load).transpose()?;
+    let dry_run = match (mode, &transcoder) {
+        (CallMode::DryRun, _) => true,
+        (CallMode::Auto, Some(transcoder)) => transcoder
+            .is_mutating(&data.0)
+            .map(|mutating| !mutating)
+            .unwrap_or(false),
+        (CallMode::Auto, None) | (CallMode::Execute, _) => false,
+    };
     async_std::task::block_on(async move {
-        let cli = ClientBuilder::<ContractsTemplateRuntime>::new()
-            .set_url(&extrinsic_opts.url.to_string())
-            .build()
-            .await?;
-
         let signer = extrinsic_opts.signer()?;
-        let events = cli
-            .call_and_watch(
-                &signer,
-                &contract_dest,
-                value,     // value
-                gas_limit, // gas_limit
-                &data.0,   // input data
-            )
+        if dry_run {
+            let (signer, contract_dest, data) = (&signer, &contract_dest, &data);
+            // a dry run submits nothing, so it is retried as a whole
+            let result = retry::with_retries(extrinsic_opts, || async move {
+                let client = rpc::connect_opts(extrinsic_opts).await?;
+                rpc::dry_run_call(
+                    &client,
+                    signer.account_id().clone(),
+                    contract_dest.clone(),
+                    value,
+                    gas_limit,
+                    &data.0,
+                )
+                .await
+            })
             .await?;
-        // ContractExecution
-        println!("regular contract call result: {:?}", events);
+            extrinsic_opts
+                .reporter()?
+                .verbose(format_args!("Gas consumed: {}", result.gas_consumed));
+            let output = match &transcoder {
+                Some(transcoder) => transcoder.decode_return(&data.0, &result.data)?,
+                None => format!("{:?}", result.data),
+            };
+            if result.reverted {
+                anyhow::bail!("The contract reverted the call: {}", output)
+            }
+            return Ok(ContractCallResult {
+                output,
+                inclusion: None,
+            });
+        }
+
+        let cli = retry::with_retries(extrinsic_opts, || rpc::client(extrinsic_opts)).await?;
+        let spinner = extrinsic_opts
+            .reporter()?
+            .spinner("Waiting for the extrinsic to be included");
+        let submission = cli.call_and_watch(
+            &signer,
+            &contract_dest,
+            value,     // value
+            gas_limit, // gas_limit
+            &data.0,   // input data
+        );
+        let result = retry::with_timeout(extrinsic_opts, submission).await?;
+        drop(spinner);
+        let events = events::decode_dispatch_error(extrinsic_opts, result).await?;
         let contract_execution_event = events
             .contract_execution()?
             .ok_or(anyhow::anyhow!("Failed to find ContractExecutionEvent"))?;
-        println!(
-            "regular contract call result: {:?}",
-            contract_execution_event
-        );
+        let inclusion = events::inclusion(extrinsic_opts, &events).await?;
 
-        Ok(contract_execution_event.data)
+        let transcoder = match transcoder {
+            Some(transcoder) => transcoder,
+            None => {
+                return Ok(ContractCallResult {
+                    output: format!("{:?}", contract_execution_event.data),
+                    inclusion: Some(inclusion),
+                })
+            }
+        };
+        let reporter = extrinsic_opts.reporter()?;
+        for event in &events.events {
+            if event.module == "Contracts" && event.variant == "ContractEmitted" {
+                let (_contract, event_data) =
+                    <(AccountId32, Vec<u8>)>::decode(&mut &event.data[..])?;
+                reporter.info(format_args!(
+                    "{} {}",
+                    "Event".bright_blue().bold(),
+                    transcoder.decode_event(&event_data)?
+                ));
+            }
+        }
+        Ok(ContractCallResult {
+            output: transcoder.decode_return(&data.0, &contract_execution_event.data)?,
+            inclusion: Some(inclusion),
+        })
     })
 }
 
@@ -193,8 +340,14 @@ pub(crate) fn call_regular_contract<'a>(
 mod tests {
     use std::{fs, io::Write};
 
-    use crate::{cmd::deploy::execute_deploy, util::tests::with_tmp_dir, ExtrinsicOpts, HexData};
+    use crate::{
+        cmd::deploy::load_contract_code,
+        util::tests::{extrinsic_opts, with_tmp_dir},
+        HexData,
+    };
     use assert_matches::assert_matches;
+    use sp_core::crypto::AccountId32;
+    use subxt::Signer;
 
     const CONTRACT: &str = r#"
 (module
@@ -211,15 +364,11 @@ mod tests {
             let wasm_path = path.join("test.wasm");
             let mut file = fs::File::create(&wasm_path).unwrap();
             let _ = file.write_all(&wasm);
-            let code = load_contract_code(contract_wasm_path)?;
+            let code = load_contract_code(&wasm_path)?;
 
-            let url = url::Url::parse("ws://localhost:9944").unwrap();
-            let extrinsic_opts = ExtrinsicOpts {
-                url,
-                suri: "//Alice".into(),
-                password: None,
-            };
-            let code = load_contract_code(contract_wasm_path)?;
+            let extrinsic_opts = extrinsic_opts("ws://localhost:9944", &[]);
+            let requester = extrinsic_opts.signer()?.account_id().clone();
+            let target_dest = AccountId32::from([1; 32]);
 
             let gas_limit = 500_000_000;
             let result = super::execute_call(
@@ -227,7 +376,7 @@ mod tests {
                 requester,
                 target_dest,
                 0 as u8,
-                code,
+                &code,
                 0, // value
                 gas_limit,
                 HexData::default(), // input
@@ -0,0 +1,73 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+    cmd::build, crate_metadata::CrateMetadata, reporter::Reporter, workspace::ManifestPath,
+    Features, UnstableFlags, Verbosity,
+};
+use anyhow::Result;
+
+/// Checks the smart-contract for errors without producing a wasm binary.
+///
+/// It does so by invoking `cargo check` with the same target and flags as a contract build, but
+/// skips post processing and optimizing the wasm file as well as generating the metadata.
+pub fn execute(
+    manifest_path: &ManifestPath,
+    verbosity: Option<Verbosity>,
+    unstable_options: UnstableFlags,
+    features: &Features,
+) -> Result<String> {
+    let crate_metadata = CrateMetadata::collect(manifest_path)?;
+
+    Reporter::new(verbosity).step(1, 1, "Checking cargo project");
+    build::build_cargo_project(
+        &crate_metadata,
+        "check",
+        false,
+        verbosity,
+        Default::default(),
+        Default::default(),
+        unstable_options,
+        features,
+    )?;
+
+    Ok("No errors.".to_string())
+}
+
+#[cfg(feature = "test-ci-only")]
+#[cfg(test)]
+mod tests {
+    use crate::{
+        util::tests::{new_project, with_tmp_dir},
+        Features, UnstableFlags,
+    };
+
+    #[test]
+    fn check_template() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let result = super::execute(
+                &manifest_path,
+                None,
+                UnstableFlags::default(),
+                &Features::default(),
+            )
+            .expect("check failed");
+            assert_eq!(result, "No errors.");
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::process::Command;
+
+use anyhow::Result;
+use colored::Colorize;
+use rustc_version::Channel;
+use serde::Serialize;
+
+/// The target the contracts are compiled for.
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// A tool or toolchain component `cargo contract build` depends upon.
+#[derive(Debug, Serialize)]
+pub struct Prerequisite {
+    pub name: String,
+    /// Whether the prerequisite is installed, `None` if that could not be determined.
+    pub installed: Option<bool>,
+    /// Whether the build fails without the prerequisite, otherwise it is only recommended.
+    pub required: bool,
+    /// How to install the prerequisite.
+    pub fix: String,
+}
+
+impl Prerequisite {
+    fn is_missing(&self) -> bool {
+        self.installed == Some(false)
+    }
+}
+
+/// The state of the prerequisites of building a contract.
+#[derive(Debug, Serialize)]
+pub struct CheckEnvResult {
+    pub prerequisites: Vec<Prerequisite>,
+}
+
+impl CheckEnvResult {
+    /// Returns whether all the required prerequisites are installed, as far as can be told.
+    pub fn is_ready(&self) -> bool {
+        !self
+            .prerequisites
+            .iter()
+            .any(|prerequisite| prerequisite.required && prerequisite.is_missing())
+    }
+
+    /// Lists the missing required prerequisites, each with the command to install it.
+    pub fn missing_report(&self) -> String {
+        let mut report = "Missing prerequisites to build contracts:".to_string();
+        for prerequisite in &self.prerequisites {
+            if prerequisite.required && prerequisite.is_missing() {
+                report.push_str(&format!(
+                    "\n  - {}: {}",
+                    prerequisite.name, prerequisite.fix
+                ));
+            }
+        }
+        report
+    }
+}
+
+impl std::fmt::Display for CheckEnvResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = self.prerequisites.iter().map(|prerequisite| {
+            let state = match (prerequisite.installed, prerequisite.required) {
+                (Some(true), _) => "installed".green(),
+                (Some(false), true) => "missing".red(),
+                (Some(false), false) => "missing (recommended)".yellow(),
+                (None, _) => "unknown".yellow(),
+            };
+            let mut line = format!("{}: {}", prerequisite.name.bold(), state);
+            if prerequisite.installed != Some(true) {
+                line.push_str(&format!(", {}", prerequisite.fix));
+            }
+            line
+        });
+        write!(f, "{}", lines.collect::<Vec<_>>().join("\n\t"))
+    }
+}
+
+/// Checks the prerequisites of building a contract, without building anything.
+///
+/// The rust target and components are looked up with `rustup`, for the toolchain which is active
+/// in the current directory. Without `rustup` their state is unknown.
+pub fn execute() -> Result<CheckEnvResult> {
+    let nightly = rustc_version::version_meta()
+        .map(|meta| matches!(meta.channel, Channel::Nightly | Channel::Dev))
+        .ok();
+    let targets = rustup_list("target");
+    let components = rustup_list("component");
+    let prerequisites = vec![
+        Prerequisite {
+            name: "rustup".into(),
+            installed: Some(which::which("rustup").is_ok()),
+            required: false,
+            fix: "install it from https://rustup.rs".into(),
+        },
+        Prerequisite {
+            name: "nightly toolchain".into(),
+            installed: nightly,
+            required: true,
+            fix: "run `rustup toolchain install nightly` and build with `cargo +nightly contract \
+                  build`"
+                .into(),
+        },
+        Prerequisite {
+            name: format!("{} target", WASM_TARGET),
+            installed: targets.map(|targets| is_listed(&targets, WASM_TARGET)),
+            required: true,
+            fix: format!("run `rustup target add {}`", WASM_TARGET),
+        },
+        Prerequisite {
+            name: "rust-src component".into(),
+            installed: components.map(|components| is_listed(&components, "rust-src")),
+            required: true,
+            fix: "run `rustup component add rust-src`".into(),
+        },
+        Prerequisite {
+            name: "wasm-opt".into(),
+            installed: Some(which::which("wasm-opt").is_ok()),
+            required: false,
+            fix: "install binaryen, e.g. `apt install binaryen` or `brew install binaryen`, see \
+                  https://github.com/WebAssembly/binaryen#tools"
+                .into(),
+        },
+    ];
+    Ok(CheckEnvResult { prerequisites })
+}
+
+/// Fails with the commands to run if a required prerequisite of the build is missing.
+///
+/// A missing `wasm-opt` is reported by the build itself, since only the optimization needs it.
+pub(crate) fn preflight() -> Result<()> {
+    let result = execute()?;
+    if !result.is_ready() {
+        anyhow::bail!("{}", result.missing_report())
+    }
+    Ok(())
+}
+
+/// Returns the output of `rustup <kind> list --installed`, `None` if `rustup` is not available.
+fn rustup_list(kind: &str) -> Option<String> {
+    Command::new("rustup")
+        .args(&[kind, "list", "--installed"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns whether `name` is one of the lines listed by `rustup`.
+fn is_listed(list: &str, name: &str) -> bool {
+    list.lines().any(|line| line.trim() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_listed, CheckEnvResult, Prerequisite};
+
+    fn prerequisite(name: &str, installed: Option<bool>, required: bool) -> Prerequisite {
+        Prerequisite {
+            name: name.into(),
+            installed,
+            required,
+            fix: format!("run `install {}`", name),
+        }
+    }
+
+    #[test]
+    fn installed_targets_and_components_are_listed() {
+        let targets = "wasm32-unknown-unknown\nx86_64-unknown-linux-gnu\n";
+        assert!(is_listed(targets, "wasm32-unknown-unknown"));
+        let components = "cargo-x86_64-unknown-linux-gnu\nrust-src\nrustc-x86_64-unknown-linux-gnu";
+        assert!(is_listed(components, "rust-src"));
+        assert!(!is_listed("rust-std-wasm32-unknown-unknown", "rust-src"));
+    }
+
+    #[test]
+    fn missing_required_prerequisites_are_reported_with_fix() {
+        let result = CheckEnvResult {
+            prerequisites: vec![
+                prerequisite("target", Some(false), true),
+                prerequisite("wasm-opt", Some(false), false),
+                prerequisite("rust-src", None, true),
+            ],
+        };
+        assert!(!result.is_ready());
+        assert_eq!(
+            result.missing_report(),
+            "Missing prerequisites to build contracts:\n  - target: run `install target`"
+        );
+    }
+
+    #[test]
+    fn missing_recommended_or_unknown_prerequisites_do_not_fail() {
+        let result = CheckEnvResult {
+            prerequisites: vec![
+                prerequisite("wasm-opt", Some(false), false),
+                prerequisite("rust-src", None, true),
+            ],
+        };
+        assert!(result.is_ready());
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::{
+    cmd::{
+        build,
+        metadata::{abi_file_name, fingerprint, METADATA_FILE},
+    },
+    crate_metadata::CrateMetadata,
+    reporter::Reporter,
+    util,
+    workspace::ManifestPath,
+    Verbosity,
+};
+
+/// Returns the paths of the artifacts written by `build` and `generate-metadata`.
+fn artifact_paths(crate_metadata: &CrateMetadata) -> Vec<PathBuf> {
+    let debug_metadata = build::debug_crate_metadata(crate_metadata);
+    let target_directory = &crate_metadata.target_directory;
+    let package_name = &crate_metadata.package_name;
+    vec![
+        crate_metadata.original_wasm.clone(),
+        crate_metadata.dest_wasm.clone(),
+        target_directory.join(format!("{}-opt.wasm", package_name)),
+        build::fingerprint_path(crate_metadata),
+        build::fingerprint_path(&debug_metadata),
+        debug_metadata.original_wasm,
+        debug_metadata.dest_wasm,
+        target_directory.join(METADATA_FILE),
+        fingerprint::path(&target_directory.join(METADATA_FILE)),
+        target_directory.join(format!("{}.contract", package_name)),
+        target_directory.join(abi_file_name(package_name)),
+    ]
+}
+
+/// Removes those of the files which exist, and returns their paths.
+fn remove_files(paths: &[PathBuf]) -> Result<Vec<&Path>> {
+    let mut removed = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            fs::remove_file(path).context(format!("Removing {}", path.display()))?;
+            removed.push(path.as_path());
+        }
+    }
+    Ok(removed)
+}
+
+/// Removes the wasm and metadata artifacts of the contract.
+///
+/// If `deep` is set, `cargo clean` is run afterwards to remove the whole target directory. Missing
+/// artifacts are not an error, there is just nothing to remove.
+pub fn execute(
+    manifest_path: &ManifestPath,
+    deep: bool,
+    verbosity: Option<Verbosity>,
+) -> Result<String> {
+    let crate_metadata = CrateMetadata::collect(manifest_path)?;
+    let reporter = Reporter::new(verbosity);
+
+    let paths = artifact_paths(&crate_metadata);
+    let removed = remove_files(&paths)?;
+    for path in &removed {
+        reporter.info(format_args!("  {} {}", "Removed".bold(), path.display()));
+    }
+
+    if deep {
+        reporter.info(format_args!(
+            "  {} {}",
+            "Running".bold(),
+            "cargo clean".bright_green().bold()
+        ));
+        util::invoke_cargo_streaming(
+            "clean",
+            &[crate_metadata.manifest_path.cargo_arg()],
+            None::<&Path>,
+            verbosity,
+        )?;
+    }
+
+    let artifacts = match removed.len() {
+        0 if !deep => return Ok("Nothing to clean".to_string()),
+        1 => "1 artifact".to_string(),
+        count => format!("{} artifacts", count),
+    };
+    if deep {
+        Ok(format!("Removed {} and the target directory", artifacts))
+    } else {
+        Ok(format!("Removed {}", artifacts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    #[cfg(feature = "test-ci-only")]
+    use crate::util::tests::new_project;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn remove_files_skips_missing_files() {
+        with_tmp_dir(|path| {
+            let existing = path.join("contract.wasm");
+            fs::write(&existing, [0u8])?;
+            let missing = path.join("metadata.json");
+
+            let paths = [existing.clone(), missing];
+            let removed = super::remove_files(&paths)?;
+
+            assert_eq!(removed, vec![existing.as_path()]);
+            assert!(!existing.exists());
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "test-ci-only")]
+    #[test]
+    fn clean_removes_artifacts() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+
+            let result = super::execute(&manifest_path, false, None)?;
+            assert_eq!(result, "Nothing to clean");
+
+            let crate_metadata = crate::crate_metadata::CrateMetadata::collect(&manifest_path)?;
+            fs::create_dir_all(&crate_metadata.target_directory)?;
+            fs::write(&crate_metadata.dest_wasm, [0u8])?;
+            let result = super::execute(&manifest_path, false, None)?;
+            assert_eq!(result, "Removed 1 artifact");
+            assert!(!crate_metadata.dest_wasm.exists());
+            Ok(())
+        })
+    }
+}
@@ -15,7 +15,9 @@
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::{
+    cmd::check_env,
     crate_metadata::CrateMetadata,
+    reporter::Reporter,
     util,
     workspace::{ManifestPath, Profile, Workspace},
     UnstableFlags, Verbosity,
@@ -25,18 +27,32 @@ use colored::Colorize;
 use parity_wasm::elements::{External, MemoryType, Module, Section};
 use regex::Regex;
 use std::{
+    collections::VecDeque,
     fs,
     fs::metadata,
     fs::File,
-    io::{self, Write},
+    io::Write,
     path::PathBuf,
     process::Command,
+    sync::{mpsc, Arc, Mutex},
+    thread,
 };
 use wat;
 
 /// This is the maximum number of pages available for a contract to allocate.
 const MAX_MEMORY_PAGES: u32 = 16;
 
+/// Like `Reporter::info`, but appends the line to the log of a component. The log is printed once
+/// the component is built, so that the output of components built in parallel is not interleaved.
+macro_rules! maybe_log {
+    ($log:expr, $verbosity:expr, $($msg:tt)*) => {
+        if !matches!($verbosity, Some($crate::Verbosity::Quiet)) {
+            $log.push_str(&format!($($msg)*));
+            $log.push('\n');
+        }
+    };
+}
+
 // &format!("--target-dir={}", target_dir.to_string_lossy() + "/" + current_compose_name),
 /// Constructs a target destination path for a current compose.
 /// This is simply creating additional folder with a compose name in a target directory.
@@ -81,21 +97,17 @@ pub fn get_dest_wasm_path(compose: String, crate_metadata: &CrateMetadata) -> Pa
 /// user-defined settings will be preserved.
 ///
 /// To disable this and use the original `Cargo.toml` as is then pass the `-Z original_manifest` flag.
+///
+/// The output of cargo is printed as it is produced, with `cargo_verbosity`.
 fn build_cargo_project_compose(
     crate_metadata: &CrateMetadata,
     current_compose_name: String,
     verbosity: Option<Verbosity>,
+    cargo_verbosity: Option<Verbosity>,
     unstable_options: UnstableFlags,
+    log: &mut String,
 ) -> Result<()> {
-    util::assert_channel()?;
-
-    // set RUSTFLAGS, read from environment var by cargo-xbuild
-    std::env::set_var(
-        "RUSTFLAGS",
-        "-C link-arg=-z -C link-arg=stack-size=65536 -C link-arg=--import-memory",
-    );
-
-    let verbosity = verbosity.map(|v| match v {
+    let xbuild_verbosity = cargo_verbosity.map(|v| match v {
         Verbosity::Verbose => xargo_lib::Verbosity::Verbose,
         Verbosity::Quiet => xargo_lib::Verbosity::Quiet,
     });
@@ -115,7 +127,7 @@ fn build_cargo_project_compose(
                     .to_string_lossy()
             ),
         ];
-        let args = xargo_lib::Args::new(target, manifest_path, verbosity, &other_args)
+        let args = xargo_lib::Args::new(target, manifest_path, xbuild_verbosity, &other_args)
             .map_err(|e| anyhow::anyhow!("{}", e))
             .context("Creating xargo args")?;
 
@@ -134,7 +146,9 @@ fn build_cargo_project_compose(
     };
 
     if unstable_options.original_manifest {
-        println!(
+        maybe_log!(
+            log,
+            verbosity,
             "{} {}",
             "warning:".yellow().bold(),
             "with 'original-manifest' enabled, the contract binary may not be of optimal size."
@@ -144,17 +158,16 @@ fn build_cargo_project_compose(
     } else {
         Workspace::new(&crate_metadata.cargo_meta, &crate_metadata.root_package.id)?
             .with_root_package_manifest(|manifest| {
-                manifest
-                    .with_removed_crate_type("rlib")?
-                    .with_profile_release_defaults(Profile::default_contract_release())?;
+                manifest.with_removed_crate_type("rlib")?;
+                Ok(())
+            })?
+            .with_workspace_manifest(|manifest| {
+                manifest.with_profile_release_defaults(Profile::default_contract_release())?;
                 Ok(())
             })?
             .using_temp(xbuild)?;
     }
 
-    // clear RUSTFLAGS
-    std::env::remove_var("RUSTFLAGS");
-
     Ok(())
 }
 
@@ -236,13 +249,21 @@ fn post_process_wasm_compose(crate_metadata: &CrateMetadata, compose: String) ->
 ///
 /// This step depends on the `wasm-opt` tool being installed. If it is not the build will still
 /// succeed, and the user will be encouraged to install it for further optimizations.
-fn optimize_wasm_compose(crate_metadata: &CrateMetadata, compose: String) -> Result<()> {
+fn optimize_wasm_compose(
+    crate_metadata: &CrateMetadata,
+    compose: String,
+    verbosity: Option<Verbosity>,
+    log: &mut String,
+) -> Result<()> {
     // check `wasm-opt` installed
     if which::which("wasm-opt").is_err() {
-        println!(
+        maybe_log!(
+            log,
+            verbosity,
             "{}",
             "wasm-opt is not installed. Install this tool on your system in order to \n\
-             reduce the size of your contract's Wasm binary. \n\
+             reduce the size of your contract's Wasm binary, e.g. with \n\
+             `apt install binaryen` or `brew install binaryen`. \n\
              See https://github.com/WebAssembly/binaryen#tools"
                 .bright_yellow()
         );
@@ -260,17 +281,20 @@ fn optimize_wasm_compose(crate_metadata: &CrateMetadata, compose: String) -> Res
         .output()?;
 
     if !output.status.success() {
-        // Dump the output streams produced by wasm-opt into the stdout/stderr.
-        io::stdout().write_all(&output.stdout)?;
-        io::stderr().write_all(&output.stderr)?;
-        anyhow::bail!("wasm-opt optimization failed");
+        anyhow::bail!(
+            "wasm-opt optimization failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
     }
 
     let original_size = metadata(&dest_wasm)?.len() as f64 / 1000.0;
     let optimized_size = metadata(&optimized)?.len() as f64 / 1000.0;
-    println!(
+    maybe_log!(
+        log,
+        verbosity,
         " Original wasm size: {:.1}K, Optimized: {:.1}K",
-        original_size, optimized_size
+        original_size,
+        optimized_size
     );
 
     // overwrite existing destination wasm file with the optimised version
@@ -310,21 +334,24 @@ impl WatContractsScanner {
         for caps in Regex::new(
             r"(?P<compose_name>[\w]+)_WAT_CONTRACT: &str = r#(?P<wat_contract>[\s\S]+)#;",
         )
-        .unwrap()
+        .expect("the WAT contract pattern is a valid regex; qed")
         .captures_iter(text)
         {
             self.contracts.push(WatContract {
                 name: caps
                     .name("compose_name")
                     .map(|r| Box::leak(r.as_str().to_lowercase().into_boxed_str()))
-                    .unwrap(),
-                code: caps.name("wat_contract").map(|r| r.as_str()).unwrap(),
+                    .expect("compose_name is not optional in the pattern; qed"),
+                code: caps
+                    .name("wat_contract")
+                    .map(|r| r.as_str())
+                    .expect("wat_contract is not optional in the pattern; qed"),
             });
         }
         !self.contracts.is_empty()
     }
 
-    fn find_by_name(&mut self, name: String) -> Option<&WatContract> {
+    fn find_by_name(&self, name: String) -> Option<&WatContract> {
         let r = self.contracts.iter().find(|&c| c.name == name);
         // println!("find_by_name res {:?} {:?}", name, r);
         r
@@ -342,83 +369,81 @@ fn compile_wat_to_wasm(
         compose_name.clone(),
         crate_metadata.target_directory.clone(),
     );
-    fs::create_dir_all(dest_wat_dir.clone()).map_err(|e| {
-        println!(
-            "{} {} {}",
-            "Error when creating a new directory for WAT contract at ./target for component: "
-                .bright_red()
-                .bold(),
-            compose_name,
-            e.to_string()
-        );
-        e
-    })?;
+    fs::create_dir_all(dest_wat_dir.clone()).context(format!(
+        "Creating a new directory for the WAT contract of component {}",
+        compose_name
+    ))?;
     let mut dest_wat_path = get_dest_wasm_path(compose_name.clone(), crate_metadata);
     dest_wat_path.set_extension("wat");
-    let mut file = File::create(dest_wat_path.clone()).map_err(|e| {
-        println!(
-            "{} {} {}",
-            "Error when creating an empty file for WAT contract at ./target for component: "
-                .bright_red()
-                .bold(),
-            compose_name,
-            e.to_string()
-        );
-        e
-    })?;
+    let mut file = File::create(dest_wat_path.clone()).context(format!(
+        "Creating an empty file for the WAT contract of component {}",
+        compose_name
+    ))?;
     let code_bytes = wat_contract.code.as_bytes();
     // During regexp search there are additional \" at the beginning and EOF. Strip them out as they're fail the compilation.
     file.write_all(&code_bytes[1..code_bytes.len() - 1])
-        .map_err(|e| {
-            println!(
-                "{} {} {}",
-                "Error when saving WAT contract as file at ./target for component: "
-                    .bright_red()
-                    .bold(),
-                compose_name,
-                e.to_string()
-            );
-            e
-        })?;
+        .context(format!(
+            "Saving the WAT contract of component {} as a file",
+            compose_name
+        ))?;
 
     // After the .wat file is saved, read it again with wat2wasm compiler / parser
-    let wasm_bytes = wat::parse_file(dest_wat_path.clone()).map_err(|e| {
-        println!(
-            "{} {} {}",
-            "Error during WAT contract compilation for component: "
-                .bright_red()
-                .bold(),
-            compose_name,
-            e.to_string()
-        );
-        e
-    })?;
+    let wasm_bytes = wat::parse_file(dest_wat_path.clone()).context(format!(
+        "Compiling the WAT contract of component {}",
+        compose_name
+    ))?;
 
     let mut file = File::create(get_dest_wasm_path(compose_name.clone(), crate_metadata))?;
     // Write a slice of WASM bytes to the file
-    file.write_all(&wasm_bytes).map_err(|e| {
-        println!(
-            "{} {} {}",
-            "Error when saving WASM contract as file at ./target for component: "
-                .bright_red()
-                .bold(),
-            compose_name,
-            e.to_string()
-        );
-        e
-    })?;
+    file.write_all(&wasm_bytes).context(format!(
+        "Saving the WASM contract of component {} as a file",
+        compose_name
+    ))?;
     Ok(())
 }
+/// Returns the `selected` components in the order of the schedule, or all `scheduled` components
+/// if none are selected.
+///
+/// Selecting a component which is not scheduled is an error.
+pub fn select_components(scheduled: &[String], selected: &[String]) -> Result<Vec<String>> {
+    if let Some(unknown) = selected
+        .iter()
+        .find(|component| !scheduled.contains(component))
+    {
+        let names: Vec<&str> = scheduled.iter().map(String::as_str).collect();
+        let hint = match util::closest_match(unknown, &names) {
+            Some(candidate) => format!("did you mean '{}'?", candidate),
+            None => format!("expected one of {}", names.join(", ")),
+        };
+        anyhow::bail!("Unknown component '{}', {}", unknown, hint)
+    }
+    Ok(scheduled
+        .iter()
+        .filter(|component| selected.is_empty() || selected.contains(component))
+        .cloned()
+        .collect())
+}
+
+/// The error for a contract without a composable schedule in its `Cargo.toml`.
+pub fn missing_schedule_error() -> anyhow::Error {
+    anyhow::anyhow!(
+        "No composable schedule found. Add the list of components as `composables` to the \
+        [package.metadata] section of Cargo.toml"
+    )
+}
+
 /// Reads contract file (lib.rs) as a text.
-fn read_contracts_file_as_text(crate_metadata: &CrateMetadata) -> String {
+fn read_contracts_file_as_text(crate_metadata: &CrateMetadata) -> Result<String> {
     let target_dir = &crate_metadata.cargo_meta.target_directory;
     let mut composable_contract_source_path: PathBuf = target_dir.to_path_buf();
     composable_contract_source_path.pop();
     composable_contract_source_path.push("lib");
     composable_contract_source_path.set_extension("rs");
 
-    fs::read_to_string(composable_contract_source_path)
-        .expect("Something went wrong reading the composable contracts file")
+    fs::read_to_string(&composable_contract_source_path).context(format!(
+        "Reading the composable contracts file {}",
+        composable_contract_source_path.display()
+    ))
 }
 
 /// Executes build of the smart-contract which produces a wasm binary that is ready for deploying.
@@ -429,38 +454,188 @@ fn read_contracts_file_as_text(crate_metadata: &CrateMetadata) -> String {
 ///
 /// Collects the contract crate's metadata using the supplied manifest (`Cargo.toml`) path. Use
 /// [`execute_build_with_metadata`] if an instance is already available.
-pub(crate) fn execute(
+///
+/// If `components` are supplied, only those components of the schedule are built.
+///
+/// Up to `jobs` components are built in parallel, defaulting to the number of cores. The output
+/// of each component is printed once it is built, and the build continues if a component fails:
+/// the error lists all components which failed.
+pub fn execute(
     manifest_path: &ManifestPath,
+    components: &[String],
+    jobs: Option<usize>,
     verbosity: Option<Verbosity>,
     unstable_options: UnstableFlags,
 ) -> Result<PathBuf> {
     let crate_metadata = CrateMetadata::collect(manifest_path)?;
 
-    let composable_schedule = crate_metadata.clone().t3rn_composable_schedule
-        .expect("Failed to read composable metadata from JSON using serde. Make sure your Cargo.toml follows the composable metadata format");
+    let composable_schedule = crate_metadata
+        .t3rn_composable_schedule
+        .clone()
+        .ok_or_else(missing_schedule_error)?;
 
-    let mut compose_dest_path = Err(anyhow::anyhow!(
-        "Empty composable t3rn contracts schedule. Didn't compile anything."
+    if composable_schedule.composables.is_empty() {
+        anyhow::bail!("Empty composable t3rn contracts schedule. Didn't compile anything.")
+    }
+    Reporter::new(verbosity).info(format_args!(
+        "{} {:?}",
+        "Detected t3rn schedule with following components:"
+            .bright_blue()
+            .bold(),
+        composable_schedule.composables
     ));
+    let composables = select_components(&composable_schedule.composables, components)?;
+    let jobs = match jobs {
+        Some(0) => anyhow::bail!("--jobs must be at least 1"),
+        Some(jobs) => jobs,
+        None => num_cpus::get(),
+    };
+    util::assert_channel()?;
+    check_env::preflight()?;
+
     // Scan for Wasm text format components.
-    let contents = read_contracts_file_as_text(&crate_metadata);
+    let contents = read_contracts_file_as_text(&crate_metadata)?;
     let mut contracts_scanner = WatContractsScanner::new_empty();
     contracts_scanner.scan_from_text(Box::leak(contents.into_boxed_str()));
 
-    for compose in composable_schedule.composables.clone() {
-        compose_dest_path = execute_with_metadata_composable(
-            &crate_metadata,
-            &mut contracts_scanner,
-            compose,
-            verbosity,
-            unstable_options.clone(),
-        );
+    // set RUSTFLAGS, read from environment var by cargo-xbuild. It is set once for all builds,
+    // since the environment is shared by the threads building the components.
+    std::env::set_var(
+        "RUSTFLAGS",
+        "-C link-arg=-z -C link-arg=stack-size=65536 -C link-arg=--import-memory",
+    );
+    let result = build_components(
+        Arc::new(crate_metadata.clone()),
+        Arc::new(contracts_scanner),
+        composables,
+        jobs,
+        verbosity,
+        unstable_options,
+    );
+    // clear RUSTFLAGS
+    std::env::remove_var("RUSTFLAGS");
+
+    result.map(|()| crate_metadata.target_directory)
+}
+
+/// Builds the components, up to `jobs` of them at the same time.
+///
+/// The components compiled with cargo share the sysroot built by xbuild. If more than one job is
+/// used, the first of them is therefore built on its own, so that the sysroot is only built once.
+/// The output of cargo is quiet then, since it is printed directly and would be interleaved.
+fn build_components(
+    crate_metadata: Arc<CrateMetadata>,
+    contracts_scanner: Arc<WatContractsScanner>,
+    components: Vec<String>,
+    jobs: usize,
+    verbosity: Option<Verbosity>,
+    unstable_options: UnstableFlags,
+) -> Result<()> {
+    let total = components.len();
+    let jobs = jobs.min(total);
+    let cargo_verbosity = if jobs > 1 {
+        Some(Verbosity::Quiet)
+    } else {
+        verbosity
+    };
+    let mut queue: VecDeque<String> = components.into();
+    let mut completed = 0;
+    let mut failures = Vec::new();
+    let reporter = Reporter::new(verbosity);
+    let mut report = |compose: String, result: Result<()>, log: String| {
+        completed += 1;
+        let status = if result.is_ok() {
+            "built".bright_green().bold()
+        } else {
+            "failed".bright_red().bold()
+        };
+        reporter.info(format_args!(
+            "{} {} {}",
+            format!("[{}/{}]", completed, total).bold(),
+            compose.bright_blue().bold(),
+            status
+        ));
+        reporter.info(log.trim_end());
+        if let Err(err) = result {
+            failures.push((compose, err));
+        }
+    };
+
+    if jobs > 1 {
+        let first_cargo_component = queue
+            .iter()
+            .position(|compose| contracts_scanner.find_by_name(compose.clone()).is_none());
+        if let Some(index) = first_cargo_component {
+            let compose = queue.remove(index).expect("the index was just found; qed");
+            let mut log = String::new();
+            let result = execute_with_metadata_composable(
+                &crate_metadata,
+                &contracts_scanner,
+                compose.clone(),
+                verbosity,
+                verbosity,
+                unstable_options.clone(),
+                &mut log,
+            );
+            report(compose, result, log);
+        }
+    }
+
+    let queue = Arc::new(Mutex::new(queue));
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> = (0..jobs)
+        .map(|_| {
+            let (queue, sender) = (queue.clone(), sender.clone());
+            let (crate_metadata, contracts_scanner) =
+                (crate_metadata.clone(), contracts_scanner.clone());
+            let unstable_options = unstable_options.clone();
+            thread::spawn(move || loop {
+                let compose = match queue.lock().expect("no worker panics; qed").pop_front() {
+                    Some(compose) => compose,
+                    None => break,
+                };
+                let mut log = String::new();
+                let result = execute_with_metadata_composable(
+                    &crate_metadata,
+                    &contracts_scanner,
+                    compose.clone(),
+                    verbosity,
+                    cargo_verbosity,
+                    unstable_options.clone(),
+                    &mut log,
+                );
+                if sender.send((compose, result, log)).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+    for (compose, result, log) in receiver {
+        report(compose, result, log);
+    }
+    for worker in workers {
+        if worker.join().is_err() {
+            anyhow::bail!("A thread building the components panicked")
+        }
     }
-    match compose_dest_path {
-        Ok(_) => Ok(crate_metadata.target_directory),
-        Err(err) => Err(err),
+
+    if failures.is_empty() {
+        return Ok(());
     }
+    let failed = failures
+        .iter()
+        .map(|(compose, err)| format!("  {}: {:#}", compose, err))
+        .collect::<Vec<_>>()
+        .join("\n");
+    anyhow::bail!(
+        "Failed to build {} of {} components:\n{}",
+        failures.len(),
+        total,
+        failed
+    )
 }
+
 /// Executes build of the smart-contract which produces a wasm binary that is ready for deploying.
 ///
 /// It does so by invoking `cargo build` and then post processing the final binary.
@@ -468,16 +643,23 @@ pub(crate) fn execute(
 /// # Note
 ///
 /// Uses the supplied `CrateMetadata`. If an instance is not available use [`execute_build`]
+///
+/// The progress of the build is appended to the `log` of the component, while the output of
+/// cargo is printed directly with `cargo_verbosity`.
 pub(crate) fn execute_with_metadata_composable(
     crate_metadata: &CrateMetadata,
-    wat_contracts_scanner: &mut WatContractsScanner,
+    wat_contracts_scanner: &WatContractsScanner,
     compose: String,
     verbosity: Option<Verbosity>,
+    cargo_verbosity: Option<Verbosity>,
     unstable_options: UnstableFlags,
-) -> Result<PathBuf> {
+    log: &mut String,
+) -> Result<()> {
     match wat_contracts_scanner.find_by_name(compose.clone()) {
         Some(wat_contract) => {
-            println!(
+            maybe_log!(
+                log,
+                verbosity,
                 "{} {} {}",
                 "[1/3]".bold(),
                 "Skipping cargo build; found WAT contract - component:"
@@ -485,7 +667,9 @@ pub(crate) fn execute_with_metadata_composable(
                     .bold(),
                 compose.as_str().bright_green().bold()
             );
-            println!(
+            maybe_log!(
+                log,
+                verbosity,
                 "{} {} {}",
                 "[2/3]".bold(),
                 "Compiling scanned WAT contract to WASM - component:"
@@ -496,7 +680,9 @@ pub(crate) fn execute_with_metadata_composable(
             compile_wat_to_wasm(compose.clone(), &crate_metadata, wat_contract)?;
         }
         None => {
-            println!(
+            maybe_log!(
+                log,
+                verbosity,
                 "{} {} {}",
                 "[1/3]".bold(),
                 "Building cargo project - component:".bright_green().bold(),
@@ -506,9 +692,13 @@ pub(crate) fn execute_with_metadata_composable(
                 &crate_metadata,
                 compose.clone(),
                 verbosity,
+                cargo_verbosity,
                 unstable_options,
+                log,
             )?;
-            println!(
+            maybe_log!(
+                log,
+                verbosity,
                 " {} {} {}",
                 "[2/3]".bold(),
                 "Post processing wasm file - component:"
@@ -520,28 +710,73 @@ pub(crate) fn execute_with_metadata_composable(
         }
     };
 
-    println!(
+    maybe_log!(
+        log,
+        verbosity,
         " {} {} {}",
         "[3/3]".bold(),
         "Optimizing wasm file - component:".bright_green().bold(),
         compose.as_str().bright_green().bold(),
     );
-    optimize_wasm_compose(&crate_metadata, compose.clone())?;
-    Ok(crate_metadata.dest_wasm.clone())
+    optimize_wasm_compose(&crate_metadata, compose.clone(), verbosity, log)?;
+    if let Some(Verbosity::Verbose) = verbosity {
+        let size = metadata(get_dest_wasm_path(compose.clone(), &crate_metadata))?.len();
+        log.push_str(&format!(
+            " {} wasm size: {}\n",
+            compose,
+            util::format_size(size)
+        ));
+    }
+    Ok(())
 }
 
-#[cfg(feature = "test-ci-only")]
 #[cfg(test)]
 mod tests {
-    use crate::{cmd, util::tests::with_tmp_dir, workspace::ManifestPath, UnstableFlags};
+    #[cfg(feature = "test-ci-only")]
+    use crate::{
+        util::tests::{new_project, with_tmp_dir},
+        UnstableFlags,
+    };
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn select_components_keeps_schedule_order() {
+        let scheduled = names(&["flipper", "incrementer", "erc20"]);
+        assert_eq!(
+            super::select_components(&scheduled, &[]).unwrap(),
+            scheduled
+        );
+        assert_eq!(
+            super::select_components(&scheduled, &names(&["erc20", "flipper"])).unwrap(),
+            names(&["flipper", "erc20"])
+        );
+    }
+
+    #[test]
+    fn select_components_rejects_unknown_component() {
+        let scheduled = names(&["flipper", "incrementer"]);
+        let err = super::select_components(&scheduled, &names(&["fliper"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown component 'fliper', did you mean 'flipper'?"
+        );
+        let err = super::select_components(&scheduled, &names(&["erc20"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown component 'erc20', expected one of flipper, incrementer"
+        );
+    }
 
+    #[cfg(feature = "test-ci-only")]
     #[test]
     fn build_template() {
         with_tmp_dir(|path| {
-            cmd::new::execute("new_project", Some(path)).expect("new project creation failed");
-            let manifest_path =
-                ManifestPath::new(&path.join("new_project").join("Cargo.toml")).unwrap();
-            super::execute(&manifest_path, None, UnstableFlags::default()).expect("build failed");
+            let manifest_path = new_project(path);
+            super::execute(&manifest_path, &[], None, None, UnstableFlags::default())
+                .expect("build failed");
             Ok(())
         })
     }
@@ -0,0 +1,100 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+use colored::Colorize;
+use subxt::Signer;
+
+use crate::{
+    cmd::{composable_build, deploy},
+    crate_metadata::CrateMetadata,
+    ExtrinsicOpts,
+};
+
+/// Uploads the code of the components in the deploy schedule of the composable metadata, each to
+/// the url appointed by the schedule.
+///
+/// The url of a component is replaced by its entry in `url_overrides`, and only the `components`
+/// are deployed if any are passed. All other extrinsic options apply to every component.
+pub fn execute(
+    extrinsic_opts: &ExtrinsicOpts,
+    url_overrides: &[(String, url::Url)],
+    components: &[String],
+) -> Result<String> {
+    let crate_metadata = CrateMetadata::collect(&Default::default())?;
+    let reporter = extrinsic_opts.reporter()?;
+    reporter.info(
+        "Deploy composable components to appointed urls"
+            .bright_blue()
+            .bold(),
+    );
+    let composable_schedule = crate_metadata
+        .t3rn_composable_schedule
+        .clone()
+        .ok_or_else(composable_build::missing_schedule_error)?;
+    let deploy_schedule = composable_schedule.deploy.ok_or_else(|| {
+        anyhow::anyhow!("Nothing to deploy. Empty deploy key of composable metadata.")
+    })?;
+    if let Some((component, _)) = url_overrides.iter().find(|(component, _)| {
+        !deploy_schedule
+            .iter()
+            .any(|deploy| &deploy.compose == component)
+    }) {
+        anyhow::bail!(
+            "Url override for component '{}', which is not in the deploy schedule",
+            component
+        )
+    }
+    let scheduled: Vec<String> = deploy_schedule
+        .iter()
+        .map(|deploy| deploy.compose.clone())
+        .collect();
+    let selected = composable_build::select_components(&scheduled, components)?;
+    // only the public account of the signer is printed, never its secret
+    let signer_account = extrinsic_opts.signer()?.account_id().clone();
+    for deploy in deploy_schedule
+        .into_iter()
+        .filter(|deploy| selected.contains(&deploy.compose))
+    {
+        reporter.info(format_args!("Deploying: {:?}", deploy));
+        let url = match url_overrides
+            .iter()
+            .find(|(component, _)| component == &deploy.compose)
+        {
+            Some((_, url)) => url.clone(),
+            None => url::Url::parse(&deploy.url)?,
+        };
+        let component_extrinsic_opts = ExtrinsicOpts {
+            url: Some(url),
+            ..extrinsic_opts.clone()
+        };
+        let dest_wasm_path =
+            composable_build::get_dest_wasm_path(deploy.compose.clone(), &crate_metadata);
+        let code = deploy::load_contract_code(&dest_wasm_path)?;
+        let code_hash =
+            deploy::execute_deploy(&component_extrinsic_opts, &code, false, false, None)?.code_hash;
+        reporter.info(format_args!(
+            "{} - {} {:?}",
+            deploy.compose.bright_blue().bold(),
+            "successfully deployed byte code with hash: ".bright_blue(),
+            code_hash
+        ));
+    }
+    Ok(format!(
+        "All components successfully deployed for {}",
+        signer_account
+    ))
+}
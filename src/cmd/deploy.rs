@@ -14,61 +14,282 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{fs, io::Read, path::PathBuf};
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
+use parity_wasm::elements::{Internal, Module};
+use serde::Serialize;
 use sp_core::H256;
-use subxt::{contracts::*, ClientBuilder, ContractsTemplateRuntime};
+use subxt::{contracts::*, ContractsTemplateRuntime, Signer};
 
-use crate::{crate_metadata, ExtrinsicOpts};
+use crate::{
+    cmd::{
+        account, build,
+        events::{self, ExtrinsicInclusion},
+        offline, retry, rpc,
+    },
+    crate_metadata::CrateMetadata,
+    workspace::ManifestPath,
+    ExtrinsicOpts,
+};
 
-/// Load the wasm blob from the specified path.
+/// The magic number and version every wasm binary starts with.
+const WASM_PREAMBLE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+/// The functions a contract must export to be callable by the contracts pallet.
+const CONTRACT_EXPORTS: [&str; 2] = ["deploy", "call"];
+
+/// Checks that the code is a well-formed wasm binary exporting the contract entry points.
+fn validate_contract_code(code: &[u8]) -> Result<()> {
+    if !code.starts_with(&WASM_PREAMBLE[..4]) {
+        anyhow::bail!("Contract code is not a wasm binary, the magic number `\\0asm` is missing")
+    }
+    if !code.starts_with(&WASM_PREAMBLE) {
+        anyhow::bail!("Contract code has an unsupported wasm version, expected version 1")
+    }
+    let module: Module = parity_wasm::deserialize_buffer(code)
+        .map_err(|e| anyhow::anyhow!("Contract code is not a well-formed wasm binary: {}", e))?;
+    let exported_functions = module
+        .export_section()
+        .map(|section| {
+            section
+                .entries()
+                .iter()
+                .filter(|entry| matches!(entry.internal(), Internal::Function(_)))
+                .map(|entry| entry.field())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    for export in CONTRACT_EXPORTS.iter() {
+        if !exported_functions.contains(export) {
+            anyhow::bail!("Contract code does not export the `{}` function", export)
+        }
+    }
+    Ok(())
+}
+
+/// Returns the path of the contract wasm written by `build` for the project at `manifest_path`.
+///
+/// Prefers the wasm of a release build, falling back to the one of a `--debug` build if only that
+/// exists.
+pub fn default_contract_wasm_path(manifest_path: &ManifestPath) -> Result<PathBuf> {
+    let crate_metadata = CrateMetadata::collect(manifest_path)?;
+    let release_wasm = crate_metadata.dest_wasm.clone();
+    let debug_wasm = build::debug_crate_metadata(&crate_metadata).dest_wasm;
+    if release_wasm.exists() {
+        Ok(release_wasm)
+    } else if debug_wasm.exists() {
+        Ok(debug_wasm)
+    } else {
+        anyhow::bail!(
+            "No contract wasm found at {}, build the contract first with `cargo contract build`",
+            release_wasm.display()
+        )
+    }
+}
+
+/// The path which, passed as the contract wasm path, reads the wasm from stdin instead.
+const STDIN_PATH: &str = "-";
+
+/// Load the wasm blob from the specified path, or from stdin if the path is `-`.
 ///
-/// Defaults to the target contract wasm in the current project, inferred via the crate metadata.
-pub fn load_contract_code(path: Option<&PathBuf>) -> Result<Vec<u8>> {
-    let contract_wasm_path = match path {
-        Some(path) => path.clone(),
-        None => {
-            let metadata = crate_metadata::CrateMetadata::collect(&Default::default())?;
-            metadata.dest_wasm
+/// Use [`default_contract_wasm_path`] for the contract wasm built for a project.
+///
+/// The loaded code is checked to be a valid contract, see [`validate_contract_code`].
+pub fn load_contract_code(path: &Path) -> Result<Vec<u8>> {
+    if path.as_os_str() == STDIN_PATH {
+        if atty::is(atty::Stream::Stdin) {
+            anyhow::bail!(
+                "Reading the contract code from stdin, but stdin is a terminal. Pipe the wasm \
+                into cargo-contract, e.g. `cat contract.wasm | cargo contract deploy -`"
+            )
         }
-    };
-    log::info!("Contract code path: {}", contract_wasm_path.display());
-    let mut data = Vec::new();
-    let mut file = fs::File::open(&contract_wasm_path)
-        .context(format!("Failed to open {}", contract_wasm_path.display()))?;
-    file.read_to_end(&mut data)?;
+        log::info!("Contract code path: stdin");
+        return read_contract_code(io::stdin().lock(), "<stdin>");
+    }
+    log::info!("Contract code path: {}", path.display());
+    let file = fs::File::open(path).context(format!("Failed to open {}", path.display()))?;
+    read_contract_code(file, &path.display().to_string())
+}
+
+/// Load the wasm blob embedded in the `.contract` bundle at `path`, as written by
+/// `generate-metadata`.
+///
+/// The loaded code is checked to be a valid contract, see [`validate_contract_code`].
+pub fn load_bundle_code(path: &Path) -> Result<Vec<u8>> {
+    log::info!("Contract bundle path: {}", path.display());
+    let contents = fs::read(path).context(format!("Failed to open {}", path.display()))?;
+    bundle_code(&contents).context(format!("Invalid contract bundle {}", path.display()))
+}
 
+/// Loads the contract code from the `bundle` if supplied, otherwise from the wasm file at
+/// `wasm_path`, defaulting to the contract wasm built for the current project.
+pub fn load_code(wasm_path: Option<&PathBuf>, bundle: Option<&Path>) -> Result<Vec<u8>> {
+    if let Some(bundle) = bundle {
+        return load_bundle_code(bundle);
+    }
+    match wasm_path {
+        Some(wasm_path) => load_contract_code(wasm_path),
+        None => load_contract_code(&default_contract_wasm_path(&ManifestPath::default())?),
+    }
+}
+
+/// Extracts and validates the hex encoded wasm from the `source` of the bundle.
+fn bundle_code(contents: &[u8]) -> Result<Vec<u8>> {
+    let bundle: serde_json::Value = serde_json::from_slice(contents)?;
+    let wasm = bundle["source"]["wasm"].as_str().ok_or_else(|| {
+        anyhow::anyhow!("The bundle contains no contract code, is it the metadata.json instead?")
+    })?;
+    let code = hex::decode(wasm.trim_start_matches("0x"))
+        .context("The contract code of the bundle is not hex encoded")?;
+    validate_contract_code(&code)?;
+    Ok(code)
+}
+
+/// Reads the contract code as raw bytes and validates it, `source` names it in errors.
+fn read_contract_code<R: Read>(mut reader: R, source: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader
+        .read_to_end(&mut data)
+        .context(format!("Reading the contract code from {}", source))?;
+    validate_contract_code(&data).context(format!("Invalid contract {}", source))?;
     Ok(data)
 }
 
+/// The result of uploading contract code.
+#[derive(Debug, Serialize)]
+pub struct DeployResult {
+    pub code_hash: H256,
+    /// Where the extrinsic was included, `None` if it was not submitted.
+    #[serde(flatten)]
+    pub inclusion: Option<ExtrinsicInclusion>,
+    /// The signed extrinsic, hex encoded, if it was signed with `--offline` instead of submitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extrinsic: Option<String>,
+}
+
 /// Put contract code to a smart contract enabled substrate chain.
-/// Returns the code hash of the deployed contract if successful.
+/// Returns the code hash of the deployed contract if successful, together with where the
+/// extrinsic was included.
 ///
-/// Optionally supply the contract wasm path, defaults to destination contract file inferred from
-/// Cargo.toml of the current contract project.
+/// The `code` is loaded with [`load_contract_code`] from a wasm file, or with
+/// [`load_bundle_code`] from a `.contract` bundle.
 ///
 /// Creates an extrinsic with the `Contracts::put_code` Call, submits via RPC, then waits for
 /// the `ContractsEvent::CodeStored` event.
-pub(crate) fn execute_deploy(
+///
+/// If `dry_run` is set, the code is loaded and the signer resolved, but the extrinsic is not
+/// submitted. The returned code hash is then computed locally, the same way the contracts pallet
+/// does.
+///
+/// If `verify` is set, the code stored on chain under the returned code hash is fetched after
+/// the upload and compared against the uploaded code.
+///
+/// If `expect_hash` is supplied, the deployment is aborted before submission unless the hash of
+/// the code matches it.
+///
+/// With `--offline`, the extrinsic is signed and returned hex encoded instead of submitted.
+///
+/// Connecting is retried on network errors, see [`retry::with_retries`]. The extrinsic is
+/// submitted only once.
+pub fn execute_deploy(
     extrinsic_opts: &ExtrinsicOpts,
-    contract_wasm_path: Option<&PathBuf>,
-) -> Result<H256> {
-    let code = load_contract_code(contract_wasm_path)?;
+    code: &[u8],
+    dry_run: bool,
+    verify: bool,
+    expect_hash: Option<H256>,
+) -> Result<DeployResult> {
+    let signer = extrinsic_opts.signer()?;
+    let reporter = extrinsic_opts.reporter()?;
 
-    async_std::task::block_on(async move {
-        let cli = ClientBuilder::<ContractsTemplateRuntime>::new()
-            .set_url(&extrinsic_opts.url.to_string())
-            .build()
-            .await?;
-        let signer = extrinsic_opts.signer()?;
+    let code_hash = H256(build::code_hash(code));
+    reporter.info(format_args!("Code hash: {:?}", code_hash));
+    if let Some(expect_hash) = expect_hash {
+        if code_hash != expect_hash {
+            anyhow::bail!(
+                "Code hash {:?} does not match the expected hash {:?}",
+                code_hash,
+                expect_hash
+            )
+        }
+    }
 
-        let events = cli.put_code_and_watch(&signer, &code).await?;
+    if dry_run {
+        // a dry run does not connect to the node, so the prefix is not queried
+        let signer_account = match extrinsic_opts.ss58_prefix {
+            Some(prefix) => account::ss58_address(signer.account_id(), prefix)?,
+            None => signer.account_id().to_string(),
+        };
+        reporter.info(format_args!("Signer account: {}", signer_account));
+        return Ok(DeployResult {
+            code_hash,
+            inclusion: None,
+            extrinsic: None,
+        });
+    }
+
+    if extrinsic_opts.offline.offline {
+        let call = PutCodeCall::<ContractsTemplateRuntime> {
+            _runtime: Default::default(),
+            code,
+        };
+        return Ok(DeployResult {
+            code_hash,
+            inclusion: None,
+            extrinsic: Some(offline::sign(extrinsic_opts, &signer, call)?),
+        });
+    }
+
+    reporter.verbose(format_args!(
+        "Call: Contracts::put_code {{ code: {} bytes, hash {:?} }}",
+        code.len(),
+        code_hash
+    ));
+
+    async_std::task::block_on(async move {
+        let signer = &signer;
+        let cli = retry::with_retries(extrinsic_opts, || rpc::client(extrinsic_opts)).await?;
+        let result = {
+            let _spinner = reporter.spinner("Waiting for the extrinsic to be included");
+            retry::with_timeout(extrinsic_opts, cli.put_code_and_watch(signer, code)).await?
+        };
+        let events = events::decode_dispatch_error(extrinsic_opts, result).await?;
         let code_stored = events
             .code_stored()?
             .ok_or(anyhow::anyhow!("Failed to find CodeStored event"))?;
+        let inclusion = events::inclusion(extrinsic_opts, &events).await?;
+
+        if verify {
+            let client = rpc::connect_opts(extrinsic_opts).await?;
+            let stored_code = rpc::fetch_pristine_code(&client, &code_stored.code_hash)
+                .await?
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "No code found on chain for code hash {:?}",
+                        code_stored.code_hash
+                    )
+                })?;
+            if stored_code != code {
+                anyhow::bail!(
+                    "Code stored on chain for code hash {:?} differs from the uploaded code",
+                    code_stored.code_hash
+                )
+            }
+            reporter.info(format_args!(
+                "Verified code stored on chain for code hash {:?}",
+                code_stored.code_hash
+            ));
+        }
 
-        Ok(code_stored.code_hash)
+        Ok(DeployResult {
+            code_hash: code_stored.code_hash,
+            inclusion: Some(inclusion),
+            extrinsic: None,
+        })
     })
 }
 
@@ -76,8 +297,14 @@ pub(crate) fn execute_deploy(
 mod tests {
     use std::{fs, io::Write};
 
-    use crate::{cmd::deploy::execute_deploy, util::tests::with_tmp_dir, ExtrinsicOpts};
+    #[cfg(feature = "test-ci-only")]
+    use crate::{cmd, util::tests::new_project};
+    use crate::{
+        cmd::deploy::execute_deploy,
+        util::tests::{extrinsic_opts, with_tmp_dir},
+    };
     use assert_matches::assert_matches;
+    use sp_core::H256;
 
     const CONTRACT: &str = r#"
 (module
@@ -95,16 +322,198 @@ mod tests {
             let mut file = fs::File::create(&wasm_path).unwrap();
             let _ = file.write_all(&wasm);
 
-            let url = url::Url::parse("ws://localhost:9944").unwrap();
-            let extrinsic_opts = ExtrinsicOpts {
-                url,
-                suri: "//Alice".into(),
-                password: None,
-            };
-            let result = execute_deploy(&extrinsic_opts, Some(&wasm_path));
+            let extrinsic_opts = extrinsic_opts("ws://localhost:9944", &[]);
+            let result = execute_deploy(
+                &extrinsic_opts,
+                &super::load_contract_code(&wasm_path)?,
+                false,
+                false,
+                None,
+            );
 
             assert_matches!(result, Ok(_));
             Ok(())
         })
     }
+
+    #[test]
+    fn validate_contract_code_accepts_contract() {
+        let wasm = wabt::wat2wasm(CONTRACT).expect("invalid wabt");
+        assert_matches!(super::validate_contract_code(&wasm), Ok(()));
+    }
+
+    #[test]
+    fn validate_contract_code_rejects_non_wasm() {
+        let result = super::validate_contract_code(b"(module)");
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Contract code is not a wasm binary, the magic number `\\0asm` is missing"
+        );
+    }
+
+    #[test]
+    fn validate_contract_code_rejects_missing_export() {
+        let wasm = wabt::wat2wasm(r#"(module (func (export "deploy")))"#).expect("invalid wabt");
+        let result = super::validate_contract_code(&wasm);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Contract code does not export the `call` function"
+        );
+    }
+
+    #[test]
+    fn read_contract_code_reads_raw_bytes() {
+        let wasm = wabt::wat2wasm(CONTRACT).expect("invalid wabt");
+        let code = super::read_contract_code(&wasm[..], "<stdin>").unwrap();
+        assert_eq!(code, wasm);
+
+        let result = super::read_contract_code(&b"\r\n"[..], "<stdin>");
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Invalid contract <stdin>"
+        );
+    }
+
+    #[test]
+    fn bundle_code_is_extracted_from_source() {
+        let wasm = wabt::wat2wasm(CONTRACT).expect("invalid wabt");
+        let bundle = serde_json::json!({
+            "metadataVersion": "0.1.0",
+            "source": { "hash": "0x00", "wasm": format!("0x{}", hex::encode(&wasm)) },
+            "spec": {},
+        });
+        let code = super::bundle_code(bundle.to_string().as_bytes()).unwrap();
+        assert_eq!(code, wasm);
+
+        let metadata = serde_json::json!({ "source": { "hash": "0x00" } });
+        let result = super::bundle_code(metadata.to_string().as_bytes());
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "The bundle contains no contract code, is it the metadata.json instead?"
+        );
+    }
+
+    #[test]
+    fn deploy_contract_dry_run_computes_code_hash_locally() {
+        with_tmp_dir(|path| {
+            let wasm = wabt::wat2wasm(CONTRACT).expect("invalid wabt");
+
+            let wasm_path = path.join("test.wasm");
+            let mut file = fs::File::create(&wasm_path).unwrap();
+            let _ = file.write_all(&wasm);
+
+            // no node is listening at this url, the dry run must not connect to it
+            let extrinsic_opts = extrinsic_opts("ws://localhost:1", &[]);
+            let result = execute_deploy(
+                &extrinsic_opts,
+                &super::load_contract_code(&wasm_path)?,
+                true,
+                false,
+                None,
+            );
+
+            assert_eq!(
+                result.ok().map(|result| result.code_hash),
+                Some(H256(sp_core::blake2_256(&wasm)))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "test-ci-only")]
+    fn deploy_contract_defaults_to_built_wasm() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let build_result =
+                cmd::build::execute(&manifest_path, Default::default()).expect("build failed");
+
+            let wasm_path = super::default_contract_wasm_path(&manifest_path)?;
+            assert_eq!(wasm_path, build_result.dest_wasm);
+
+            let extrinsic_opts = extrinsic_opts("ws://localhost:1", &[]);
+            let result = execute_deploy(
+                &extrinsic_opts,
+                &super::load_contract_code(&wasm_path)?,
+                true,
+                false,
+                None,
+            );
+            assert_matches!(result, Ok(_));
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "test-ci-only")]
+    fn default_contract_wasm_path_requires_build() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+
+            let result = super::default_contract_wasm_path(&manifest_path);
+            assert!(result
+                .err()
+                .unwrap()
+                .to_string()
+                .ends_with("build the contract first with `cargo contract build`"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn deploy_contract_aborts_on_unexpected_hash() {
+        with_tmp_dir(|path| {
+            let wasm = wabt::wat2wasm(CONTRACT).expect("invalid wabt");
+
+            let wasm_path = path.join("test.wasm");
+            let mut file = fs::File::create(&wasm_path).unwrap();
+            let _ = file.write_all(&wasm);
+
+            let extrinsic_opts = extrinsic_opts("ws://localhost:1", &[]);
+            let expected = H256::zero();
+            let result = execute_deploy(
+                &extrinsic_opts,
+                &super::load_contract_code(&wasm_path)?,
+                false,
+                false,
+                Some(expected),
+            );
+
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                format!(
+                    "Code hash {:?} does not match the expected hash {:?}",
+                    H256(sp_core::blake2_256(&wasm)),
+                    expected
+                )
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn deploy_contract_offline_requires_chain_state() {
+        with_tmp_dir(|path| {
+            let wasm = wabt::wat2wasm(CONTRACT).expect("invalid wabt");
+
+            let wasm_path = path.join("test.wasm");
+            let mut file = fs::File::create(&wasm_path).unwrap();
+            let _ = file.write_all(&wasm);
+
+            let extrinsic_opts = extrinsic_opts("ws://localhost:1", &["--offline"]);
+            let result = execute_deploy(
+                &extrinsic_opts,
+                &super::load_contract_code(&wasm_path)?,
+                false,
+                false,
+                None,
+            );
+
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                "--offline requires --nonce"
+            );
+            Ok(())
+        })
+    }
 }
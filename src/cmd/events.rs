@@ -0,0 +1,165 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{fmt, time::Duration};
+
+use colored::Colorize;
+use serde::Serialize;
+use sp_core::H256;
+use subxt::{
+    system::System, ContractsTemplateRuntime, ExtrinsicSuccess, ModuleError, RuntimeError,
+};
+
+use crate::{cmd::rpc, ExtrinsicOpts, Reporter};
+
+/// The finality level reached by the block a submitted extrinsic was included in.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Finality {
+    /// The block was imported, but may still be retracted.
+    InBlock,
+    /// The block was finalized.
+    Finalized,
+}
+
+impl fmt::Display for Finality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finality::InBlock => write!(f, "in block"),
+            Finality::Finalized => write!(f, "finalized"),
+        }
+    }
+}
+
+/// The block a submitted extrinsic was included in, and its index within the block.
+#[derive(Debug, Serialize)]
+pub struct ExtrinsicInclusion {
+    pub block_hash: H256,
+    /// `None` if the extrinsic could not be found in the block.
+    pub extrinsic_index: Option<usize>,
+    pub finality: Finality,
+}
+
+impl fmt::Display for ExtrinsicInclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block {:?}", self.block_hash)?;
+        if let Some(extrinsic_index) = self.extrinsic_index {
+            write!(f, ", extrinsic index {}", extrinsic_index)?;
+        }
+        write!(f, " ({})", self.finality)
+    }
+}
+
+/// Looks up where a successfully submitted extrinsic was included.
+///
+/// The index is found by matching the hash of the extrinsic against the extrinsics of the block.
+/// A failure to do so is logged, not returned, since the extrinsic was submitted regardless.
+///
+/// With `--wait-for-finalized`, waits until the block is finalized, which fails if the block is
+/// retracted instead.
+pub(crate) async fn inclusion(
+    extrinsic_opts: &ExtrinsicOpts,
+    result: &ExtrinsicSuccess<ContractsTemplateRuntime>,
+) -> anyhow::Result<ExtrinsicInclusion> {
+    let extrinsic_index = async {
+        let client = rpc::connect_opts(extrinsic_opts).await?;
+        rpc::extrinsic_index(&client, &result.block, &result.extrinsic).await
+    }
+    .await
+    .unwrap_or_else(|err: anyhow::Error| {
+        log::warn!(
+            "Failed to find the extrinsic in block {:?}: {:#}",
+            result.block,
+            err
+        );
+        None
+    });
+    let finality = if extrinsic_opts.wait_for_finalized {
+        let client = rpc::connect_opts(extrinsic_opts).await?;
+        let timeout = Duration::from_secs(extrinsic_opts.timeout);
+        rpc::wait_for_finalized(&client, &result.block, timeout).await?;
+        Finality::Finalized
+    } else {
+        Finality::InBlock
+    };
+    Ok(ExtrinsicInclusion {
+        block_hash: result.block,
+        extrinsic_index,
+        finality,
+    })
+}
+
+/// Reports all events emitted by a successfully submitted extrinsic, one per line, as
+/// `Module::Variant` followed by the SCALE encoded event data.
+///
+/// The events are status output of the command, nothing is printed if the `reporter` is quiet.
+pub(crate) fn display_events<T: System>(reporter: &Reporter, result: &ExtrinsicSuccess<T>) {
+    reporter.info("Events".bright_blue().bold());
+    for event in &result.events {
+        let name = format!("{}::{}", event.module, event.variant);
+        if event.data.is_empty() {
+            reporter.info(format_args!("  {}", name.bold()));
+        } else {
+            reporter.info(format_args!(
+                "  {} 0x{}",
+                name.bold(),
+                hex::encode(&event.data)
+            ));
+        }
+    }
+}
+
+/// Converts the result of an extrinsic submission, surfacing the decoded dispatch error if the
+/// extrinsic failed on chain.
+///
+/// A module error is described as `Module.Error: documentation`, with the documentation of the
+/// error looked up in the runtime metadata of the node. If the lookup fails, only the names of
+/// the module and the error are reported.
+pub(crate) async fn decode_dispatch_error<T>(
+    extrinsic_opts: &ExtrinsicOpts,
+    result: Result<T, subxt::Error>,
+) -> anyhow::Result<T> {
+    let runtime_error = match result {
+        Ok(value) => return Ok(value),
+        Err(subxt::Error::Runtime(runtime_error)) => runtime_error,
+        Err(err) => return Err(err.into()),
+    };
+    match runtime_error {
+        RuntimeError::Module(ModuleError { module, error }) => {
+            let documentation = async {
+                let client = rpc::connect_opts(extrinsic_opts).await?;
+                rpc::error_documentation(&client, &module, &error).await
+            }
+            .await
+            .unwrap_or_else(|err: anyhow::Error| {
+                log::warn!(
+                    "Failed to look up the documentation of {}.{}: {:#}",
+                    module,
+                    error,
+                    err
+                );
+                None
+            });
+            match documentation {
+                Some(documentation) => {
+                    anyhow::bail!("Extrinsic failed: {}.{}: {}", module, error, documentation)
+                }
+                None => anyhow::bail!("Extrinsic failed: {}.{}", module, error),
+            }
+        }
+        runtime_error => anyhow::bail!("Extrinsic failed: {}", runtime_error),
+    }
+}
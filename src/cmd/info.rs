@@ -0,0 +1,64 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+use sp_core::{crypto::AccountId32, H256};
+
+use crate::cmd::rpc::{self, ContractInfo};
+
+/// Queries the contracts pallet storage for the code stored under `code_hash` and the contract
+/// at `address`, and returns a report of what was found.
+///
+/// Nothing is submitted to the chain. Code or contracts which are not found are reported as
+/// such, not as an error.
+pub fn execute(
+    url: &url::Url,
+    accept_invalid_certs: bool,
+    code_hash: Option<H256>,
+    address: Option<AccountId32>,
+) -> Result<String> {
+    async_std::task::block_on(async move {
+        let client = rpc::connect(url, accept_invalid_certs).await?;
+        let mut report = Vec::new();
+
+        if let Some(code_hash) = code_hash {
+            match rpc::fetch_pristine_code(&client, &code_hash).await? {
+                Some(code) => report.push(format!("Code {:?}: {} bytes", code_hash, code.len())),
+                None => report.push(format!("Code {:?}: code not found", code_hash)),
+            }
+        }
+
+        if let Some(address) = address {
+            match rpc::fetch_contract_info(&client, &address).await? {
+                Some(ContractInfo::Alive(info)) => {
+                    report.push(format!("Contract {}: alive", address));
+                    report.push(format!("  code hash: {:?}", info.code_hash));
+                    report.push(format!("  storage size: {} bytes", info.storage_size));
+                    report.push(format!("  storage items: {}", info.total_pair_count));
+                    report.push(format!("  rent allowance: {}", info.rent_allowance));
+                    report.push(format!("  rent deducted at block: {}", info.deduct_block));
+                }
+                Some(ContractInfo::Tombstone(_)) => report.push(format!(
+                    "Contract {}: evicted, only a tombstone remains",
+                    address
+                )),
+                None => report.push(format!("Contract {}: contract not found", address)),
+            }
+        }
+
+        Ok(report.join("\n"))
+    })
+}
@@ -15,47 +15,456 @@
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
 use anyhow::Result;
+use codec::{Decode, Encode};
+use serde::Serialize;
+use sp_core::{crypto::AccountId32, H256};
+use sp_runtime::DispatchError;
 use subxt::{
-    balances::Balances, contracts::*, system::System, ClientBuilder, ContractsTemplateRuntime,
+    balances::Balances, contracts::*, system::System, ContractsTemplateRuntime, Encoded, RawEvent,
+    RuntimeError, Signer,
 };
 
-use crate::{ExtrinsicOpts, HexData};
+use crate::{
+    cmd::{
+        build,
+        events::{self, ExtrinsicInclusion},
+        offline, retry,
+        rpc::{self, Code},
+    },
+    ExtrinsicOpts, HexData,
+};
+
+/// The `Contracts::instantiate` call of contracts pallet versions which derive the contract
+/// address from a salt, in addition to the code hash and the deploying account.
+#[derive(Encode)]
+struct InstantiateWithSaltCall<'a, T: Contracts> {
+    #[codec(compact)]
+    endowment: <T as Balances>::Balance,
+    #[codec(compact)]
+    gas_limit: Gas,
+    code_hash: &'a <T as System>::Hash,
+    data: &'a [u8],
+    salt: &'a [u8],
+}
+
+impl<'a, T: Contracts> subxt::Call<T> for InstantiateWithSaltCall<'a, T> {
+    const MODULE: &'static str = "Contracts";
+    const FUNCTION: &'static str = "instantiate";
+}
 
-/// Instantiate a contract stored at the supplied code hash.
-/// Returns the account id of the instantiated contract if successful.
+/// The `Contracts::instantiate_with_code` call of newer contracts pallet versions, uploading the
+/// code and instantiating it in a single extrinsic.
+#[derive(Encode)]
+struct InstantiateWithCodeCall<'a, T: Contracts> {
+    #[codec(compact)]
+    endowment: <T as Balances>::Balance,
+    #[codec(compact)]
+    gas_limit: Gas,
+    code: &'a [u8],
+    data: &'a [u8],
+    salt: &'a [u8],
+}
+
+impl<'a, T: Contracts> subxt::Call<T> for InstantiateWithCodeCall<'a, T> {
+    const MODULE: &'static str = "Contracts";
+    const FUNCTION: &'static str = "instantiate_with_code";
+}
+
+/// The result of instantiating a contract.
+#[derive(Debug, Serialize)]
+pub struct InstantiateResult {
+    /// The account id of the instantiated contract, `None` if the extrinsic was not submitted.
+    pub contract: Option<<ContractsTemplateRuntime as System>::AccountId>,
+    /// Where the extrinsic was included, `None` if it was not submitted.
+    #[serde(flatten)]
+    pub inclusion: Option<ExtrinsicInclusion>,
+    /// The hash of the code the contract is instantiated from.
+    pub code_hash: H256,
+    /// The gas limit the extrinsic was signed with.
+    pub gas_limit: u64,
+    /// The signed extrinsic, hex encoded, if it was signed with `--offline` instead of submitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extrinsic: Option<String>,
+}
+
+/// Instantiate a contract from code stored at the supplied code hash, or from wasm code uploaded
+/// in the same extrinsic.
+/// Returns the account id of the instantiated contract if successful, together with where the
+/// extrinsic was included.
+///
+/// If no `gas_limit` is supplied, the gas required is estimated by a dry run of the instantiation
+/// on the node, and increased by `gas_margin` percent.
+///
+/// If a `salt` is supplied it is passed to the call, for contracts pallet versions which use it to
+/// derive the contract address. Otherwise the call without a salt is submitted.
+///
+/// Creates an extrinsic with the `Contracts::instantiate` Call, or with the
+/// `Contracts::instantiate_with_code` Call for [`Code::Upload`], submits via RPC, then waits for
+/// the `ContractsEvent::Instantiated` event. Uploading requires a newer contracts pallet, which
+/// always takes a salt, an empty one if none is supplied. Connecting and estimating the gas are
+/// retried on network errors, the extrinsic is submitted only once.
+///
+/// With `--offline`, the extrinsic is signed and returned hex encoded instead of submitted, and
+/// neither an account id nor an inclusion is returned.
 ///
-/// Creates an extrinsic with the `Contracts::instantiate` Call, submits via RPC, then waits for
-/// the `ContractsEvent::Instantiated` event.
-pub(crate) fn execute_instantiate(
+/// Before submitting, the `endowment` is checked against the existential deposit of the chain,
+/// since the account of a contract endowed with less would be reaped right away. Unless `force`
+/// is set, such an instantiation is rejected, otherwise only a warning is printed. The check is
+/// skipped when signing offline.
+pub fn execute_instantiate(
     extrinsic_opts: &ExtrinsicOpts,
     endowment: <ContractsTemplateRuntime as Balances>::Balance,
-    gas_limit: u64,
-    code_hash: <ContractsTemplateRuntime as System>::Hash,
+    gas_limit: Option<u64>,
+    gas_margin: u64,
+    code: Code,
     data: HexData,
-) -> Result<<ContractsTemplateRuntime as System>::AccountId> {
+    salt: Option<HexData>,
+    force: bool,
+) -> Result<InstantiateResult> {
+    let signer = extrinsic_opts.signer()?;
+    let code_hash = match &code {
+        Code::Existing(code_hash) => *code_hash,
+        Code::Upload(wasm) => H256(build::code_hash(&wasm.0)),
+    };
+
+    if extrinsic_opts.offline.offline {
+        let gas_limit = gas_limit.ok_or_else(|| {
+            anyhow::anyhow!(
+                "--offline requires a --gas limit, it cannot be estimated without a node"
+            )
+        })?;
+        let extrinsic = match (&code, &salt) {
+            (Code::Upload(wasm), salt) => {
+                let call = InstantiateWithCodeCall::<ContractsTemplateRuntime> {
+                    endowment,
+                    gas_limit,
+                    code: &wasm.0,
+                    data: &data.0,
+                    salt: salt.as_ref().map_or(&[][..], |salt| &salt.0[..]),
+                };
+                offline::sign(extrinsic_opts, &signer, call)?
+            }
+            (Code::Existing(_), Some(salt)) => {
+                let call = InstantiateWithSaltCall::<ContractsTemplateRuntime> {
+                    endowment,
+                    gas_limit,
+                    code_hash: &code_hash,
+                    data: &data.0,
+                    salt: &salt.0,
+                };
+                offline::sign(extrinsic_opts, &signer, call)?
+            }
+            (Code::Existing(_), None) => {
+                let call = InstantiateCall::<ContractsTemplateRuntime> {
+                    endowment,
+                    gas_limit,
+                    code_hash: &code_hash,
+                    data: &data.0,
+                };
+                offline::sign(extrinsic_opts, &signer, call)?
+            }
+        };
+        return Ok(InstantiateResult {
+            contract: None,
+            inclusion: None,
+            code_hash,
+            gas_limit,
+            extrinsic: Some(extrinsic),
+        });
+    }
+
     async_std::task::block_on(async move {
-        let cli = ClientBuilder::<ContractsTemplateRuntime>::new()
-            .set_url(&extrinsic_opts.url.to_string())
-            .build()
-            .await?;
-        let signer = extrinsic_opts.signer()?;
-
-        let events = cli
-            .instantiate_and_watch(&signer, endowment, gas_limit, &code_hash, &data.0)
-            .await?;
+        check_endowment(extrinsic_opts, endowment, force).await?;
+        let (signer, code, data, salt) = (&signer, &code, &data, &salt);
+        let cli = retry::with_retries(extrinsic_opts, || rpc::client(extrinsic_opts)).await?;
+
+        let gas_limit = match gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => {
+                let estimated = retry::with_retries(extrinsic_opts, || async move {
+                    let rpc_client = rpc::connect_opts(extrinsic_opts).await?;
+                    rpc::estimate_instantiate_gas(
+                        &rpc_client,
+                        signer.account_id().clone(),
+                        endowment,
+                        code.clone(),
+                        &data.0,
+                        salt.as_ref().map_or(&[][..], |salt| &salt.0[..]),
+                    )
+                    .await
+                })
+                .await?;
+                estimated.saturating_add(estimated.saturating_mul(gas_margin) / 100)
+            }
+        };
+        let reporter = extrinsic_opts.reporter()?;
+        reporter.info(format_args!("Gas limit: {}", gas_limit));
+        if reporter.is_verbose() {
+            let (function, code_arg) = match code {
+                Code::Existing(code_hash) => ("instantiate", format!("code_hash: {:?}", code_hash)),
+                Code::Upload(wasm) => (
+                    "instantiate_with_code",
+                    format!("code: {} bytes", wasm.0.len()),
+                ),
+            };
+            reporter.verbose(format_args!(
+                "Call: Contracts::{} {{ endowment: {}, gas_limit: {}, {}, data: 0x{}, salt: {} }}",
+                function,
+                endowment,
+                gas_limit,
+                code_arg,
+                hex::encode(&data.0),
+                salt.as_ref().map_or("none".to_string(), |salt| format!(
+                    "0x{}",
+                    hex::encode(&salt.0)
+                ))
+            ));
+        }
+
+        let spinner = reporter.spinner("Waiting for the extrinsic to be included");
+        let submission = async {
+            match (code, salt) {
+                (Code::Upload(wasm), salt) => {
+                    let call = InstantiateWithCodeCall::<ContractsTemplateRuntime> {
+                        endowment,
+                        gas_limit,
+                        code: &wasm.0,
+                        data: &data.0,
+                        salt: salt.as_ref().map_or(&[][..], |salt| &salt.0[..]),
+                    };
+                    cli.watch(call, signer).await
+                }
+                (Code::Existing(_), Some(salt)) => {
+                    let call = InstantiateWithSaltCall::<ContractsTemplateRuntime> {
+                        endowment,
+                        gas_limit,
+                        code_hash: &code_hash,
+                        data: &data.0,
+                        salt: &salt.0,
+                    };
+                    cli.watch(call, signer).await
+                }
+                (Code::Existing(_), None) => {
+                    cli.instantiate_and_watch(signer, endowment, gas_limit, &code_hash, &data.0)
+                        .await
+                }
+            }
+        };
+        let result = retry::with_timeout(extrinsic_opts, submission).await?;
+        drop(spinner);
+        let events = events::decode_dispatch_error(extrinsic_opts, result).await?;
+        events::display_events(&reporter, &events);
         let instantiated = events
             .instantiated()?
             .ok_or(anyhow::anyhow!("Failed to find Instantiated event"))?;
+        extrinsic_opts.reporter()?.info(format_args!(
+            "Instantiated contract {} from code hash {:?}, caller {}",
+            instantiated.contract, code_hash, instantiated.caller
+        ));
+        let inclusion = events::inclusion(extrinsic_opts, &events).await?;
+
+        Ok(InstantiateResult {
+            contract: Some(instantiated.contract),
+            inclusion: Some(inclusion),
+            code_hash,
+            gas_limit,
+            extrinsic: None,
+        })
+    })
+}
+
+/// The `Utility::batch` call, dispatching the encoded calls in order until one of them fails.
+#[derive(Encode)]
+struct BatchCall {
+    calls: Vec<Encoded>,
+}
+
+impl<T: System> subxt::Call<T> for BatchCall {
+    const MODULE: &'static str = "Utility";
+    const FUNCTION: &'static str = "batch";
+}
+
+/// A call of a batch which failed, see [`InstantiateBatchResult`].
+#[derive(Debug, Serialize)]
+pub struct BatchFailure {
+    /// The index of the failed call within the batch.
+    pub index: u32,
+    /// The dispatch error of the failed call.
+    pub error: String,
+    /// The number of calls after the failed one, which were not dispatched.
+    pub skipped: u32,
+}
+
+/// The result of instantiating several contracts in a single batch extrinsic.
+#[derive(Debug, Serialize)]
+pub struct InstantiateBatchResult {
+    /// The account ids of the instantiated contracts, in the order of their calls.
+    pub contracts: Vec<<ContractsTemplateRuntime as System>::AccountId>,
+    /// The call which failed, `None` if all contracts were instantiated.
+    pub failure: Option<BatchFailure>,
+    /// Where the extrinsic was included.
+    #[serde(flatten)]
+    pub inclusion: ExtrinsicInclusion,
+}
+
+/// Instantiates `count` contracts from the code stored at `code_hash` in a single
+/// `Utility::batch` extrinsic, returning the account ids of all instantiated contracts.
+///
+/// The contracts pallet derives the address of a contract from the code hash, the deploying
+/// account and the salt, so the instances are told apart by their salt: the index of the
+/// instance, SCALE encoded as `u32`, appended to the supplied `salt`. This requires a contracts
+/// pallet version which takes a salt.
+///
+/// The batch is dispatched even if one of its calls fails, but the calls after the failed one are
+/// skipped. The contracts instantiated before are kept and returned, together with the failure.
+///
+/// The `endowment` of each contract is checked against the existential deposit like with
+/// [`execute_instantiate`]. Signing offline is not supported, since encoding the batched calls
+/// requires the runtime metadata of the node.
+pub fn execute_instantiate_batch(
+    extrinsic_opts: &ExtrinsicOpts,
+    endowment: <ContractsTemplateRuntime as Balances>::Balance,
+    gas_limit: u64,
+    code_hash: H256,
+    data: HexData,
+    salt: Option<HexData>,
+    count: u32,
+    force: bool,
+) -> Result<InstantiateBatchResult> {
+    if count == 0 {
+        anyhow::bail!("The batch must instantiate at least one contract")
+    }
+    if extrinsic_opts.offline.offline {
+        anyhow::bail!("A batch of instantiations cannot be signed with --offline")
+    }
+    let signer = extrinsic_opts.signer()?;
+    let salts = (0..count)
+        .map(|index| {
+            let mut salt = salt.clone().unwrap_or_default().0;
+            salt.extend(index.encode());
+            salt
+        })
+        .collect::<Vec<_>>();
+    extrinsic_opts.reporter()?.verbose(format_args!(
+        "Call: Utility::batch {{ calls: {} x Contracts::instantiate {{ endowment: {}, gas_limit: \
+         {}, code_hash: {:?}, data: 0x{} }} }}",
+        count,
+        endowment,
+        gas_limit,
+        code_hash,
+        hex::encode(&data.0)
+    ));
 
-        Ok(instantiated.contract)
+    async_std::task::block_on(async move {
+        check_endowment(extrinsic_opts, endowment, force).await?;
+        let cli = retry::with_retries(extrinsic_opts, || rpc::client(extrinsic_opts)).await?;
+        let calls = salts
+            .iter()
+            .map(|salt| {
+                cli.encode(InstantiateWithSaltCall::<ContractsTemplateRuntime> {
+                    endowment,
+                    gas_limit,
+                    code_hash: &code_hash,
+                    data: &data.0,
+                    salt,
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let spinner = extrinsic_opts
+            .reporter()?
+            .spinner("Waiting for the extrinsic to be included");
+        let result =
+            retry::with_timeout(extrinsic_opts, cli.watch(BatchCall { calls }, &signer)).await?;
+        drop(spinner);
+        let events = events::decode_dispatch_error(extrinsic_opts, result).await?;
+        events::display_events(&extrinsic_opts.reporter()?, &events);
+        let (contracts, interrupted) = batch_outcome(&events.events)?;
+        let failure = interrupted.map(|(index, error)| BatchFailure {
+            index,
+            error: RuntimeError::from_dispatch(cli.metadata(), error.clone())
+                .map(|error| error.to_string())
+                .unwrap_or_else(|_| format!("{:?}", error)),
+            skipped: count.saturating_sub(index + 1),
+        });
+        let inclusion = events::inclusion(extrinsic_opts, &events).await?;
+        Ok(InstantiateBatchResult {
+            contracts,
+            failure,
+            inclusion,
+        })
     })
 }
 
+/// Returns the contracts instantiated by a batch, in order, and the index and error of the call
+/// which interrupted it, if any.
+fn batch_outcome(events: &[RawEvent]) -> Result<(Vec<AccountId32>, Option<(u32, DispatchError)>)> {
+    let mut contracts = Vec::new();
+    let mut interrupted = None;
+    for event in events {
+        match (event.module.as_str(), event.variant.as_str()) {
+            ("Contracts", "Instantiated") => {
+                let (_caller, contract) =
+                    <(AccountId32, AccountId32)>::decode(&mut &event.data[..])?;
+                contracts.push(contract);
+            }
+            ("Utility", "BatchInterrupted") => {
+                interrupted = Some(<(u32, DispatchError)>::decode(&mut &event.data[..])?);
+            }
+            _ => (),
+        }
+    }
+    Ok((contracts, interrupted))
+}
+
+/// Checks that the `endowment` is at least the existential deposit of the chain, see
+/// [`execute_instantiate`].
+async fn check_endowment(
+    extrinsic_opts: &ExtrinsicOpts,
+    endowment: <ContractsTemplateRuntime as Balances>::Balance,
+    force: bool,
+) -> Result<()> {
+    let client = rpc::connect_opts(extrinsic_opts).await?;
+    let existential_deposit = rpc::existential_deposit(&client).await?;
+    if let Some(warning) = compare_endowment(endowment, existential_deposit, force)? {
+        extrinsic_opts.reporter()?.warning(warning);
+    }
+    Ok(())
+}
+
+/// Rejects an `endowment` below the `existential_deposit`, unless `force` is set. Returns the
+/// warning to print for such an endowment if it is forced.
+fn compare_endowment(
+    endowment: <ContractsTemplateRuntime as Balances>::Balance,
+    existential_deposit: <ContractsTemplateRuntime as Balances>::Balance,
+    force: bool,
+) -> Result<Option<String>> {
+    if endowment >= existential_deposit {
+        return Ok(None);
+    }
+    let message = format!(
+        "The endowment {} is below the existential deposit of the chain, {}, so the account of \
+         the contract would be reaped",
+        endowment, existential_deposit
+    );
+    if !force {
+        anyhow::bail!(
+            "{}. Pass an --endowment of at least {}, or --force to instantiate anyway",
+            message,
+            existential_deposit
+        )
+    }
+    Ok(Some(message))
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs, io::Write};
 
-    use crate::{cmd::deploy::execute_deploy, util::tests::with_tmp_dir, ExtrinsicOpts, HexData};
+    use crate::{
+        cmd::deploy::{execute_deploy, load_contract_code},
+        util::tests::{extrinsic_opts, with_tmp_dir},
+        HexData,
+    };
     use assert_matches::assert_matches;
 
     const CONTRACT: &str = r#"
@@ -65,6 +474,62 @@ mod tests {
 )
 "#;
 
+    #[test]
+    fn batch_outcome_lists_contracts_until_interrupted() {
+        use codec::Encode;
+        use sp_core::crypto::AccountId32;
+        use sp_runtime::DispatchError;
+        use subxt::RawEvent;
+
+        let event = |module: &str, variant: &str, data: Vec<u8>| RawEvent {
+            module: module.into(),
+            variant: variant.into(),
+            data,
+        };
+        let (caller, first, second) = (
+            AccountId32::from([0; 32]),
+            AccountId32::from([1; 32]),
+            AccountId32::from([2; 32]),
+        );
+        let events = vec![
+            event("System", "NewAccount", first.encode()),
+            event("Contracts", "Instantiated", (&caller, &first).encode()),
+            event("Contracts", "Instantiated", (&caller, &second).encode()),
+            event(
+                "Utility",
+                "BatchInterrupted",
+                (2u32, DispatchError::BadOrigin).encode(),
+            ),
+        ];
+
+        let (contracts, interrupted) = super::batch_outcome(&events).unwrap();
+        assert_eq!(contracts, vec![first, second]);
+        assert_eq!(interrupted, Some((2, DispatchError::BadOrigin)));
+
+        let (contracts, interrupted) = super::batch_outcome(&events[..2]).unwrap();
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(interrupted, None);
+    }
+
+    #[test]
+    fn endowment_below_existential_deposit_requires_force() {
+        assert_eq!(super::compare_endowment(100, 100, false).unwrap(), None);
+
+        let err = super::compare_endowment(99, 100, false).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("Pass an --endowment of at least 100, or --force to instantiate anyway"));
+
+        let warning = super::compare_endowment(99, 100, true).unwrap();
+        assert_eq!(
+            warning.as_deref(),
+            Some(
+                "The endowment 99 is below the existential deposit of the chain, 100, so the \
+                 account of the contract would be reaped"
+            )
+        );
+    }
+
     #[test]
     fn instantiate_contract() {
         with_tmp_dir(|path| {
@@ -74,22 +539,22 @@ mod tests {
             let mut file = fs::File::create(&wasm_path).unwrap();
             let _ = file.write_all(&wasm);
 
-            let url = url::Url::parse("ws://localhost:9944").unwrap();
-            let extrinsic_opts = ExtrinsicOpts {
-                url,
-                suri: "//Alice".into(),
-                password: None,
-            };
-            let code_hash =
-                execute_deploy(&extrinsic_opts, Some(&wasm_path)).expect("Deploy should succeed");
+            let extrinsic_opts = extrinsic_opts("ws://localhost:9944", &[]);
+            let code = load_contract_code(&wasm_path)?;
+            let code_hash = execute_deploy(&extrinsic_opts, &code, false, false, None)
+                .expect("Deploy should succeed")
+                .code_hash;
 
             let gas_limit = 500_000_000;
             let result = super::execute_instantiate(
                 &extrinsic_opts,
                 100000000000000,
-                gas_limit,
-                code_hash,
+                Some(gas_limit),
+                0,
+                super::Code::Existing(code_hash),
                 HexData::default(),
+                None,
+                false,
             );
 
             assert_matches!(result, Ok(_));
@@ -0,0 +1,179 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encoding the call data of contracts from their metadata, for the commands instantiating and
+//! calling them.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use sp_core::crypto::AccountId32;
+use structopt::StructOpt;
+
+use crate::{
+    cmd::{
+        registry::{self, MetadataRegistry},
+        MessageKind, Transcoder,
+    },
+    crate_metadata::CrateMetadata,
+    HexData,
+};
+
+/// Arguments for encoding the call data of a contract from its metadata, instead of passing it
+/// hex encoded with --data
+#[derive(Debug, StructOpt)]
+pub struct MessageArgs {
+    /// Name of the constructor or message to call, optionally with its arguments, e.g.
+    /// `new(true, 42)`
+    #[structopt(long)]
+    message: Option<String>,
+    /// An argument of the constructor or message. Can be passed multiple times, in order.
+    /// Requires --message, or --constructor for instantiate
+    #[structopt(long = "arg", number_of_values = 1)]
+    args: Vec<String>,
+    /// Path to the contract metadata, defaults to ./target/metadata.json if --message is passed.
+    /// A contract instantiated from the current project is called with the metadata it was
+    /// instantiated with
+    #[structopt(long, parse(from_os_str))]
+    metadata: Option<PathBuf>,
+}
+
+impl MessageArgs {
+    /// Returns the call data encoded from --message and --arg, falling back to the raw data.
+    pub fn input_data(&self, kind: MessageKind, data: Option<&HexData>) -> Result<HexData> {
+        let message = match (&self.message, data) {
+            (Some(message), _) => message,
+            (None, Some(data)) => return self.raw_data(data),
+            (None, None) => anyhow::bail!("Pass the call data either with --message or --data"),
+        };
+        let transcoder = Transcoder::load(&self.metadata_path_or_default(None)?)?;
+        transcoder.encode(kind, message, &self.args).map(HexData)
+    }
+
+    /// Returns the call data of the constructor selected with --constructor or --message, encoded
+    /// with the arguments passed with --arg, falling back to the raw data.
+    ///
+    /// If neither a constructor nor raw data is passed, the only constructor of the contract is
+    /// called. Contracts with several constructors require selecting one.
+    ///
+    /// The metadata is read from the `.contract` bundle if supplied and --metadata is not passed.
+    pub fn constructor_data(
+        &self,
+        constructor: Option<&str>,
+        data: Option<&HexData>,
+        bundle: Option<&Path>,
+    ) -> Result<HexData> {
+        let constructor = match (constructor.or_else(|| self.message.as_deref()), data) {
+            (Some(constructor), _) => Some(constructor),
+            (None, Some(data)) => return self.raw_data(data),
+            (None, None) => None,
+        };
+        let transcoder = Transcoder::load(&self.metadata_path_or_default(bundle)?)?;
+        let constructor = match constructor {
+            Some(constructor) => constructor.to_string(),
+            None => transcoder.default_constructor()?,
+        };
+        transcoder
+            .encode(MessageKind::Constructor, &constructor, &self.args)
+            .map(HexData)
+    }
+
+    /// Returns the call data of the message encoded with the metadata at `metadata_path`, see
+    /// [`Self::contract_metadata_path`], falling back to the raw data.
+    pub fn call_data(&self, metadata_path: Option<&Path>, data: &HexData) -> Result<HexData> {
+        match (&self.message, metadata_path) {
+            (Some(message), Some(metadata_path)) => Transcoder::load(metadata_path)?
+                .encode(MessageKind::Message, message, &self.args)
+                .map(HexData),
+            _ => self.input_data(MessageKind::Message, Some(data)),
+        }
+    }
+
+    /// Returns the path to the metadata of the `contract`, used to encode and decode calls to it.
+    ///
+    /// Defaults to the metadata registered when the contract was instantiated from the current
+    /// project, else to the metadata of the current project if --message is passed.
+    pub fn contract_metadata_path(&self, contract: &AccountId32) -> Result<Option<PathBuf>> {
+        if self.metadata.is_none() {
+            if let Some(metadata_path) =
+                registry::registered_metadata_path(AsRef::<[u8]>::as_ref(contract))?
+            {
+                return Ok(Some(metadata_path));
+            }
+        }
+        self.metadata_path()
+    }
+
+    /// Registers the metadata the `contract` was instantiated with in the current project, see
+    /// [`Self::contract_metadata_path`].
+    ///
+    /// The metadata is only registered if it was passed or used to encode the constructor, see
+    /// [`Self::constructor_data`], and if it describes the instantiated code, i.e. its source hash
+    /// is `code_hash`. Nothing is registered outside of a project, or if the metadata does not
+    /// exist.
+    pub fn register_contract(
+        &self,
+        contract: &AccountId32,
+        code_hash: &[u8],
+        constructor: Option<&str>,
+        data: Option<&HexData>,
+        bundle: Option<&Path>,
+    ) -> Result<()> {
+        let raw_data = constructor.or_else(|| self.message.as_deref()).is_none() && data.is_some();
+        if raw_data && self.metadata.is_none() && bundle.is_none() {
+            return Ok(());
+        }
+        let crate_metadata = match CrateMetadata::collect(&Default::default()) {
+            Ok(crate_metadata) => crate_metadata,
+            Err(_) => return Ok(()),
+        };
+        let metadata_path = self.metadata_path_or_default(bundle)?;
+        if !metadata_path.exists() || !registry::matches_code_hash(&metadata_path, code_hash)? {
+            return Ok(());
+        }
+        let target_dir = &crate_metadata.target_directory;
+        let mut registry = MetadataRegistry::load(target_dir)?;
+        registry.register(AsRef::<[u8]>::as_ref(contract), &metadata_path)?;
+        registry.store(target_dir)
+    }
+
+    fn raw_data(&self, data: &HexData) -> Result<HexData> {
+        if !self.args.is_empty() {
+            anyhow::bail!("--arg cannot be combined with --data, pass the arguments in the data")
+        }
+        Ok(data.clone())
+    }
+
+    /// Returns the path to the contract metadata, if it is used to encode or decode the call.
+    pub fn metadata_path(&self) -> Result<Option<PathBuf>> {
+        match (&self.metadata, &self.message) {
+            (None, None) => Ok(None),
+            _ => self.metadata_path_or_default(None).map(Some),
+        }
+    }
+
+    /// Returns the path to the contract metadata, defaulting to the `bundle` if supplied, else to
+    /// the metadata of the current project.
+    fn metadata_path_or_default(&self, bundle: Option<&Path>) -> Result<PathBuf> {
+        match (&self.metadata, bundle) {
+            (Some(metadata_path), _) => Ok(metadata_path.clone()),
+            (None, Some(bundle)) => Ok(bundle.to_path_buf()),
+            (None, None) => Ok(CrateMetadata::collect(&Default::default())?
+                .target_directory
+                .join("metadata.json")),
+        }
+    }
+}
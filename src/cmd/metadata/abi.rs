@@ -0,0 +1,316 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A flat, Solidity-like ABI of the contract, derived from the ink! metadata.
+//!
+//! The ABI is an array of entries, one per constructor, message and event:
+//!
+//! ```json
+//! [
+//!   { "type": "constructor", "name": "new", "selector": "0xd183512b",
+//!     "inputs": [{ "name": "init_value", "type": "bool" }], "stateMutability": "payable" },
+//!   { "type": "function", "name": "get", "selector": "0x1e5ca456",
+//!     "inputs": [], "outputs": [{ "type": "bool" }], "stateMutability": "view",
+//!     "docs": "Simply returns the current value of our `bool`." },
+//!   { "type": "event", "name": "Flipped",
+//!     "inputs": [{ "name": "value", "type": "bool", "indexed": false }] }
+//! ]
+//! ```
+//!
+//! Entries which are documented in the contract source carry their doc comment as `docs`.
+//!
+//! It only describes the interface of the contract. The ink! metadata remains the source of truth,
+//! e.g. for the layout of the storage and the SCALE encoding of composite types.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+/// Transforms the ink! metadata, as produced by the metadata generation, into the flat ABI.
+pub(crate) fn from_ink_metadata(ink_meta: &Map<String, Value>) -> Result<Value> {
+    let spec = ink_meta
+        .get("spec")
+        .ok_or_else(|| anyhow::anyhow!("No contract spec found in the ink! metadata"))?;
+    let types = ink_meta
+        .get("types")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut abi = Vec::new();
+    for constructor in entries(spec, "constructors") {
+        abi.push(with_docs(
+            json!({
+                "type": "constructor",
+                "name": name(constructor),
+                "selector": constructor["selector"],
+                "inputs": inputs(types, constructor),
+                "stateMutability": "payable",
+            }),
+            constructor,
+        ));
+    }
+    for message in entries(spec, "messages") {
+        let outputs = match message
+            .get("returnType")
+            .or_else(|| message.get("return_type"))
+        {
+            Some(return_type) if !return_type.is_null() => {
+                vec![json!({ "type": type_name(types, return_type) })]
+            }
+            _ => Vec::new(),
+        };
+        let mutability = if message["payable"].as_bool() == Some(true) {
+            "payable"
+        } else if message["mutates"].as_bool() == Some(false) {
+            "view"
+        } else {
+            "nonpayable"
+        };
+        abi.push(with_docs(
+            json!({
+                "type": "function",
+                "name": name(message),
+                "selector": message["selector"],
+                "inputs": inputs(types, message),
+                "outputs": outputs,
+                "stateMutability": mutability,
+            }),
+            message,
+        ));
+    }
+    for event in entries(spec, "events") {
+        let inputs = args(event)
+            .iter()
+            .map(|arg| {
+                json!({
+                    "name": name(arg),
+                    "type": type_name(types, &arg["type"]),
+                    "indexed": arg["indexed"].as_bool().unwrap_or(false),
+                })
+            })
+            .collect::<Vec<_>>();
+        abi.push(with_docs(
+            json!({
+                "type": "event",
+                "name": name(event),
+                "inputs": inputs,
+            }),
+            event,
+        ));
+    }
+    Ok(Value::Array(abi))
+}
+
+fn entries<'a>(spec: &'a Value, key: &str) -> &'a [Value] {
+    spec[key].as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn args(entry: &Value) -> &[Value] {
+    entry["args"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn inputs(types: &[Value], entry: &Value) -> Vec<Value> {
+    args(entry)
+        .iter()
+        .map(|arg| json!({ "name": name(arg), "type": type_name(types, &arg["type"]) }))
+        .collect()
+}
+
+/// Adds the doc comment of the ink! metadata `entry` to the ABI entry, if it is documented.
+fn with_docs(mut abi_entry: Value, entry: &Value) -> Value {
+    if let Some(docs) = docs(entry) {
+        abi_entry["docs"] = Value::String(docs);
+    }
+    abi_entry
+}
+
+/// Returns the doc comment of an entry, one line per `///` line of the source.
+///
+/// The metadata keeps the space following the `///`, which is stripped. Blank lines separating
+/// paragraphs are kept, leading and trailing ones are not.
+fn docs(entry: &Value) -> Option<String> {
+    let lines = entry["docs"]
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end())
+        .collect::<Vec<_>>();
+    Some(lines.join("\n").trim().to_string()).filter(|docs| !docs.is_empty())
+}
+
+/// Returns the name of an entry, which older metadata versions give as a path.
+fn name(entry: &Value) -> String {
+    match &entry["name"] {
+        Value::String(name) => name.clone(),
+        Value::Array(path) => path
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("::"),
+        _ => String::new(),
+    }
+}
+
+/// Returns the name of a type, preferring the name it is referred to by in the contract source.
+///
+/// Types without a display name are looked up in the type registry, falling back to `unknown`.
+fn type_name(types: &[Value], ty: &Value) -> String {
+    let display_name = ty["displayName"]
+        .as_array()
+        .or_else(|| ty["display_name"].as_array())
+        .map(|path| {
+            path.iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("::")
+        })
+        .filter(|display_name| !display_name.is_empty());
+    if let Some(display_name) = display_name {
+        return display_name;
+    }
+    let type_id = ty.as_u64().or_else(|| ty["type"].as_u64());
+    // registries with explicit ids are 0-based, older ones are referenced by 1-based index
+    let registry_type = type_id.and_then(|type_id| {
+        types
+            .iter()
+            .find(|ty| ty["id"].as_u64() == Some(type_id))
+            .map(|ty| &ty["type"])
+            .or_else(|| {
+                (type_id as usize)
+                    .checked_sub(1)
+                    .and_then(|index| types.get(index))
+            })
+    });
+    registry_type
+        .and_then(|ty| {
+            ty["def"]["primitive"]
+                .as_str()
+                .map(ToString::to_string)
+                .or_else(|| {
+                    ty["path"]
+                        .as_array()
+                        .and_then(|path| path.last())
+                        .and_then(Value::as_str)
+                        .map(ToString::to_string)
+                })
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Map, Value};
+
+    use super::from_ink_metadata;
+
+    fn ink_metadata() -> Map<String, Value> {
+        let metadata = json!({
+            "spec": {
+                "constructors": [{
+                    "name": ["new"],
+                    "selector": "0xd183512b",
+                    "args": [{ "name": "init_value", "type": { "type": 1, "displayName": ["bool"] } }],
+                }],
+                "messages": [
+                    {
+                        "name": ["flip"],
+                        "selector": "0x633aa551",
+                        "mutates": true,
+                        "payable": false,
+                        "args": [],
+                        "returnType": null,
+                    },
+                    {
+                        "name": ["get"],
+                        "selector": "0x1e5ca456",
+                        "mutates": false,
+                        "payable": false,
+                        "args": [],
+                        "returnType": { "type": 1, "displayName": [] },
+                    },
+                ],
+                "events": [{
+                    "name": ["Flipped"],
+                    "args": [{ "name": "value", "type": { "type": 1, "displayName": ["bool"] }, "indexed": true }],
+                }],
+            },
+            "types": [{ "def": { "primitive": "bool" } }],
+        });
+        match metadata {
+            Value::Object(metadata) => metadata,
+            _ => unreachable!("the metadata is an object; qed"),
+        }
+    }
+
+    #[test]
+    fn abi_lists_constructors_messages_and_events() {
+        let abi = from_ink_metadata(&ink_metadata()).unwrap();
+        let expected = json!([
+            {
+                "type": "constructor",
+                "name": "new",
+                "selector": "0xd183512b",
+                "inputs": [{ "name": "init_value", "type": "bool" }],
+                "stateMutability": "payable",
+            },
+            {
+                "type": "function",
+                "name": "flip",
+                "selector": "0x633aa551",
+                "inputs": [],
+                "outputs": [],
+                "stateMutability": "nonpayable",
+            },
+            {
+                "type": "function",
+                "name": "get",
+                "selector": "0x1e5ca456",
+                "inputs": [],
+                "outputs": [{ "type": "bool" }],
+                "stateMutability": "view",
+            },
+            {
+                "type": "event",
+                "name": "Flipped",
+                "inputs": [{ "name": "value", "type": "bool", "indexed": true }],
+            },
+        ]);
+        assert_eq!(abi, expected);
+    }
+
+    #[test]
+    fn abi_entries_carry_the_docs_of_the_source() {
+        let mut metadata = ink_metadata();
+        let spec = metadata.get_mut("spec").unwrap();
+        spec["constructors"][0]["docs"] = json!([" Creates a new flipper.", ""]);
+        spec["messages"][0]["docs"] = json!([" Flips the value.", "", " Returns nothing.  ",]);
+        spec["messages"][1]["docs"] = json!([]);
+
+        let abi = from_ink_metadata(&metadata).unwrap();
+        assert_eq!(abi[0]["docs"], "Creates a new flipper.");
+        assert_eq!(abi[1]["docs"], "Flips the value.\n\nReturns nothing.");
+        assert!(abi[2].get("docs").is_none());
+        assert!(abi[3].get("docs").is_none());
+    }
+
+    #[test]
+    fn missing_spec_is_an_error() {
+        assert!(from_ink_metadata(&Map::new()).is_err());
+    }
+}
@@ -14,13 +14,51 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use core::fmt::{Display, Formatter, Result as DisplayResult, Write};
+use core::{
+    fmt::{Display, Formatter, Result as DisplayResult, Write},
+    str::FromStr,
+};
 use semver::Version;
 use serde::{Serialize, Serializer};
 use serde_json::{Map, Value};
 use url::Url;
 
-const METADATA_VERSION: &str = "0.1.0";
+/// The versions of the metadata format which can be generated, the latest one last.
+const METADATA_VERSIONS: [&str; 1] = ["0.1.0"];
+
+/// A version of the metadata format supported by the generator, defaults to the latest one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetadataVersion(Version);
+
+impl Default for MetadataVersion {
+    fn default() -> Self {
+        let latest = METADATA_VERSIONS[METADATA_VERSIONS.len() - 1];
+        MetadataVersion(Version::parse(latest).expect("METADATA_VERSIONS are valid semver; qed"))
+    }
+}
+
+impl FromStr for MetadataVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> anyhow::Result<Self> {
+        match Version::parse(input) {
+            Ok(version) if METADATA_VERSIONS.contains(&version.to_string().as_str()) => {
+                Ok(MetadataVersion(version))
+            }
+            _ => anyhow::bail!(
+                "Unsupported metadata version '{}', supported versions are: {}",
+                input,
+                METADATA_VERSIONS.join(", ")
+            ),
+        }
+    }
+}
+
+impl Display for MetadataVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        write!(f, "{}", self.0)
+    }
+}
 
 /// An entire ink! project for metadata file generation purposes.
 #[derive(Debug, Serialize)]
@@ -38,22 +76,26 @@ pub struct ContractMetadata {
 impl ContractMetadata {
     /// Construct new contract metadata
     pub fn new(
+        metadata_version: MetadataVersion,
         source: Source,
         contract: Contract,
         user: Option<User>,
         ink: Map<String, Value>,
     ) -> Self {
-        let metadata_version = semver::Version::parse(METADATA_VERSION)
-            .expect("METADATA_VERSION is a valid semver string");
-
         Self {
-            metadata_version,
+            metadata_version: metadata_version.0,
             source,
             contract,
             user,
             ink,
         }
     }
+
+    /// Embeds the contract wasm code into the metadata, turning it into a `.contract` bundle.
+    pub fn with_wasm(mut self, wasm: Vec<u8>) -> Self {
+        self.source.wasm = Some(SourceWasm(wasm));
+        self
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -62,6 +104,9 @@ pub struct Source {
     hash: [u8; 32],
     language: SourceLanguage,
     compiler: SourceCompiler,
+    /// The contract wasm code, only present in `.contract` bundles
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wasm: Option<SourceWasm>,
 }
 
 impl Source {
@@ -71,10 +116,24 @@ impl Source {
             hash,
             language,
             compiler,
+            wasm: None,
         }
     }
 }
 
+/// The wasm code of a contract, serialized as a hex encoded string.
+#[derive(Debug)]
+pub struct SourceWasm(Vec<u8>);
+
+impl Serialize for SourceWasm {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_as_byte_str(&self.0, serializer)
+    }
+}
+
 /// The language and version in which a smart contract is written.
 #[derive(Debug)]
 pub struct SourceLanguage {
@@ -239,3 +298,28 @@ where
     }
     serializer.serialize_str(&hex)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MetadataVersion;
+
+    #[test]
+    fn default_metadata_version_is_the_latest() {
+        let version: MetadataVersion = "0.1.0".parse().unwrap();
+        assert_eq!(version, MetadataVersion::default());
+        assert_eq!(version.to_string(), "0.1.0");
+    }
+
+    #[test]
+    fn unsupported_metadata_version_lists_supported_ones() {
+        for input in &["0.2.0", "1"] {
+            assert_eq!(
+                input.parse::<MetadataVersion>().unwrap_err().to_string(),
+                format!(
+                    "Unsupported metadata version '{}', supported versions are: 0.1.0",
+                    input
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A fingerprint of the inputs of the metadata generation, to skip it if nothing changed.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use blake2::digest::{Update as _, VariableOutput as _};
+
+use super::MetadataVersion;
+use crate::crate_metadata::CrateMetadata;
+
+/// Returns the path the fingerprint of the last generation of the metadata at `metadata_path` is
+/// stored at, next to the metadata. Each output path thus has its own fingerprint.
+pub(crate) fn path(metadata_path: &Path) -> PathBuf {
+    metadata_path.with_extension("fingerprint")
+}
+
+/// Computes the fingerprint of the inputs of the metadata generation, hex encoded.
+///
+/// It covers the contents of the files of the contract package, excluding the target directory
+/// and hidden directories, the wasm of the contract, and the versions of the toolchain and of
+/// cargo-contract, and the requested metadata version. Only the paths and contents of the files
+/// are hashed, not their timestamps.
+pub(crate) fn compute(
+    crate_metadata: &CrateMetadata,
+    original_manifest: bool,
+    metadata_version: &MetadataVersion,
+) -> Result<String> {
+    let package_dir = crate_metadata
+        .manifest_path
+        .directory()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let mut hasher = blake2::VarBlake2b::new_keyed(&[], 32);
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update(rustc_version::version()?.to_string());
+    hasher.update([original_manifest as u8]);
+    hasher.update(metadata_version.to_string());
+
+    let mut files = Vec::new();
+    collect_files(&package_dir, &crate_metadata.target_directory, &mut files)?;
+    files.sort();
+    for file in files {
+        let relative = file.strip_prefix(&package_dir).unwrap_or(&file);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update(fs::read(&file).context(format!("Reading {}", file.display()))?);
+    }
+    if let Ok(wasm) = fs::read(&crate_metadata.dest_wasm) {
+        hasher.update(wasm);
+    }
+
+    let mut fingerprint = String::new();
+    hasher.finalize_variable(|hash| {
+        fingerprint = hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+    });
+    Ok(fingerprint)
+}
+
+/// Returns the fingerprint stored by the last generation of the metadata at `metadata_path`, if
+/// any.
+pub(crate) fn load(metadata_path: &Path) -> Option<String> {
+    fs::read_to_string(path(metadata_path))
+        .ok()
+        .map(|fingerprint| fingerprint.trim().to_string())
+}
+
+/// Stores the fingerprint of a completed generation of the metadata at `metadata_path`.
+pub(crate) fn store(metadata_path: &Path, fingerprint: &str) -> Result<()> {
+    let path = path(metadata_path);
+    fs::write(&path, fingerprint).context(format!("Writing {}", path.display()))
+}
+
+/// Collects the files in `dir` recursively, skipping `target_directory` and hidden entries.
+pub(crate) fn collect_files(
+    dir: &Path,
+    target_directory: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir).context(format!("Reading directory {}", dir.display()))? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .map_or(false, |name| name.to_string_lossy().starts_with('.'));
+        if hidden || path == target_directory || path.ends_with("target") {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, target_directory, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{collect_files, load, store};
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn collect_files_skips_target_and_hidden_entries() {
+        with_tmp_dir(|path| {
+            fs::create_dir_all(path.join("src"))?;
+            fs::create_dir_all(path.join("target"))?;
+            fs::create_dir_all(path.join(".git"))?;
+            fs::write(path.join("Cargo.toml"), "")?;
+            fs::write(path.join("src").join("lib.rs"), "")?;
+            fs::write(path.join("target").join("contract.wasm"), "")?;
+            fs::write(path.join(".git").join("HEAD"), "")?;
+            fs::write(path.join(".gitignore"), "")?;
+
+            let mut files = Vec::new();
+            collect_files(path, &path.join("target"), &mut files)?;
+            files.sort();
+
+            assert_eq!(
+                files,
+                vec![path.join("Cargo.toml"), path.join("src").join("lib.rs")]
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn fingerprints_are_stored_per_output_path() {
+        with_tmp_dir(|path| {
+            let metadata = path.join("metadata.json");
+            let other = path.join("dist").join("other.json");
+            fs::create_dir_all(path.join("dist"))?;
+
+            store(&metadata, "first")?;
+            assert_eq!(load(&other), None);
+            store(&other, "second")?;
+            assert_eq!(load(&metadata).as_deref(), Some("first"));
+            assert_eq!(load(&other).as_deref(), Some("second"));
+            Ok(())
+        })
+    }
+}
@@ -14,39 +14,104 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+mod abi;
 mod contract;
+pub(crate) mod fingerprint;
 
 use crate::{
+    cmd::build::OptimizationPasses,
     crate_metadata::CrateMetadata,
+    reporter::Reporter,
     util,
     workspace::{ManifestPath, Workspace},
-    UnstableFlags, Verbosity,
+    Features, UnstableFlags, Verbosity,
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use contract::{
     Compiler, Contract, ContractMetadata, Language, Source, SourceCompiler, SourceLanguage, User,
 };
 use semver::Version;
-use std::{fs, path::PathBuf};
+use std::{
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
 use url::Url;
 
-const METADATA_FILE: &str = "metadata.json";
+pub use contract::MetadataVersion;
+
+pub const METADATA_FILE: &str = "metadata.json";
+
+/// Paths of the files written by the metadata generation.
+pub struct MetadataResult {
+    /// The metadata of the contract.
+    pub metadata_file: PathBuf,
+    /// The `.contract` bundle, containing both the metadata and the wasm code of the contract.
+    pub bundle_file: PathBuf,
+    /// The flat ABI of the contract, if requested with `--abi`.
+    pub abi_file: Option<PathBuf>,
+}
 
 /// Executes the metadata generation process
 struct GenerateMetadataCommand {
     crate_metadata: CrateMetadata,
     verbosity: Option<Verbosity>,
     unstable_options: UnstableFlags,
+    force: bool,
+    /// Whether the wasm is bundled as produced by cargo, without post processing and optimization.
+    skip_optimization: bool,
+    /// Whether to additionally write the flat ABI of the contract.
+    abi: bool,
+    /// The version of the metadata format to generate.
+    metadata_version: MetadataVersion,
+    /// The directory the metadata and the bundle are written to.
+    output_dir: PathBuf,
+    /// The file name of the metadata, the bundle is named after its stem.
+    metadata_file_name: String,
 }
 
 impl GenerateMetadataCommand {
-    pub fn exec(&self) -> Result<PathBuf> {
+    pub fn exec(&self) -> Result<MetadataResult> {
         util::assert_channel()?;
-        println!("  Generating metadata");
 
+        let reporter = Reporter::new(self.verbosity);
         let cargo_meta = &self.crate_metadata.cargo_meta;
-        let out_path = cargo_meta.target_directory.join(METADATA_FILE);
-        let target_dir = cargo_meta.target_directory.clone();
+        let out_path = self.output_dir.join(&self.metadata_file_name);
+        let bundle_name = if self.metadata_file_name == METADATA_FILE {
+            self.crate_metadata.package_name.clone()
+        } else {
+            Path::new(&self.metadata_file_name)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .expect("the metadata file name is validated to be a file name; qed")
+        };
+        let bundle_path = self.output_dir.join(format!("{}.contract", bundle_name));
+        let abi_path = self.output_dir.join(abi_file_name(&bundle_name));
+        let target_dir = self.crate_metadata.target_directory.clone();
+
+        let original_manifest = self.unstable_options.original_manifest;
+        let up_to_date = !self.force
+            && out_path.exists()
+            && bundle_path.exists()
+            && (!self.abi || abi_path.exists())
+            && fingerprint::load(&out_path)
+                == Some(fingerprint::compute(
+                    &self.crate_metadata,
+                    original_manifest,
+                    &self.metadata_version,
+                )?);
+        if up_to_date {
+            reporter.info(
+                "  Metadata is up to date, skipping generation. Pass --force to regenerate it",
+            );
+            return Ok(MetadataResult {
+                metadata_file: out_path,
+                bundle_file: bundle_path,
+                abi_file: Some(abi_path).filter(|_| self.abi),
+            });
+        }
+        reporter.info("  Generating metadata");
 
         // build the extended contract project metadata
         let (source_meta, contract_meta, user_meta) = self.extended_metadata()?;
@@ -66,11 +131,29 @@ impl GenerateMetadataCommand {
                 self.verbosity,
             )?;
 
+            // the `docs` of constructors, messages and events are collected from the doc
+            // comments when the ink! macros are expanded, so they are part of the metadata
+            // regardless of the profile, and are passed through unaltered
             let ink_meta: serde_json::Map<String, serde_json::Value> =
                 serde_json::from_slice(&stdout)?;
-            let metadata = ContractMetadata::new(source_meta, contract_meta, user_meta, ink_meta);
+            if self.abi {
+                let abi = abi::from_ink_metadata(&ink_meta)?;
+                fs::write(&abi_path, serde_json::to_string_pretty(&abi)?)?;
+            }
+            let metadata = ContractMetadata::new(
+                self.metadata_version.clone(),
+                source_meta,
+                contract_meta,
+                user_meta,
+                ink_meta,
+            );
             let contents = serde_json::to_string_pretty(&metadata)?;
             fs::write(&out_path, contents)?;
+
+            let wasm = fs::read(&self.crate_metadata.dest_wasm)?;
+            let bundle = metadata.with_wasm(wasm);
+            let contents = serde_json::to_string(&bundle)?;
+            fs::write(&bundle_path, contents)?;
             Ok(())
         };
 
@@ -79,16 +162,30 @@ impl GenerateMetadataCommand {
         } else {
             Workspace::new(&cargo_meta, &self.crate_metadata.root_package.id)?
                 .with_root_package_manifest(|manifest| {
-                    manifest
-                        .with_added_crate_type("rlib")?
-                        .with_profile_release_lto(false)?;
+                    manifest.with_added_crate_type("rlib")?;
+                    Ok(())
+                })?
+                .with_workspace_manifest(|manifest| {
+                    manifest.with_profile_release_lto(false)?;
                     Ok(())
                 })?
                 .with_metadata_gen_package()?
                 .using_temp(generate_metadata)?;
         }
 
-        Ok(out_path)
+        // the wasm is rebuilt as part of the generation, so it is fingerprinted afterwards
+        let fingerprint = fingerprint::compute(
+            &self.crate_metadata,
+            original_manifest,
+            &self.metadata_version,
+        )?;
+        fingerprint::store(&out_path, &fingerprint)?;
+
+        Ok(MetadataResult {
+            metadata_file: out_path,
+            bundle_file: bundle_path,
+            abi_file: Some(abi_path).filter(|_| self.abi),
+        })
     }
 
     /// Generate the extended contract project metadata
@@ -140,44 +237,117 @@ impl GenerateMetadataCommand {
         super::build::execute_with_metadata(
             &self.crate_metadata,
             self.verbosity,
+            false,
+            if self.skip_optimization {
+                OptimizationPasses::Zero
+            } else {
+                OptimizationPasses::default()
+            },
+            self.force,
+            Default::default(),
+            Default::default(),
             self.unstable_options.clone(),
+            &Features::default(),
         )?;
 
         let wasm = fs::read(&self.crate_metadata.dest_wasm)?;
-
-        use ::blake2::digest::{Update as _, VariableOutput as _};
-        let mut output = [0u8; 32];
-        let mut blake2 = blake2::VarBlake2b::new_keyed(&[], 32);
-        blake2.update(wasm);
-        blake2.finalize_variable(|result| output.copy_from_slice(result));
-        Ok(output)
+        Ok(super::build::code_hash(&wasm))
     }
 }
 
 /// Generates a file with metadata describing the ABI of the smart-contract.
 ///
 /// It does so by generating and invoking a temporary workspace member.
-pub(crate) fn execute(
+///
+/// The generation is skipped if neither the contract sources nor its wasm changed since the
+/// metadata was last generated, unless `force` is set.
+///
+/// The metadata is written to `output_dir` as `name`, defaulting to `metadata.json` in the target
+/// directory. A relative `output_dir` is resolved against the directory of the manifest, and
+/// created if it does not exist. With a custom `name` the bundle is named after its stem, so that
+/// the metadata of several versions can be kept side by side.
+///
+/// With `abi` a flat, Solidity-like ABI of the constructors, messages and events is written as
+/// well, named after the bundle with the extension `.abi.json`.
+///
+/// With `skip_optimization` the raw wasm produced by cargo is hashed and bundled, see
+/// [`build::raw_crate_metadata`](super::build::raw_crate_metadata).
+pub fn execute(
     manifest_path: ManifestPath,
     verbosity: Option<Verbosity>,
+    target_dir: Option<&Path>,
     unstable_options: UnstableFlags,
-) -> Result<PathBuf> {
-    let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+    force: bool,
+    skip_optimization: bool,
+    abi: bool,
+    output_dir: Option<&Path>,
+    name: Option<&str>,
+    metadata_version: MetadataVersion,
+) -> Result<MetadataResult> {
+    let mut crate_metadata = CrateMetadata::collect(&manifest_path)?;
+    if let Some(target_dir) = target_dir {
+        crate_metadata = crate_metadata.with_target_directory(target_dir)?;
+    }
+    if skip_optimization {
+        crate_metadata = super::build::raw_crate_metadata(&crate_metadata);
+    }
+    let output_dir = match output_dir {
+        Some(output_dir) => resolve_output_dir(&manifest_path, output_dir)?,
+        None => crate_metadata.target_directory.clone(),
+    };
+    let metadata_file_name = match name {
+        Some(name) => validate_file_name(name)?,
+        None => METADATA_FILE.to_string(),
+    };
     GenerateMetadataCommand {
         crate_metadata,
         verbosity,
         unstable_options,
+        force,
+        skip_optimization,
+        abi,
+        output_dir,
+        metadata_file_name,
+        metadata_version,
     }
     .exec()
 }
 
+/// Returns the file name of the flat ABI written next to the bundle named `bundle_name`.
+pub(crate) fn abi_file_name(bundle_name: &str) -> String {
+    format!("{}.abi.json", bundle_name)
+}
+
+/// Resolves the output directory against the directory of the manifest, and creates it.
+fn resolve_output_dir(manifest_path: &ManifestPath, output_dir: &Path) -> Result<PathBuf> {
+    let manifest_dir = manifest_path.directory().unwrap_or_else(|| Path::new("."));
+    let output_dir = env::current_dir()?.join(manifest_dir).join(output_dir);
+    fs::create_dir_all(&output_dir).context(format!(
+        "Creating output directory {}",
+        output_dir.display()
+    ))?;
+    Ok(output_dir.canonicalize()?)
+}
+
+/// Checks that the name of the metadata file is a plain file name, not a path.
+fn validate_file_name(name: &str) -> Result<String> {
+    match Path::new(name).file_name() {
+        Some(file_name) if file_name == OsStr::new(name) => Ok(name.to_string()),
+        _ => anyhow::bail!(
+            "Invalid metadata file name '{}', expected a file name without a directory. Use \
+            --output-dir to choose the directory",
+            name
+        ),
+    }
+}
+
 #[cfg(feature = "test-ci-only")]
 #[cfg(test)]
 mod tests {
     use crate::{
         cmd::{self, metadata::contract::*},
         crate_metadata::CrateMetadata,
-        util::tests::with_tmp_dir,
+        util::tests::{new_project, with_tmp_dir},
         workspace::ManifestPath,
         UnstableFlags,
     };
@@ -253,9 +423,8 @@ mod tests {
     fn generate_metadata() {
         env_logger::try_init().ok();
         with_tmp_dir(|path| {
-            cmd::new::execute("new_project", Some(path)).expect("new project creation failed");
+            let manifest_path = new_project(path);
             let working_dir = path.join("new_project");
-            let manifest_path = ManifestPath::new(working_dir.join("Cargo.toml"))?;
 
             // add optional metadata fields
             let mut test_manifest = TestContractManifest::new(manifest_path)?;
@@ -273,9 +442,20 @@ mod tests {
             test_manifest.write()?;
 
             let crate_metadata = CrateMetadata::collect(&test_manifest.manifest_path)?;
-            let metadata_file =
-                cmd::metadata::execute(test_manifest.manifest_path, None, UnstableFlags::default())
-                    .expect("generate metadata failed");
+            let metadata_result = cmd::metadata::execute(
+                test_manifest.manifest_path,
+                None,
+                None,
+                UnstableFlags::default(),
+                false,
+                false,
+                false,
+                None,
+                None,
+                Default::default(),
+            )
+            .expect("generate metadata failed");
+            let metadata_file = metadata_result.metadata_file;
             let metadata_json: Map<String, Value> =
                 serde_json::from_slice(&fs::read(&metadata_file)?)?;
 
@@ -318,6 +498,11 @@ mod tests {
 
             // calculate wasm hash
             let wasm = fs::read(&crate_metadata.dest_wasm)?;
+            let mut expected_wasm = String::new();
+            write!(expected_wasm, "0x").expect("failed writing to string");
+            for byte in &wasm {
+                write!(expected_wasm, "{:02x}", byte).expect("failed writing to string");
+            }
             let mut output = [0u8; 32];
             let mut blake2 = blake2::VarBlake2b::new_keyed(&[], 32);
             blake2.update(wasm);
@@ -359,8 +544,121 @@ mod tests {
             assert_eq!("http://homepage.com/", homepage.as_str().unwrap());
             assert_eq!("Apache-2.0", license.as_str().unwrap());
             assert_eq!(&expected_user_metadata, user.as_object().unwrap());
+            assert!(source.get("wasm").is_none());
+
+            // the bundle additionally contains the wasm code
+            let bundle_json: Map<String, Value> =
+                serde_json::from_slice(&fs::read(&metadata_result.bundle_file)?)?;
+            let bundle_source = bundle_json.get("source").expect("source not found");
+            let bundle_wasm = bundle_source
+                .get("wasm")
+                .expect("source.wasm not found")
+                .as_str()
+                .unwrap();
+            assert_eq!(expected_wasm, bundle_wasm);
+            assert_eq!(
+                expected_hash,
+                bundle_source.get("hash").unwrap().as_str().unwrap()
+            );
+            assert_eq!(metadata_json.get("spec"), bundle_json.get("spec"));
 
             Ok(())
         })
     }
+
+    #[test]
+    fn generate_metadata_preserves_docs_of_messages() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+
+            let metadata_result = cmd::metadata::execute(
+                manifest_path,
+                None,
+                None,
+                UnstableFlags::default(),
+                false,
+                false,
+                true,
+                None,
+                None,
+                Default::default(),
+            )
+            .expect("generate metadata failed");
+
+            let metadata: Value =
+                serde_json::from_slice(&fs::read(&metadata_result.metadata_file)?)?;
+            let flip = metadata["spec"]["messages"]
+                .as_array()
+                .expect("the messages are an array")
+                .iter()
+                .find(|message| message["name"] == serde_json::json!(["flip"]))
+                .expect("the flip message is in the metadata");
+            let docs = flip["docs"]
+                .as_array()
+                .expect("the docs are an array")
+                .iter()
+                .map(|line| line.as_str().expect("a doc line is a string"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            assert!(docs.contains("This one flips the value of the stored `bool`"));
+
+            let abi_file = metadata_result.abi_file.expect("the abi was requested");
+            let abi: Value = serde_json::from_slice(&fs::read(&abi_file)?)?;
+            let flip = abi
+                .as_array()
+                .expect("the abi is an array")
+                .iter()
+                .find(|entry| entry["name"] == "flip")
+                .expect("the flip message is in the abi");
+            assert!(flip["docs"]
+                .as_str()
+                .expect("the flip message is documented")
+                .starts_with("A message that can be called on instantiated contracts."));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn generate_metadata_to_output_dir_with_name_and_abi() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            let working_dir = path.join("new_project");
+
+            let metadata_result = cmd::metadata::execute(
+                manifest_path,
+                None,
+                None,
+                UnstableFlags::default(),
+                false,
+                false,
+                true,
+                Some(std::path::Path::new("metadata/v2")),
+                Some("runtime-v2.json"),
+                Default::default(),
+            )
+            .expect("generate metadata failed");
+
+            let output_dir = working_dir.join("metadata").join("v2").canonicalize()?;
+            assert_eq!(
+                metadata_result.metadata_file,
+                output_dir.join("runtime-v2.json")
+            );
+            assert_eq!(
+                metadata_result.bundle_file,
+                output_dir.join("runtime-v2.contract")
+            );
+            assert!(metadata_result.metadata_file.exists());
+            assert!(metadata_result.bundle_file.exists());
+
+            let abi_file = metadata_result.abi_file.expect("the abi was requested");
+            assert_eq!(abi_file, output_dir.join("runtime-v2.abi.json"));
+            let abi: serde_json::Value = serde_json::from_slice(&fs::read(&abi_file)?)?;
+            assert!(abi
+                .as_array()
+                .expect("the abi is an array")
+                .iter()
+                .any(|entry| entry["type"] == "constructor" && entry["name"] == "new"));
+            Ok(())
+        })
+    }
 }
@@ -14,19 +14,62 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+#[cfg(feature = "extrinsics")]
+pub mod account;
 pub mod build;
 #[cfg(feature = "extrinsics")]
 mod call;
+pub mod check;
+pub mod check_env;
+pub mod clean;
 pub mod composable_build;
 #[cfg(feature = "extrinsics")]
+pub mod composable_deploy;
+#[cfg(feature = "extrinsics")]
 pub mod deploy;
 #[cfg(feature = "extrinsics")]
+mod events;
+#[cfg(feature = "extrinsics")]
+pub mod info;
+#[cfg(feature = "extrinsics")]
 mod instantiate;
+#[cfg(feature = "extrinsics")]
+pub mod message;
 pub mod metadata;
 pub mod new;
+#[cfg(feature = "extrinsics")]
+mod nonce;
+#[cfg(feature = "extrinsics")]
+pub mod offline;
+#[cfg(feature = "extrinsics")]
+pub mod receipt;
+#[cfg(feature = "extrinsics")]
+pub mod registry;
+#[cfg(feature = "extrinsics")]
+pub mod remove_code;
+#[cfg(feature = "extrinsics")]
+mod retry;
+#[cfg(feature = "extrinsics")]
+mod rpc;
+pub mod size_analysis;
+#[cfg(feature = "extrinsics")]
+pub mod storage;
+pub mod test;
+#[cfg(feature = "extrinsics")]
+mod tip;
+#[cfg(feature = "tls")]
+mod tls;
+#[cfg(feature = "extrinsics")]
+mod transcode;
+pub mod verify;
+pub mod watch;
 
 #[cfg(feature = "extrinsics")]
-pub(crate) use self::{
-    call::call_regular_contract, call::execute_call, call::execute_contract_call,
-    deploy::execute_deploy, instantiate::execute_instantiate,
+pub use self::{
+    call::call_regular_contract, call::execute_call, call::execute_contract_call, call::CallMode,
+    deploy::execute_deploy, events::ExtrinsicInclusion, events::Finality,
+    instantiate::execute_instantiate, instantiate::execute_instantiate_batch, message::MessageArgs,
+    rpc::Code, transcode::MessageKind, transcode::Transcoder,
 };
+#[cfg(feature = "extrinsics")]
+pub(crate) use self::{nonce::NonceSigner, rpc::ss58_prefix, rpc::token_decimals, tip::TipSigner};
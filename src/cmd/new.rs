@@ -16,34 +16,213 @@
 
 use std::{
     env, fs,
-    io::{Cursor, Read, Seek, SeekFrom, Write},
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
     path::Path,
+    process::Command,
+    str::FromStr,
 };
 
 use anyhow::Result;
+use colored::Colorize;
 use heck::CamelCase as _;
 
-pub(crate) fn execute<P>(name: &str, dir: Option<P>) -> Result<String>
+use crate::Reporter;
+
+/// The starter contracts a new project can be created from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    /// A contract storing a single `bool` which can be flipped.
+    Flipper,
+    /// An implementation of the ERC-20 token standard.
+    Erc20,
+    /// A contract without any storage and a single no-op message.
+    Empty,
+}
+
+impl Template {
+    /// The names of all templates, as accepted by `--template`.
+    pub const NAMES: [&'static str; 3] = ["flipper", "erc20", "empty"];
+
+    /// Returns the zip archive of the template files embedded in the binary.
+    fn archive(&self) -> &'static [u8] {
+        match self {
+            Template::Flipper => {
+                include_bytes!(concat!(env!("OUT_DIR"), "/template-flipper.zip"))
+            }
+            Template::Erc20 => include_bytes!(concat!(env!("OUT_DIR"), "/template-erc20.zip")),
+            Template::Empty => include_bytes!(concat!(env!("OUT_DIR"), "/template-empty.zip")),
+        }
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Template::Flipper
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "flipper" => Ok(Template::Flipper),
+            "erc20" => Ok(Template::Erc20),
+            "empty" => Ok(Template::Empty),
+            _ => Err(format!(
+                "Unknown template '{}', expected one of {}",
+                input,
+                Template::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// The `.gitignore` written to new projects in case the template does not provide one.
+const DEFAULT_GITIGNORE: &str = "/target/\n";
+
+/// Initializes a git repository in the project directory and stages the generated files.
+///
+/// A missing `git` executable is not an error: a warning is printed and the project is left
+/// without a repository.
+fn init_git_repository(dir: &Path) -> Result<()> {
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, DEFAULT_GITIGNORE)?;
+    }
+
+    for args in &[&["init", "--quiet"][..], &["add", "--all"][..]] {
+        let output = match Command::new("git").args(*args).current_dir(dir).output() {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Reporter::default().warning(
+                    "git was not found on PATH, skipping repository initialization".bold(),
+                );
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if !output.status.success() {
+            io::stderr().write_all(&output.stderr)?;
+            anyhow::bail!(
+                "`git {}` failed with status {}",
+                args.join(" "),
+                output.status
+            )
+        }
+    }
+    Ok(())
+}
+
+/// Keywords which cannot be used as a crate name, since it must be a valid Rust identifier.
+const KEYWORDS: [&str; 52] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield", "_",
+];
+
+/// Returns what makes the name an invalid crate name, empty if the name is valid.
+fn name_problems(name: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if name.is_empty() {
+        problems.push("is empty".to_string());
+        return problems;
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        problems.push("starts with a digit".to_string());
+    }
+    let mut invalid_chars = Vec::new();
+    for c in name.chars() {
+        if !(c.is_ascii_alphanumeric() || c == '_') && !invalid_chars.contains(&c) {
+            invalid_chars.push(c);
+        }
+    }
+    for c in invalid_chars {
+        problems.push(match c {
+            ' ' => "contains a space".to_string(),
+            '-' => "contains a hyphen".to_string(),
+            c => format!("contains the invalid character '{}'", c),
+        });
+    }
+    if KEYWORDS.contains(&name) {
+        problems.push("is a Rust keyword".to_string());
+    }
+    problems
+}
+
+/// Returns a valid crate name derived from the name, if there is one.
+fn sanitize_name(name: &str) -> Option<String> {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if sanitized.chars().all(|c| c == '_') {
+        return None;
+    }
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert_str(0, "contract_");
+    }
+    if KEYWORDS.contains(&sanitized.as_str()) {
+        sanitized.push_str("_contract");
+    }
+    Some(sanitized)
+}
+
+/// Checks the contract name against cargo's package name rules, since it is used as the name of
+/// the crate and of the contract module.
+fn validate_name(name: &str) -> Result<()> {
+    let problems = name_problems(name);
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let mut message = format!(
+        "Contract names must be valid Rust identifiers; '{}' {}",
+        name,
+        problems.join(", ")
+    );
+    if let Some(suggestion) = sanitize_name(name) {
+        message.push_str(&format!(". Try '{}' instead", suggestion));
+    }
+    anyhow::bail!(message)
+}
+
+pub fn execute<P>(
+    name: &str,
+    dir: Option<P>,
+    template: Template,
+    git: bool,
+    force: bool,
+) -> Result<String>
 where
     P: AsRef<Path>,
 {
-    if name.contains('-') {
-        anyhow::bail!("Contract names cannot contain hyphens");
-    }
+    validate_name(name)?;
 
     let out_dir = dir
         .map_or(env::current_dir()?, |p| p.as_ref().to_path_buf())
         .join(name);
-    if out_dir.join("Cargo.toml").exists() {
-        anyhow::bail!("A Cargo package already exists in {}", name);
+    if !force {
+        if out_dir.join("Cargo.toml").exists() {
+            anyhow::bail!(
+                "A Cargo package already exists in {}, use --force to overwrite it",
+                name
+            );
+        }
+        if out_dir.exists() && fs::read_dir(&out_dir)?.next().is_some() {
+            anyhow::bail!(
+                "Directory {} already exists and is not empty, use --force to overwrite its files",
+                out_dir.display()
+            );
+        }
     }
     if !out_dir.exists() {
         fs::create_dir(&out_dir)?;
     }
 
-    let template = include_bytes!(concat!(env!("OUT_DIR"), "/template.zip"));
     let mut cursor = Cursor::new(Vec::new());
-    cursor.write_all(template)?;
+    cursor.write_all(template.archive())?;
     cursor.seek(SeekFrom::Start(0))?;
 
     let mut archive = zip::ZipArchive::new(cursor)?;
@@ -69,18 +248,9 @@ where
             }
             let mut outfile = fs::OpenOptions::new()
                 .write(true)
-                .create_new(true)
-                .open(outpath.clone())
-                .map_err(|e| {
-                    if e.kind() == std::io::ErrorKind::AlreadyExists {
-                        anyhow::anyhow!(
-                            "New contract file {} already exists",
-                            file.sanitized_name().display()
-                        )
-                    } else {
-                        anyhow::anyhow!(e)
-                    }
-                })?;
+                .create(true)
+                .truncate(true)
+                .open(outpath.clone())?;
 
             outfile.write_all(contents.as_bytes())?;
         }
@@ -96,6 +266,10 @@ where
         }
     }
 
+    if git {
+        init_git_repository(&out_dir)?;
+    }
+
     Ok(format!("Created contract {}", name))
 }
 
@@ -107,31 +281,112 @@ mod tests {
     #[test]
     fn rejects_hyphenated_name() {
         with_tmp_dir(|path| {
-            let result = cmd::new::execute("rejects-hyphenated-name", Some(path));
+            let result = cmd::new::execute(
+                "rejects-hyphenated-name",
+                Some(path),
+                Template::Flipper,
+                false,
+                false,
+            );
             assert_eq!(
                 format!("{:?}", result),
-                r#"Err(Contract names cannot contain hyphens)"#
+                "Err(Contract names must be valid Rust identifiers; 'rejects-hyphenated-name' \
+                 contains a hyphen. Try 'rejects_hyphenated_name' instead)"
             );
             Ok(())
         })
     }
 
+    #[test]
+    fn accepts_valid_names() {
+        for name in &["flipper", "my_contract", "Erc20", "_private", "contract2"] {
+            assert!(validate_name(name).is_ok(), "{} should be valid", name);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        let error = |name| validate_name(name).unwrap_err().to_string();
+        assert_eq!(
+            error("my contract"),
+            "Contract names must be valid Rust identifiers; 'my contract' contains a space. \
+             Try 'my_contract' instead"
+        );
+        assert_eq!(
+            error("1st-contract"),
+            "Contract names must be valid Rust identifiers; '1st-contract' starts with a digit, \
+             contains a hyphen. Try 'contract_1st_contract' instead"
+        );
+        assert_eq!(
+            error("fn"),
+            "Contract names must be valid Rust identifiers; 'fn' is a Rust keyword. \
+             Try 'fn_contract' instead"
+        );
+        assert_eq!(
+            error("my.contract!"),
+            "Contract names must be valid Rust identifiers; 'my.contract!' contains the invalid \
+             character '.', contains the invalid character '!'. Try 'my_contract_' instead"
+        );
+        assert_eq!(
+            error(""),
+            "Contract names must be valid Rust identifiers; '' is empty"
+        );
+    }
+
     #[test]
     fn contract_cargo_project_already_exists() {
         with_tmp_dir(|path| {
             let name = "test_contract_cargo_project_already_exists";
-            let _ = execute(name, Some(path));
-            let result = cmd::new::execute(name, Some(path));
+            let _ = execute(name, Some(path), Template::Flipper, false, false);
+            let result = cmd::new::execute(name, Some(path), Template::Flipper, false, false);
 
             assert!(result.is_err(), "Should fail");
             assert_eq!(
                 result.err().unwrap().to_string(),
-                "A Cargo package already exists in test_contract_cargo_project_already_exists"
+                "A Cargo package already exists in test_contract_cargo_project_already_exists, \
+                 use --force to overwrite it"
             );
             Ok(())
         })
     }
 
+    #[test]
+    fn creates_project_from_template() {
+        with_tmp_dir(|path| {
+            for template in Template::NAMES.iter() {
+                let name = format!("new_{}", template);
+                let template = template.parse().expect("template names are valid");
+                cmd::new::execute(&name, Some(path), template, false, false)?;
+
+                let lib_rs = fs::read_to_string(path.join(&name).join("lib.rs"))?;
+                assert!(lib_rs.contains(&format!("mod {} {{", name)));
+                assert!(path.join(&name).join("Cargo.toml").exists());
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn initializes_git_repository() {
+        with_tmp_dir(|path| {
+            let name = "initializes_git_repository";
+            cmd::new::execute(name, Some(path), Template::Flipper, true, false)?;
+
+            assert!(path.join(name).join(".git").is_dir());
+            assert!(path.join(name).join(".gitignore").is_file());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn rejects_unknown_template() {
+        let result = "erc721".parse::<Template>();
+        assert_eq!(
+            result,
+            Err("Unknown template 'erc721', expected one of flipper, erc20, empty".to_string())
+        );
+    }
+
     #[test]
     fn dont_overwrite_existing_files_not_in_cargo_project() {
         with_tmp_dir(|path| {
@@ -139,14 +394,36 @@ mod tests {
             let dir = path.join(name);
             fs::create_dir_all(&dir).unwrap();
             fs::File::create(dir.join(".gitignore")).unwrap();
-            let result = cmd::new::execute(name, Some(path));
+            let result = cmd::new::execute(name, Some(path), Template::Flipper, false, false);
 
             assert!(result.is_err(), "Should fail");
             assert_eq!(
                 result.err().unwrap().to_string(),
-                "New contract file .gitignore already exists"
+                format!(
+                    "Directory {} already exists and is not empty, use --force to overwrite its \
+                     files",
+                    dir.display()
+                )
             );
             Ok(())
         })
     }
+
+    #[test]
+    fn force_overwrites_existing_files() {
+        with_tmp_dir(|path| {
+            let name = "force_overwrites_existing_files";
+            let dir = path.join(name);
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join("lib.rs"), "previous work")?;
+            fs::write(dir.join("notes.txt"), "unrelated")?;
+
+            cmd::new::execute(name, Some(path), Template::Flipper, false, true)?;
+
+            let lib_rs = fs::read_to_string(dir.join("lib.rs"))?;
+            assert!(lib_rs.contains(&format!("mod {} {{", name)));
+            assert_eq!(fs::read_to_string(dir.join("notes.txt"))?, "unrelated");
+            Ok(())
+        })
+    }
 }
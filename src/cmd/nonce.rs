@@ -0,0 +1,70 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{future::Future, pin::Pin};
+
+use subxt::{
+    system::System, ContractsTemplateRuntime, Runtime, SignedExtra, Signer, UncheckedExtrinsic,
+};
+
+use crate::ExtrinsicSigner;
+
+type SignedPayload = sp_runtime::generic::SignedPayload<
+    subxt::Encoded,
+    <<ContractsTemplateRuntime as Runtime>::Extra as SignedExtra<ContractsTemplateRuntime>>::Extra,
+>;
+
+/// Signs extrinsics with a fixed nonce, instead of the one the client queries from the node.
+///
+/// Scripts submitting several extrinsics in a row from the same account manage the nonces
+/// themselves, since the nonce queried from the node lags behind until the previous extrinsics
+/// are included.
+pub(crate) struct NonceSigner {
+    signer: ExtrinsicSigner,
+    nonce: <ContractsTemplateRuntime as System>::Index,
+}
+
+impl NonceSigner {
+    pub fn new(
+        signer: ExtrinsicSigner,
+        nonce: <ContractsTemplateRuntime as System>::Index,
+    ) -> Self {
+        Self { signer, nonce }
+    }
+}
+
+impl Signer<ContractsTemplateRuntime> for NonceSigner {
+    fn account_id(&self) -> &<ContractsTemplateRuntime as System>::AccountId {
+        self.signer.account_id()
+    }
+
+    fn nonce(&self) -> Option<<ContractsTemplateRuntime as System>::Index> {
+        Some(self.nonce)
+    }
+
+    fn sign(
+        &self,
+        payload: SignedPayload,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<UncheckedExtrinsic<ContractsTemplateRuntime>, String>>
+                + Send
+                + Sync,
+        >,
+    > {
+        self.signer.sign(payload)
+    }
+}
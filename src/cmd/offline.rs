@@ -0,0 +1,99 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signing extrinsics without connecting to a node, and broadcasting them later.
+
+use std::{convert::TryFrom, fs, path::Path};
+
+use anyhow::{Context, Result};
+use codec::{Decode, Encode};
+use frame_metadata::RuntimeMetadataPrefixed;
+use sp_core::H256;
+use sp_runtime::generic::SignedPayload;
+use subxt::{Call, ContractsTemplateRuntime, Metadata, Runtime, SignedExtra};
+
+use crate::{cmd::rpc, ExtrinsicOpts, ExtrinsicSigner, HexData};
+
+/// Signs the call as an extrinsic without connecting to a node, and returns it hex encoded.
+///
+/// The chain state usually queried from the node, i.e. the nonce of the signer, the genesis hash,
+/// the runtime versions and the runtime metadata, must be supplied with the offline options.
+pub(crate) fn sign<C: Call<ContractsTemplateRuntime>>(
+    extrinsic_opts: &ExtrinsicOpts,
+    signer: &ExtrinsicSigner,
+    call: C,
+) -> Result<String> {
+    let opts = &extrinsic_opts.offline;
+    let nonce = required(opts.nonce, "nonce")?;
+    let genesis_hash = required(opts.genesis_hash, "genesis-hash")?;
+    let spec_version = required(opts.spec_version, "spec-version")?;
+    let transaction_version = required(opts.transaction_version, "transaction-version")?;
+    let runtime_metadata = opts
+        .runtime_metadata
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("--offline requires --runtime-metadata"))?;
+
+    let metadata = load_runtime_metadata(runtime_metadata)?;
+    let call = metadata
+        .module_with_calls(C::MODULE)
+        .and_then(|module| module.call(C::FUNCTION, call))
+        .map_err(|err| {
+            anyhow::anyhow!(
+                "Encoding the {}::{} call: {:?}",
+                C::MODULE,
+                C::FUNCTION,
+                err
+            )
+        })?;
+    let extra = <ContractsTemplateRuntime as Runtime>::Extra::new(
+        spec_version,
+        transaction_version,
+        nonce,
+        genesis_hash,
+    );
+    let payload = SignedPayload::new(call, extra.extra())
+        .map_err(|err| anyhow::anyhow!("Creating the signed payload: {:?}", err))?;
+    let extrinsic = async_std::task::block_on(signer.sign(payload))
+        .map_err(|err| anyhow::anyhow!("Signing the extrinsic: {}", err))?;
+    Ok(format!("0x{}", hex::encode(extrinsic.encode())))
+}
+
+/// Broadcasts an extrinsic signed with `--offline` and returns its hash.
+///
+/// The extrinsic is submitted without waiting for it to be included in a block.
+pub fn submit(url: &url::Url, accept_invalid_certs: bool, extrinsic: &HexData) -> Result<H256> {
+    async_std::task::block_on(async move {
+        let client = rpc::connect(url, accept_invalid_certs).await?;
+        rpc::submit_extrinsic(&client, &extrinsic.0).await
+    })
+}
+
+fn required<T: Copy>(value: Option<T>, flag: &str) -> Result<T> {
+    value.ok_or_else(|| anyhow::anyhow!("--offline requires --{}", flag))
+}
+
+/// Loads the runtime metadata, as returned hex encoded by the `state_getMetadata` RPC method.
+fn load_runtime_metadata(path: &Path) -> Result<Metadata> {
+    let contents =
+        fs::read_to_string(path).context(format!("Reading runtime metadata {}", path.display()))?;
+    let bytes = hex::decode(contents.trim().trim_matches('"').trim_start_matches("0x")).context(
+        format!("Runtime metadata {} is not hex encoded", path.display()),
+    )?;
+    let prefixed = RuntimeMetadataPrefixed::decode(&mut &bytes[..])
+        .context(format!("Decoding runtime metadata {}", path.display()))?;
+    Metadata::try_from(prefixed)
+        .map_err(|err| anyhow::anyhow!("Invalid runtime metadata {}: {:?}", path.display(), err))
+}
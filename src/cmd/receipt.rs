@@ -0,0 +1,141 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Receipts of successful deployments and instantiations, as a durable record of what was
+//! deployed where.
+
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sp_core::{crypto::AccountId32, H256};
+use subxt::Signer;
+
+use super::ExtrinsicInclusion;
+use crate::ExtrinsicOpts;
+
+/// The record of an extrinsic which deployed code or instantiated a contract.
+#[derive(Debug, Serialize)]
+pub struct Receipt {
+    /// Seconds since the unix epoch at which the receipt was written.
+    pub timestamp: u64,
+    /// The url of the node the extrinsic was submitted to.
+    pub url: String,
+    /// The SS58 address of the account which signed the extrinsic.
+    pub signer: String,
+    pub code_hash: H256,
+    /// The SS58 address of the instantiated contract, `None` for a deployment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    pub block_hash: H256,
+    pub extrinsic_index: Option<usize>,
+    /// The gas limit the instantiation was submitted with, `None` for a deployment.
+    ///
+    /// This is the limit, not the gas used, which the contracts pallet does not report.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<u64>,
+}
+
+impl Receipt {
+    /// Creates the receipt of the extrinsic submitted with the `extrinsic_opts`, which stored
+    /// the code with the `code_hash` and was included as described by `inclusion`.
+    pub fn new(
+        extrinsic_opts: &ExtrinsicOpts,
+        code_hash: H256,
+        inclusion: &ExtrinsicInclusion,
+    ) -> Result<Self> {
+        let signer = extrinsic_opts.signer()?;
+        Ok(Receipt {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            url: extrinsic_opts.url().to_string(),
+            signer: extrinsic_opts.ss58_address_or_default(signer.account_id()),
+            code_hash,
+            contract: None,
+            block_hash: inclusion.block_hash,
+            extrinsic_index: inclusion.extrinsic_index,
+            gas_limit: None,
+        })
+    }
+
+    /// Records the contract instantiated by the extrinsic, with the gas limit it was submitted
+    /// with.
+    pub fn with_contract(
+        mut self,
+        extrinsic_opts: &ExtrinsicOpts,
+        contract: &AccountId32,
+        gas_limit: u64,
+    ) -> Self {
+        self.contract = Some(extrinsic_opts.ss58_address_or_default(contract));
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Appends the receipt as a line of json to the file at `path`, which is created if it does
+    /// not exist. A single file thus collects the receipts of several deployments.
+    pub fn append_to(&self, path: &Path) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("Opening receipt file {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)
+            .context(format!("Writing receipt file {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Receipt;
+    use crate::util::tests::with_tmp_dir;
+
+    fn receipt(timestamp: u64) -> Receipt {
+        Receipt {
+            timestamp,
+            url: "ws://localhost:9944/".into(),
+            signer: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".into(),
+            code_hash: Default::default(),
+            contract: None,
+            block_hash: Default::default(),
+            extrinsic_index: Some(1),
+            gas_limit: None,
+        }
+    }
+
+    #[test]
+    fn receipts_are_appended_as_json_lines() {
+        with_tmp_dir(|path| {
+            let receipt_path = path.join("receipts.jsonl");
+            receipt(1).append_to(&receipt_path)?;
+            receipt(2).append_to(&receipt_path)?;
+
+            let contents = fs::read_to_string(&receipt_path)?;
+            let lines = contents.lines().collect::<Vec<_>>();
+            assert_eq!(lines.len(), 2);
+            let second: serde_json::Value = serde_json::from_str(lines[1])?;
+            assert_eq!(second["timestamp"], 2);
+            assert_eq!(second["extrinsic_index"], 1);
+            assert!(second.get("contract").is_none());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Remembers the metadata of the contracts instantiated from a project, so that calls to them can
+//! be encoded and their results decoded given only the contract address.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::crate_metadata::CrateMetadata;
+
+/// The file in the target directory the registry is stored in.
+pub const REGISTRY_FILE: &str = "contracts.json";
+
+/// Maps the addresses of contracts, hex encoded, to the paths of their metadata.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetadataRegistry {
+    contracts: BTreeMap<String, PathBuf>,
+}
+
+impl MetadataRegistry {
+    /// Loads the registry from the target directory, an empty one if none was stored yet.
+    pub fn load(target_dir: &Path) -> Result<Self> {
+        let path = target_dir.join(REGISTRY_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read(&path).context(format!("Reading {}", path.display()))?;
+        serde_json::from_slice(&contents).context(format!("Parsing {}", path.display()))
+    }
+
+    /// Stores the registry in the target directory.
+    pub fn store(&self, target_dir: &Path) -> Result<()> {
+        let path = target_dir.join(REGISTRY_FILE);
+        fs::create_dir_all(target_dir)?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .context(format!("Writing {}", path.display()))
+    }
+
+    /// Registers the metadata of the contract at `address`, replacing any previous one.
+    ///
+    /// The path is made absolute, so that it can be resolved from any directory.
+    pub fn register(&mut self, address: &[u8], metadata_path: &Path) -> Result<()> {
+        let metadata_path = metadata_path
+            .canonicalize()
+            .context(format!("Resolving {}", metadata_path.display()))?;
+        self.contracts.insert(hex_address(address), metadata_path);
+        Ok(())
+    }
+
+    /// Returns the path of the metadata of the contract at `address`, if it was registered.
+    pub fn lookup(&self, address: &[u8]) -> Option<&Path> {
+        self.contracts
+            .get(&hex_address(address))
+            .map(PathBuf::as_path)
+    }
+}
+
+/// Returns true if the `source.hash` of the metadata or bundle at `metadata_path` is `code_hash`,
+/// i.e. if it describes the contract code with that hash.
+pub fn matches_code_hash(metadata_path: &Path, code_hash: &[u8]) -> Result<bool> {
+    let contents =
+        fs::read(metadata_path).context(format!("Reading {}", metadata_path.display()))?;
+    let metadata: serde_json::Value = serde_json::from_slice(&contents)
+        .context(format!("Parsing {}", metadata_path.display()))?;
+    let hash = match metadata["source"]["hash"].as_str() {
+        Some(hash) => hash,
+        None => return Ok(false),
+    };
+    Ok(hex::decode(hash.trim_start_matches("0x")).ok().as_deref() == Some(code_hash))
+}
+
+/// Returns the path to the metadata the contract at `address` was instantiated with from the
+/// current project, if it was registered. `None` outside of a project.
+pub fn registered_metadata_path(address: &[u8]) -> Result<Option<PathBuf>> {
+    let crate_metadata = match CrateMetadata::collect(&Default::default()) {
+        Ok(crate_metadata) => crate_metadata,
+        Err(_) => return Ok(None),
+    };
+    let registry = MetadataRegistry::load(&crate_metadata.target_directory)?;
+    Ok(registry.lookup(address).map(PathBuf::from))
+}
+
+fn hex_address(address: &[u8]) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{matches_code_hash, MetadataRegistry};
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn registered_metadata_is_looked_up_by_address() {
+        with_tmp_dir(|path| {
+            let metadata_path = path.join("metadata.json");
+            fs::write(&metadata_path, "{}")?;
+            let target_dir = path.join("target");
+            assert_eq!(
+                MetadataRegistry::load(&target_dir)?,
+                MetadataRegistry::default()
+            );
+
+            let mut registry = MetadataRegistry::default();
+            registry.register(&[1; 32], &metadata_path)?;
+            registry.store(&target_dir)?;
+
+            let registry = MetadataRegistry::load(&target_dir)?;
+            assert_eq!(
+                registry.lookup(&[1; 32]),
+                Some(metadata_path.canonicalize()?.as_path())
+            );
+            assert_eq!(registry.lookup(&[2; 32]), None);
+            Ok(())
+        })
+    }
+    #[test]
+    fn metadata_matches_the_code_hash_of_its_source() {
+        with_tmp_dir(|path| {
+            let metadata_path = path.join("metadata.json");
+            let hash = format!("0x{}", hex::encode([1; 32]));
+            fs::write(
+                &metadata_path,
+                serde_json::json!({ "source": { "hash": hash } }).to_string(),
+            )?;
+            assert!(matches_code_hash(&metadata_path, &[1; 32])?);
+            assert!(!matches_code_hash(&metadata_path, &[2; 32])?);
+
+            fs::write(&metadata_path, "{}")?;
+            assert!(!matches_code_hash(&metadata_path, &[1; 32])?);
+            Ok(())
+        })
+    }
+}
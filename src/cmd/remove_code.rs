@@ -0,0 +1,127 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+use codec::{Decode, Encode};
+use serde::Serialize;
+use sp_core::H256;
+use subxt::{ContractsTemplateRuntime, EventsDecoder};
+
+use crate::{
+    cmd::{
+        events::{self, ExtrinsicInclusion},
+        offline, retry, rpc,
+    },
+    ExtrinsicOpts,
+};
+
+/// The `Contracts::remove_code` call, removing code which is not used by any contract.
+///
+/// Only newer versions of the contracts pallet provide this call, on other chains the submission
+/// fails because the call is missing from the runtime metadata.
+#[derive(Clone, Debug, PartialEq, Encode)]
+struct RemoveCodeCall {
+    code_hash: H256,
+}
+
+impl subxt::Call<ContractsTemplateRuntime> for RemoveCodeCall {
+    const MODULE: &'static str = "Contracts";
+    const FUNCTION: &'static str = "remove_code";
+
+    fn events_decoder(decoder: &mut EventsDecoder<ContractsTemplateRuntime>) {
+        decoder.register_type_size::<H256>("CodeHash<T>");
+    }
+}
+
+/// The `Contracts::CodeRemoved` event, emitted once the code was removed.
+#[derive(Clone, Debug, PartialEq, Decode)]
+struct CodeRemovedEvent {
+    code_hash: H256,
+}
+
+impl subxt::Event<ContractsTemplateRuntime> for CodeRemovedEvent {
+    const MODULE: &'static str = "Contracts";
+    const EVENT: &'static str = "CodeRemoved";
+}
+
+/// The result of removing contract code.
+#[derive(Debug, Serialize)]
+pub struct RemoveCodeResult {
+    pub code_hash: H256,
+    /// Where the extrinsic was included, `None` if it was not submitted.
+    #[serde(flatten)]
+    pub inclusion: Option<ExtrinsicInclusion>,
+    /// The signed extrinsic, hex encoded, if it was signed with `--offline` instead of submitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extrinsic: Option<String>,
+}
+
+/// Removes the code stored under `code_hash` from the chain, reclaiming its storage deposit.
+///
+/// Creates an extrinsic with the `Contracts::remove_code` Call, submits via RPC, then waits for
+/// the `ContractsEvent::CodeRemoved` event. If the code is still used by a contract, the decoded
+/// dispatch error is returned. Connecting is retried on network errors, the extrinsic is
+/// submitted only once.
+///
+/// With `--offline`, the extrinsic is signed and returned hex encoded instead of submitted.
+pub fn execute(extrinsic_opts: &ExtrinsicOpts, code_hash: H256) -> Result<RemoveCodeResult> {
+    let signer = extrinsic_opts.signer()?;
+    let call = RemoveCodeCall { code_hash };
+
+    if extrinsic_opts.offline.offline {
+        return Ok(RemoveCodeResult {
+            code_hash,
+            inclusion: None,
+            extrinsic: Some(offline::sign(extrinsic_opts, &signer, call)?),
+        });
+    }
+
+    extrinsic_opts.reporter()?.verbose(format_args!(
+        "Call: Contracts::remove_code {{ code_hash: {:?} }}",
+        code_hash
+    ));
+
+    async_std::task::block_on(async move {
+        let cli = retry::with_retries(extrinsic_opts, || rpc::client(extrinsic_opts)).await?;
+        let result = retry::with_timeout(extrinsic_opts, cli.watch(call, &signer)).await?;
+        let events = events::decode_dispatch_error(extrinsic_opts, result).await?;
+        let code_removed = events
+            .find_event::<CodeRemovedEvent>()?
+            .ok_or_else(|| anyhow::anyhow!("Failed to find CodeRemoved event"))?;
+        let inclusion = events::inclusion(extrinsic_opts, &events).await?;
+
+        Ok(RemoveCodeResult {
+            code_hash: code_removed.code_hash,
+            inclusion: Some(inclusion),
+            extrinsic: None,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use codec::Encode;
+    use sp_core::H256;
+
+    use super::RemoveCodeCall;
+
+    #[test]
+    fn remove_code_call_encodes_the_code_hash() {
+        let code_hash = H256::repeat_byte(0x42);
+        let call = RemoveCodeCall { code_hash };
+        assert_eq!(call.encode(), code_hash.as_bytes());
+    }
+}
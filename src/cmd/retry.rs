@@ -0,0 +1,102 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{future::Future, time::Duration};
+
+use anyhow::Result;
+
+use crate::{util, ExtrinsicOpts};
+
+/// The delay before the first retry, doubled for every further attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Runs the future returned by `f`, retrying it with exponential backoff on network errors, up to
+/// the number of retries configured in the extrinsic options.
+///
+/// Other errors, e.g. the node rejecting a request, are returned immediately. So is a timeout, if
+/// the node does not respond within the configured timeout.
+///
+/// # Note
+///
+/// Only requests which do not submit an extrinsic may be retried, e.g. connecting to the node:
+/// once an extrinsic was sent, a retry would sign and submit it a second time.
+pub(crate) async fn with_retries<T, F, Fut>(extrinsic_opts: &ExtrinsicOpts, mut f: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let reporter = extrinsic_opts.reporter()?;
+    let mut attempt = 0;
+    loop {
+        match with_timeout(extrinsic_opts, f()).await? {
+            Err(err) if attempt < extrinsic_opts.retries && util::is_network_error(&err) => {
+                let backoff = INITIAL_BACKOFF * 2u32.pow(attempt);
+                attempt += 1;
+                reporter.verbose(format_args!(
+                    "Network error: {:#}. Retrying in {}ms (attempt {}/{})",
+                    err,
+                    backoff.as_millis(),
+                    attempt,
+                    extrinsic_opts.retries
+                ));
+                async_std::task::sleep(backoff).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Runs the `future` once, failing if the node does not respond within the configured timeout.
+///
+/// Used for submitting an extrinsic and watching it, which must not be retried, see
+/// [`with_retries`].
+pub(crate) async fn with_timeout<T, Fut>(extrinsic_opts: &ExtrinsicOpts, future: Fut) -> Result<T>
+where
+    Fut: Future<Output = T>,
+{
+    let timeout = Duration::from_secs(extrinsic_opts.timeout);
+    async_std::future::timeout(timeout, future)
+        .await
+        .map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!(
+                    "The node did not respond within {} seconds",
+                    extrinsic_opts.timeout
+                ),
+            )
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unresponsive_node_times_out() {
+        let extrinsic_opts =
+            crate::util::tests::extrinsic_opts("ws://localhost:1", &["--timeout", "0"]);
+        let result = async_std::task::block_on(with_timeout(
+            &extrinsic_opts,
+            async_std::future::pending::<()>(),
+        ));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "The node did not respond within 0 seconds"
+        );
+    }
+}
@@ -0,0 +1,758 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{convert::TryFrom, future::Future, pin::Pin, time::Duration};
+
+use anyhow::Result;
+use codec::Decode;
+use colored::Colorize;
+use frame_metadata::{
+    DecodeDifferent, DecodeDifferentStr, ModuleMetadata, RuntimeMetadata, RuntimeMetadataPrefixed,
+    RuntimeMetadataV11,
+};
+use jsonrpsee::{
+    common::{self, to_value as to_json_value, Params},
+    raw::RawClient,
+    transport::{http::HttpTransportClient, ws::WsTransportClient, TransportClient},
+};
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+use subxt::{balances::Balances, system::System, Client, ClientBuilder, ContractsTemplateRuntime};
+
+use crate::ExtrinsicOpts;
+
+/// How often the finalized head is polled while waiting for a block to be finalized.
+const FINALITY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The maximum gas limit accepted by the contracts pallet RPC for dry runs.
+const DRY_RUN_GAS_LIMIT: u64 = 5 * 1_000_000_000_000;
+
+/// The code a contract is instantiated from.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Code {
+    /// Code already stored on chain, referenced by its hash.
+    Existing(<ContractsTemplateRuntime as System>::Hash),
+    /// Wasm code uploaded together with the instantiation, by newer contracts pallets only.
+    Upload(Bytes),
+}
+
+/// Request for the `contracts_instantiate` RPC method.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstantiateRequest {
+    origin: <ContractsTemplateRuntime as System>::AccountId,
+    endowment: <ContractsTemplateRuntime as Balances>::Balance,
+    gas_limit: u64,
+    code: Code,
+    data: Bytes,
+    salt: Bytes,
+}
+
+/// The subset of the `contracts_instantiate` RPC method result required by cargo-contract.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InstantiateResult {
+    gas_consumed: u64,
+}
+
+/// The result of the `rpc_methods` RPC method, listing the methods the node supports.
+#[derive(Deserialize)]
+struct RpcMethods {
+    methods: Vec<String>,
+}
+
+impl RpcMethods {
+    fn contains(&self, method: &str) -> bool {
+        self.methods.iter().any(|name| name == method)
+    }
+}
+
+/// Request for the `contracts_call` RPC method.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CallRequest {
+    origin: <ContractsTemplateRuntime as System>::AccountId,
+    dest: <ContractsTemplateRuntime as System>::AccountId,
+    value: <ContractsTemplateRuntime as Balances>::Balance,
+    gas_limit: u64,
+    input_data: Bytes,
+}
+
+/// The result of the `contracts_call` RPC method.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum CallResult {
+    #[serde(rename_all = "camelCase")]
+    Success {
+        flags: u32,
+        data: Bytes,
+        gas_consumed: u64,
+    },
+    Error(()),
+}
+
+/// The outcome of a dry run of a message call.
+pub(crate) struct DryRunCall {
+    /// The SCALE encoded return value of the message.
+    pub data: Vec<u8>,
+    pub gas_consumed: u64,
+    /// Whether the contract reverted the call.
+    pub reverted: bool,
+}
+
+/// Connects to the RPC interface of the node at the supplied url.
+///
+/// Both websocket (`ws://`, `wss://`) and HTTP (`http://`, `https://`) urls are supported, but
+/// only the former support subscriptions, see [`client`]. `wss://` urls require the `tls`
+/// feature. With `accept_invalid_certs` the certificate of the node is not verified, which is
+/// only supported for `wss://` urls.
+pub(crate) async fn connect(
+    url: &url::Url,
+    accept_invalid_certs: bool,
+) -> Result<jsonrpsee::Client> {
+    connect_with_trace(url, accept_invalid_certs, false).await
+}
+
+/// Connects to the RPC interface of the node configured in the extrinsic options.
+///
+/// With `--verbose`, every RPC request and response is printed.
+pub(crate) async fn connect_opts(extrinsic_opts: &ExtrinsicOpts) -> Result<jsonrpsee::Client> {
+    let trace = extrinsic_opts.reporter()?.is_verbose();
+    connect_with_trace(
+        &extrinsic_opts.url(),
+        extrinsic_opts.accept_invalid_certs,
+        trace,
+    )
+    .await
+}
+
+async fn connect_with_trace(
+    url: &url::Url,
+    accept_invalid_certs: bool,
+    trace: bool,
+) -> Result<jsonrpsee::Client> {
+    match url.scheme() {
+        "ws" => {
+            let transport = WsTransportClient::new(url.as_str()).await?;
+            Ok(raw_client(transport, trace))
+        }
+        #[cfg(feature = "tls")]
+        "wss" => {
+            let transport = super::tls::transport(url, accept_invalid_certs).await?;
+            Ok(raw_client(transport, trace))
+        }
+        #[cfg(not(feature = "tls"))]
+        "wss" => {
+            let _ = accept_invalid_certs;
+            anyhow::bail!(
+                "Connecting to {} requires TLS support, which is not compiled in. Install \
+                cargo-contract with `--features tls`",
+                url
+            )
+        }
+        "http" | "https" => {
+            if accept_invalid_certs {
+                anyhow::bail!(
+                    "--accept-invalid-certs is only supported for wss:// urls, not {}",
+                    url
+                )
+            }
+            let transport = HttpTransportClient::new(url.as_str());
+            Ok(raw_client(transport, trace))
+        }
+        scheme => anyhow::bail!(
+            "Unsupported url scheme '{}', expected ws, wss, http or https",
+            scheme
+        ),
+    }
+}
+
+fn raw_client<T>(transport: T, trace: bool) -> jsonrpsee::Client
+where
+    T: TransportClient + Send + 'static,
+    T::Error: Send + Sync,
+{
+    if trace {
+        RawClient::new(TracingTransport(transport)).into()
+    } else {
+        RawClient::new(transport).into()
+    }
+}
+
+/// Prints the requests sent and the responses received by the wrapped transport to stderr, so that
+/// they never mix with the result of the command.
+struct TracingTransport<T>(T);
+
+impl<T> TransportClient for TracingTransport<T>
+where
+    T: TransportClient + Send,
+{
+    type Error = T::Error;
+
+    fn send_request<'a>(
+        &'a mut self,
+        request: common::Request,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'a>> {
+        match common::to_string(&request) {
+            Ok(request) => eprintln!("{} {}", "RPC request".bold(), request),
+            Err(err) => eprintln!("{} <unserializable: {}>", "RPC request".bold(), err),
+        }
+        self.0.send_request(request)
+    }
+
+    fn next_response<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<common::Response, Self::Error>> + Send + 'a>> {
+        Box::pin(async move {
+            let response = self.0.next_response().await?;
+            match common::to_string(&response) {
+                Ok(json) => eprintln!("{} {}", "RPC response".bold(), json),
+                Err(err) => eprintln!("{} <unserializable: {}>", "RPC response".bold(), err),
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Returns whether the RPC transport of the url supports subscriptions. HTTP only supports
+/// requests.
+fn supports_subscriptions(url: &url::Url) -> bool {
+    !matches!(url.scheme(), "http" | "https")
+}
+
+/// Connects the subxt client to the node configured in the extrinsic options.
+///
+/// The client is used to submit extrinsics and watch them until they are included in a block,
+/// which requires a subscription. An error is returned if the url of the node does not support
+/// subscriptions, i.e. over HTTP.
+///
+/// With `--verbose`, the version of the runtime metadata of the chain and all RPC traffic are
+/// printed.
+pub(crate) async fn client(
+    extrinsic_opts: &ExtrinsicOpts,
+) -> Result<Client<ContractsTemplateRuntime>> {
+    let url = extrinsic_opts.url();
+    if !supports_subscriptions(&url) {
+        anyhow::bail!(
+            "Waiting for an extrinsic to be included in a block requires subscriptions, which are \
+             not supported over {}. Pass the ws:// or wss:// url of the node instead, or sign the \
+             extrinsic with --offline and broadcast it with `submit`",
+            url.scheme()
+        )
+    }
+    let rpc_client = connect_opts(extrinsic_opts).await?;
+    let reporter = extrinsic_opts.reporter()?;
+    if reporter.is_verbose() {
+        let metadata: Bytes = rpc_client
+            .request("state_getMetadata", Params::None)
+            .await?;
+        match metadata_version(&metadata) {
+            Some(version) => {
+                reporter.verbose(format_args!("Runtime metadata version: V{}", version))
+            }
+            None => reporter.verbose("Runtime metadata version: unknown"),
+        }
+    }
+    let client = ClientBuilder::<ContractsTemplateRuntime>::new()
+        .set_client(rpc_client)
+        .build()
+        .await?;
+    Ok(client)
+}
+
+/// Returns the documentation of an error of a runtime module, joined into a single line.
+///
+/// Fetches and decodes the runtime metadata via the `state_getMetadata` RPC method. Returns
+/// `None` if the metadata does not contain the error or its documentation.
+pub(crate) async fn error_documentation(
+    client: &jsonrpsee::Client,
+    module: &str,
+    error: &str,
+) -> Result<Option<String>> {
+    let modules = runtime_modules(client).await?;
+    let documentation = modules
+        .iter()
+        .filter(|metadata| is_named(&metadata.name, module))
+        .filter_map(|metadata| match &metadata.errors {
+            DecodeDifferent::Decoded(errors) => Some(errors),
+            DecodeDifferent::Encode(_) => None,
+        })
+        .flatten()
+        .find(|metadata| is_named(&metadata.name, error))
+        .and_then(|metadata| match &metadata.documentation {
+            DecodeDifferent::Decoded(documentation) => Some(
+                documentation
+                    .iter()
+                    .map(|line| line.trim())
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            DecodeDifferent::Encode(_) => None,
+        })
+        .filter(|documentation| !documentation.is_empty());
+    Ok(documentation)
+}
+
+/// Fetches the existential deposit of the chain, i.e. the minimum balance of an account, below
+/// which it is reaped.
+///
+/// Reads the `ExistentialDeposit` constant of the `Balances` module from the runtime metadata.
+pub(crate) async fn existential_deposit(
+    client: &jsonrpsee::Client,
+) -> Result<<ContractsTemplateRuntime as Balances>::Balance> {
+    let modules = runtime_modules(client).await?;
+    let value = modules
+        .iter()
+        .filter(|metadata| is_named(&metadata.name, "Balances"))
+        .filter_map(|metadata| match &metadata.constants {
+            DecodeDifferent::Decoded(constants) => Some(constants),
+            DecodeDifferent::Encode(_) => None,
+        })
+        .flatten()
+        .find(|metadata| is_named(&metadata.name, "ExistentialDeposit"))
+        .and_then(|metadata| match &metadata.value {
+            DecodeDifferent::Decoded(value) => Some(value),
+            DecodeDifferent::Encode(_) => None,
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!("The runtime has no Balances::ExistentialDeposit constant")
+        })?;
+    Ok(Decode::decode(&mut &value[..])?)
+}
+
+/// Returns true if the decoded name of a module, error or constant in the runtime metadata is
+/// `expected`.
+fn is_named(name: &DecodeDifferentStr, expected: &str) -> bool {
+    matches!(name, DecodeDifferent::Decoded(name) if name == expected)
+}
+
+/// Fetches and decodes the modules of the runtime metadata via the `state_getMetadata` RPC method.
+async fn runtime_modules(client: &jsonrpsee::Client) -> Result<Vec<ModuleMetadata>> {
+    let metadata: Bytes = client.request("state_getMetadata", Params::None).await?;
+    let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])?;
+    match metadata.1 {
+        RuntimeMetadata::V11(RuntimeMetadataV11 {
+            modules: DecodeDifferent::Decoded(modules),
+            ..
+        }) => Ok(modules),
+        _ => anyhow::bail!("Unsupported runtime metadata version"),
+    }
+}
+
+/// Returns the version of the encoded runtime metadata, which follows the `meta` magic number.
+fn metadata_version(metadata: &[u8]) -> Option<u8> {
+    match metadata {
+        [b'm', b'e', b't', b'a', version, ..] => Some(*version),
+        _ => None,
+    }
+}
+
+/// Fetches the number of decimals of the token of the chain from the `system_properties` RPC
+/// method of the node configured in the extrinsic options.
+///
+/// Chains with several tokens report an array of decimals, the first one is the native token.
+pub(crate) async fn token_decimals(extrinsic_opts: &ExtrinsicOpts) -> Result<u8> {
+    parse_token_decimals(&system_properties(extrinsic_opts).await?)
+}
+
+/// Fetches the SS58 address prefix of the chain from the `system_properties` RPC method of the
+/// node configured in the extrinsic options.
+///
+/// Returns `None` if the node does not report one, i.e. the chain uses the default prefix.
+pub(crate) async fn ss58_prefix(extrinsic_opts: &ExtrinsicOpts) -> Result<Option<u8>> {
+    Ok(parse_ss58_prefix(&system_properties(extrinsic_opts).await?))
+}
+
+async fn system_properties(
+    extrinsic_opts: &ExtrinsicOpts,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let client = connect_opts(extrinsic_opts).await?;
+    let properties = client.request("system_properties", Params::None).await?;
+    Ok(properties)
+}
+
+fn parse_ss58_prefix(properties: &serde_json::Map<String, serde_json::Value>) -> Option<u8> {
+    properties
+        .get("ss58Format")
+        .and_then(serde_json::Value::as_u64)
+        .and_then(|prefix| u8::try_from(prefix).ok())
+}
+
+fn parse_token_decimals(properties: &serde_json::Map<String, serde_json::Value>) -> Result<u8> {
+    let decimals = match properties.get("tokenDecimals") {
+        Some(serde_json::Value::Array(decimals)) => decimals.first(),
+        decimals => decimals,
+    };
+    decimals
+        .and_then(serde_json::Value::as_u64)
+        .and_then(|decimals| u8::try_from(decimals).ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "The node does not report the decimals of its token, pass them with --decimals"
+            )
+        })
+}
+
+/// Estimates the gas required to instantiate the supplied code.
+///
+/// Performs a dry run of the instantiation via the `contracts_instantiate` RPC method, nothing is
+/// submitted to the chain. Older contracts pallets do not provide the method, which is reported
+/// as an error asking for an explicit gas limit.
+pub(crate) async fn estimate_instantiate_gas(
+    client: &jsonrpsee::Client,
+    origin: <ContractsTemplateRuntime as System>::AccountId,
+    endowment: <ContractsTemplateRuntime as Balances>::Balance,
+    code: Code,
+    data: &[u8],
+    salt: &[u8],
+) -> Result<u64> {
+    let request = InstantiateRequest {
+        origin,
+        endowment,
+        gas_limit: DRY_RUN_GAS_LIMIT,
+        code,
+        data: data.to_vec().into(),
+        salt: salt.to_vec().into(),
+    };
+    // nodes which cannot list their methods are asked for the estimate regardless
+    let methods: Result<RpcMethods, _> = client.request("rpc_methods", Params::None).await;
+    if matches!(methods, Ok(listed) if !listed.contains("contracts_instantiate")) {
+        anyhow::bail!("gas estimation requires a newer contracts pallet, pass --gas")
+    }
+    let params = Params::Array(vec![to_json_value(request)?]);
+    let result: InstantiateResult = client.request("contracts_instantiate", params).await?;
+    Ok(result.gas_consumed)
+}
+
+/// Calls a message of the contract at `dest` without submitting an extrinsic.
+///
+/// Performs a dry run of the call via the `contracts_call` RPC method, the storage of the contract
+/// is left unchanged and no fees are paid.
+pub(crate) async fn dry_run_call(
+    client: &jsonrpsee::Client,
+    origin: <ContractsTemplateRuntime as System>::AccountId,
+    dest: <ContractsTemplateRuntime as System>::AccountId,
+    value: <ContractsTemplateRuntime as Balances>::Balance,
+    gas_limit: u64,
+    input_data: &[u8],
+) -> Result<DryRunCall> {
+    let request = CallRequest {
+        origin,
+        dest,
+        value,
+        gas_limit,
+        input_data: input_data.to_vec().into(),
+    };
+    let params = Params::Array(vec![to_json_value(request)?]);
+    match client.request("contracts_call", params).await? {
+        CallResult::Success {
+            flags,
+            data,
+            gas_consumed,
+        } => Ok(DryRunCall {
+            data: data.0,
+            gas_consumed,
+            // the first bit of the flags is set if the contract reverted
+            reverted: flags & 1 == 1,
+        }),
+        CallResult::Error(()) => anyhow::bail!("The dry run of the call failed on the node"),
+    }
+}
+
+/// Information about a contract account, as stored in the `Contracts::ContractInfoOf` map.
+///
+/// All fields are decoded to match the storage layout, not all of them are reported.
+#[allow(dead_code)]
+#[derive(Decode)]
+pub(crate) enum ContractInfo {
+    /// The contract is alive and can be called.
+    Alive(AliveContractInfo),
+    /// The contract was evicted, only a hash of its storage remains.
+    Tombstone(<ContractsTemplateRuntime as System>::Hash),
+}
+
+/// Information about a contract which is alive.
+#[allow(dead_code)]
+#[derive(Decode)]
+pub(crate) struct AliveContractInfo {
+    pub trie_id: Vec<u8>,
+    pub storage_size: u32,
+    pub empty_pair_count: u32,
+    pub total_pair_count: u32,
+    pub code_hash: <ContractsTemplateRuntime as System>::Hash,
+    pub rent_allowance: <ContractsTemplateRuntime as Balances>::Balance,
+    pub deduct_block: <ContractsTemplateRuntime as System>::BlockNumber,
+    pub last_write: Option<<ContractsTemplateRuntime as System>::BlockNumber>,
+}
+
+/// Fetches the original wasm code stored on chain under the supplied code hash, if any.
+///
+/// Reads the `Contracts::PristineCode` storage map via the `state_getStorage` RPC method.
+pub(crate) async fn fetch_pristine_code(
+    client: &jsonrpsee::Client,
+    code_hash: &<ContractsTemplateRuntime as System>::Hash,
+) -> Result<Option<Vec<u8>>> {
+    let mut key = contracts_storage_key(b"PristineCode");
+    // the map uses the identity hasher, the code hash is appended to the prefix as is
+    key.extend_from_slice(code_hash.as_ref());
+    fetch_storage(client, key).await
+}
+
+/// Fetches the information stored on chain about the contract at the supplied address, if any.
+///
+/// Reads the `Contracts::ContractInfoOf` storage map via the `state_getStorage` RPC method.
+pub(crate) async fn fetch_contract_info(
+    client: &jsonrpsee::Client,
+    address: &<ContractsTemplateRuntime as System>::AccountId,
+) -> Result<Option<ContractInfo>> {
+    let mut key = contracts_storage_key(b"ContractInfoOf");
+    // the map uses the twox64 concat hasher
+    let address: &[u8] = address.as_ref();
+    key.extend_from_slice(&sp_core::twox_64(address));
+    key.extend_from_slice(address);
+    fetch_storage(client, key).await
+}
+
+/// Fetches all key value pairs stored by the contract with the supplied trie id, see
+/// [`AliveContractInfo`].
+///
+/// The storage of a contract lives in a child trie, which is read via the `childstate_getKeys`
+/// and `childstate_getStorage` RPC methods. The keys are the blake2 256 hashes of the keys the
+/// contract stored the values under.
+pub(crate) async fn fetch_contract_storage(
+    client: &jsonrpsee::Client,
+    trie_id: &[u8],
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let child_key = Bytes(child_storage_key(trie_id));
+    let params = Params::Array(vec![
+        to_json_value(&child_key)?,
+        to_json_value(Bytes(Vec::new()))?,
+    ]);
+    let keys: Vec<Bytes> = client.request("childstate_getKeys", params).await?;
+    let mut storage = Vec::with_capacity(keys.len());
+    for key in keys {
+        let params = Params::Array(vec![to_json_value(&child_key)?, to_json_value(&key)?]);
+        let value: Option<Bytes> = client.request("childstate_getStorage", params).await?;
+        // the value may have been removed since the keys were fetched
+        if let Some(value) = value {
+            storage.push((key.0, value.0));
+        }
+    }
+    Ok(storage)
+}
+
+/// Submits a signed extrinsic via the `author_submitExtrinsic` RPC method, and returns its hash.
+pub(crate) async fn submit_extrinsic(
+    client: &jsonrpsee::Client,
+    extrinsic: &[u8],
+) -> Result<<ContractsTemplateRuntime as System>::Hash> {
+    let params = Params::Array(vec![to_json_value(Bytes(extrinsic.to_vec()))?]);
+    let hash = client.request("author_submitExtrinsic", params).await?;
+    Ok(hash)
+}
+
+/// A block as returned by the `chain_getBlock` RPC method, with the extrinsics still encoded.
+#[derive(Deserialize)]
+struct SignedBlock {
+    block: Block,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    extrinsics: Vec<Bytes>,
+}
+
+/// Returns the index of the extrinsic with the supplied hash within the block, if it is found.
+///
+/// Fetches the block via the `chain_getBlock` RPC method.
+pub(crate) async fn extrinsic_index(
+    client: &jsonrpsee::Client,
+    block_hash: &<ContractsTemplateRuntime as System>::Hash,
+    extrinsic_hash: &<ContractsTemplateRuntime as System>::Hash,
+) -> Result<Option<usize>> {
+    let params = Params::Array(vec![to_json_value(block_hash)?]);
+    let block: Option<SignedBlock> = client.request("chain_getBlock", params).await?;
+    let index = block.and_then(|block| {
+        block
+            .block
+            .extrinsics
+            .iter()
+            .position(|extrinsic| sp_core::blake2_256(&extrinsic.0) == extrinsic_hash.0)
+    });
+    Ok(index)
+}
+
+/// The subset of a block header required to follow finality.
+#[derive(Deserialize)]
+struct Header {
+    /// The block number, hex encoded.
+    number: String,
+}
+
+/// Returns the number of the block with the supplied hash.
+///
+/// Fetches the header via the `chain_getHeader` RPC method.
+async fn block_number(
+    client: &jsonrpsee::Client,
+    block_hash: &<ContractsTemplateRuntime as System>::Hash,
+) -> Result<u64> {
+    let params = Params::Array(vec![to_json_value(block_hash)?]);
+    let header: Option<Header> = client.request("chain_getHeader", params).await?;
+    let header = header.ok_or_else(|| anyhow::anyhow!("Block {:?} not found", block_hash))?;
+    parse_block_number(&header.number)
+}
+
+fn parse_block_number(number: &str) -> Result<u64> {
+    u64::from_str_radix(number.trim_start_matches("0x"), 16)
+        .map_err(|err| anyhow::anyhow!("Invalid block number '{}': {}", number, err))
+}
+
+/// Waits until the block with the supplied hash is finalized.
+///
+/// Polls the finalized head via the `chain_getFinalizedHead` RPC method until it reaches the
+/// number of the block, and then checks that the block is part of the finalized chain. An error
+/// is returned if the block was retracted by a reorg instead, or if it is not finalized within the
+/// `timeout`.
+pub(crate) async fn wait_for_finalized(
+    client: &jsonrpsee::Client,
+    block_hash: &<ContractsTemplateRuntime as System>::Hash,
+    timeout: Duration,
+) -> Result<()> {
+    async_std::future::timeout(timeout, poll_finalized(client, block_hash))
+        .await
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "Block {:?} was not finalized within {} seconds, the extrinsic was included but \
+                may not be final. Pass a longer --timeout to wait longer",
+                block_hash,
+                timeout.as_secs()
+            )
+        })?
+}
+
+async fn poll_finalized(
+    client: &jsonrpsee::Client,
+    block_hash: &<ContractsTemplateRuntime as System>::Hash,
+) -> Result<()> {
+    let number = block_number(client, block_hash).await?;
+    loop {
+        let finalized_head: <ContractsTemplateRuntime as System>::Hash = client
+            .request("chain_getFinalizedHead", Params::None)
+            .await?;
+        if block_number(client, &finalized_head).await? >= number {
+            let params = Params::Array(vec![to_json_value(number)?]);
+            let finalized: Option<<ContractsTemplateRuntime as System>::Hash> =
+                client.request("chain_getBlockHash", params).await?;
+            if finalized.as_ref() == Some(block_hash) {
+                return Ok(());
+            }
+            anyhow::bail!(
+                "Block {:?} was retracted before it was finalized, the extrinsic may have been \
+                included in another block",
+                block_hash
+            )
+        }
+        async_std::task::sleep(FINALITY_POLL_INTERVAL).await;
+    }
+}
+
+/// Returns the prefix of the storage item of the contracts pallet with the supplied name.
+fn contracts_storage_key(item: &[u8]) -> Vec<u8> {
+    let mut key = sp_core::twox_128(b"Contracts").to_vec();
+    key.extend_from_slice(&sp_core::twox_128(item));
+    key
+}
+
+/// Returns the key of the default child trie with the supplied id, as expected by the
+/// `childstate` RPC methods.
+fn child_storage_key(trie_id: &[u8]) -> Vec<u8> {
+    let mut key = b":child_storage:default:".to_vec();
+    key.extend_from_slice(trie_id);
+    key
+}
+
+/// Fetches and decodes the value stored under the supplied key, if any.
+async fn fetch_storage<V: Decode>(client: &jsonrpsee::Client, key: Vec<u8>) -> Result<Option<V>> {
+    let params = Params::Array(vec![to_json_value(Bytes(key))?]);
+    let data: Option<Bytes> = client.request("state_getStorage", params).await?;
+    let value = data.map(|data| V::decode(&mut &data[..])).transpose()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_ss58_prefix, parse_token_decimals};
+
+    fn properties(json: &str) -> serde_json::Map<String, serde_json::Value> {
+        serde_json::from_str(json).expect("valid properties")
+    }
+
+    #[test]
+    fn only_websockets_support_subscriptions() {
+        let supports = |url: &str| super::supports_subscriptions(&url::Url::parse(url).unwrap());
+        assert!(supports("ws://localhost:9944"));
+        assert!(supports("wss://rococo-rpc.polkadot.io"));
+        assert!(!supports("http://localhost:9933"));
+        assert!(!supports("https://rococo-rpc.polkadot.io"));
+    }
+
+    #[test]
+    fn token_decimals_are_parsed() {
+        let single = properties(r#"{"tokenDecimals": 12, "tokenSymbol": "UNIT"}"#);
+        assert_eq!(parse_token_decimals(&single).unwrap(), 12);
+
+        let multiple = properties(r#"{"tokenDecimals": [10, 18]}"#);
+        assert_eq!(parse_token_decimals(&multiple).unwrap(), 10);
+
+        assert!(parse_token_decimals(&properties("{}")).is_err());
+    }
+
+    #[test]
+    fn rpc_methods_are_listed() {
+        let methods: super::RpcMethods =
+            serde_json::from_str(r#"{"version": 1, "methods": ["contracts_call", "rpc_methods"]}"#)
+                .expect("valid methods");
+        assert!(methods.contains("contracts_call"));
+        assert!(!methods.contains("contracts_instantiate"));
+    }
+
+    #[test]
+    fn only_decoded_names_match() {
+        use frame_metadata::DecodeDifferent;
+
+        assert!(super::is_named(
+            &DecodeDifferent::Decoded("Balances".into()),
+            "Balances"
+        ));
+        assert!(!super::is_named(
+            &DecodeDifferent::Decoded("System".into()),
+            "Balances"
+        ));
+        assert!(!super::is_named(
+            &DecodeDifferent::Encode("Balances"),
+            "Balances"
+        ));
+    }
+
+    #[test]
+    fn ss58_prefix_is_parsed() {
+        let kusama = properties(r#"{"ss58Format": 2, "tokenDecimals": 12}"#);
+        assert_eq!(parse_ss58_prefix(&kusama), Some(2));
+        assert_eq!(parse_ss58_prefix(&properties("{}")), None);
+    }
+}
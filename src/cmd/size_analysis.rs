@@ -0,0 +1,264 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use parity_wasm::elements::{
+    ImportCountType, Module, NameSection, Section, Serialize as WasmSerialize,
+};
+use serde::Serialize;
+
+use crate::{crate_metadata::CrateMetadata, util, workspace::ManifestPath};
+
+/// The size of a section of the wasm file.
+#[derive(Debug, Serialize)]
+pub struct SectionSize {
+    pub name: String,
+    /// Size in bytes, including the section header.
+    pub size: u64,
+}
+
+/// The size of the code of a function of the wasm file.
+#[derive(Debug, Serialize)]
+pub struct FunctionSize {
+    /// The index of the function in the function index space, which includes the imports.
+    pub index: u32,
+    /// The name of the function from the name section, `func[<index>]` if it has none.
+    pub name: String,
+    /// Size in bytes of the function body.
+    pub size: u64,
+}
+
+/// The break down of the size of a wasm file.
+#[derive(Debug, Serialize)]
+pub struct SizeAnalysisResult {
+    /// The analyzed wasm file.
+    pub wasm: PathBuf,
+    /// Size in bytes of the whole wasm file.
+    pub size: u64,
+    /// The sections in the order of the file.
+    pub sections: Vec<SectionSize>,
+    /// The number of functions defined, i.e. not imported, by the wasm file.
+    pub function_count: usize,
+    /// The largest functions, largest first.
+    pub functions: Vec<FunctionSize>,
+}
+
+impl std::fmt::Display for SizeAnalysisResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let share = |size: u64| 100.0 * size as f64 / self.size.max(1) as f64;
+        write!(
+            f,
+            "{} {} ({})",
+            "Wasm file:".bold(),
+            self.wasm.display(),
+            util::format_size(self.size)
+        )?;
+        write!(f, "\n\t{}", "Sections:".bold())?;
+        for section in &self.sections {
+            write!(
+                f,
+                "\n\t  {:>10} {:>5.1}%  {}",
+                util::format_size(section.size),
+                share(section.size),
+                section.name
+            )?;
+        }
+        write!(
+            f,
+            "\n\t{} {} of {}",
+            "Largest functions:".bold(),
+            self.functions.len(),
+            self.function_count
+        )?;
+        for function in &self.functions {
+            write!(
+                f,
+                "\n\t  {:>10} {:>5.1}%  {}",
+                util::format_size(function.size),
+                share(function.size),
+                function.name
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Breaks the size of the built wasm file of the contract down into its sections and the `top`
+/// largest functions.
+///
+/// The wasm file which is deployed, i.e. the result of `cargo contract build`, has its name section
+/// stripped, so its functions are listed by index. With `raw` the wasm file produced by cargo is
+/// analyzed instead, which contains the names of the functions but is not yet tree shaken and
+/// optimized.
+pub fn execute(manifest_path: &ManifestPath, raw: bool, top: usize) -> Result<SizeAnalysisResult> {
+    let crate_metadata = CrateMetadata::collect(manifest_path)?;
+    let wasm = if raw {
+        crate_metadata.original_wasm
+    } else {
+        crate_metadata.dest_wasm
+    };
+    if !wasm.exists() {
+        anyhow::bail!(
+            "The wasm file {} does not exist, run `cargo contract build` first",
+            wasm.display()
+        )
+    }
+    let code = fs::read(&wasm).context(format!("Reading {}", wasm.display()))?;
+    analyze(wasm, &code, top)
+}
+
+/// Breaks the size of the `code` of the `wasm` file down.
+fn analyze(wasm: PathBuf, code: &[u8], top: usize) -> Result<SizeAnalysisResult> {
+    let module: Module = parity_wasm::deserialize_buffer(code)
+        .context(format!("Loading wasm file {}", wasm.display()))?;
+    // the names are kept as a custom section if they can not be parsed
+    let module = module.parse_names().unwrap_or_else(|(_, module)| module);
+
+    let sections = module
+        .sections()
+        .iter()
+        .map(|section| {
+            Ok(SectionSize {
+                name: section_name(section),
+                size: serialized_size(section.clone())?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let imported = module.import_count(ImportCountType::Function) as u32;
+    let names = match module.names_section() {
+        Some(NameSection::Function(names)) => Some(names.names()),
+        _ => None,
+    };
+    let bodies = module
+        .code_section()
+        .map(|code| code.bodies())
+        .unwrap_or_default();
+    let mut functions = bodies
+        .iter()
+        .enumerate()
+        .map(|(i, body)| {
+            let index = imported + i as u32;
+            Ok(FunctionSize {
+                index,
+                name: names
+                    .and_then(|names| names.get(index))
+                    .cloned()
+                    .unwrap_or_else(|| format!("func[{}]", index)),
+                size: serialized_size(body.clone())?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    functions.sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+    let function_count = functions.len();
+    functions.truncate(top);
+
+    Ok(SizeAnalysisResult {
+        wasm,
+        size: code.len() as u64,
+        sections,
+        function_count,
+        functions,
+    })
+}
+
+/// Returns the name of the section, as printed by the wasm tooling.
+fn section_name(section: &Section) -> String {
+    let name = match section {
+        Section::Custom(custom) => return format!("custom \"{}\"", custom.name()),
+        Section::Unparsed { id, .. } => return format!("unknown {}", id),
+        Section::Type(_) => "type",
+        Section::Import(_) => "import",
+        Section::Function(_) => "function",
+        Section::Table(_) => "table",
+        Section::Memory(_) => "memory",
+        Section::Global(_) => "global",
+        Section::Export(_) => "export",
+        Section::Start(_) => "start",
+        Section::Element(_) => "element",
+        Section::Code(_) => "code",
+        Section::Data(_) => "data",
+        Section::Name(_) => "custom \"name\"",
+        _ => "other",
+    };
+    name.to_string()
+}
+
+/// Returns the number of bytes the wasm element is serialized to.
+fn serialized_size<T: WasmSerialize>(element: T) -> Result<u64>
+where
+    T::Error: std::fmt::Debug,
+{
+    let mut buffer = Vec::new();
+    element
+        .serialize(&mut buffer)
+        .map_err(|err| anyhow::anyhow!("Serializing wasm: {:?}", err))?;
+    Ok(buffer.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use parity_wasm::{
+        builder,
+        elements::{Instruction, Instructions},
+    };
+
+    fn function_body(nops: usize) -> Instructions {
+        let mut instructions = vec![Instruction::Nop; nops];
+        instructions.push(Instruction::End);
+        Instructions::new(instructions)
+    }
+
+    #[test]
+    fn largest_functions_are_listed_first() {
+        let module = builder::module()
+            .function()
+            .signature()
+            .build()
+            .body()
+            .with_instructions(function_body(1))
+            .build()
+            .build()
+            .function()
+            .signature()
+            .build()
+            .body()
+            .with_instructions(function_body(100))
+            .build()
+            .build()
+            .build();
+        let code = parity_wasm::serialize(module).unwrap();
+
+        let result = super::analyze("test.wasm".into(), &code, 1).unwrap();
+
+        assert_eq!(result.size, code.len() as u64);
+        assert_eq!(result.function_count, 2);
+        assert_eq!(result.functions.len(), 1);
+        assert_eq!(result.functions[0].name, "func[1]");
+        assert_eq!(result.functions[0].size, 103);
+        let section_names = result
+            .sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(section_names, vec!["type", "function", "code"]);
+        let sections_size: u64 = result.sections.iter().map(|section| section.size).sum();
+        assert_eq!(sections_size + 8, result.size);
+    }
+}
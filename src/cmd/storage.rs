@@ -0,0 +1,150 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+use sp_core::crypto::AccountId32;
+
+use crate::cmd::{
+    rpc::{self, ContractInfo},
+    Transcoder,
+};
+
+/// A key value pair of the contract storage.
+#[derive(Debug, Serialize)]
+pub struct StorageEntry {
+    /// The key the value is stored under on chain, hex encoded.
+    pub key: String,
+    /// The SCALE encoded value, hex encoded.
+    pub value: String,
+    /// The path of the field in the storage layout of the metadata, if the key is part of it.
+    pub field: Option<String>,
+    /// The value decoded with the metadata, if the key is part of the storage layout.
+    pub decoded: Option<String>,
+}
+
+/// The storage of a contract.
+#[derive(Debug, Serialize)]
+pub struct StorageResult {
+    pub contract: String,
+    pub entries: Vec<StorageEntry>,
+}
+
+impl std::fmt::Display for StorageResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Contract {}: {} storage items",
+            self.contract,
+            self.entries.len()
+        )?;
+        for entry in &self.entries {
+            write!(f, "\n  {}: {}", entry.key, entry.value)?;
+            if let (Some(field), Some(decoded)) = (&entry.field, &entry.decoded) {
+                write!(f, "\n    {} = {}", field, decoded)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the storage of the contract at `address` and returns all its key value pairs.
+///
+/// Nothing is submitted to the chain. If the `metadata_path` is supplied, the values stored in the
+/// cells of the storage layout are decoded as well. The entries of hash maps are only dumped raw,
+/// since their keys are hashed.
+pub fn execute(
+    url: &url::Url,
+    accept_invalid_certs: bool,
+    address: &AccountId32,
+    metadata_path: Option<&Path>,
+) -> Result<StorageResult> {
+    let transcoder = metadata_path.map(Transcoder::load).transpose()?;
+    async_std::task::block_on(async move {
+        let client = rpc::connect(url, accept_invalid_certs).await?;
+        let info = match rpc::fetch_contract_info(&client, address).await? {
+            Some(ContractInfo::Alive(info)) => info,
+            Some(ContractInfo::Tombstone(_)) => {
+                anyhow::bail!("Contract {} was evicted, only a tombstone remains", address)
+            }
+            None => anyhow::bail!("Contract {} not found", address),
+        };
+        let storage = rpc::fetch_contract_storage(&client, &info.trie_id).await?;
+        let entries = storage
+            .into_iter()
+            .map(|(key, value)| {
+                let cell = transcoder.as_ref().and_then(|transcoder| {
+                    transcoder
+                        .decode_storage(&key, &value)
+                        .unwrap_or_else(|err| {
+                            log::warn!(
+                                "Failed to decode storage key 0x{}: {:#}",
+                                hex::encode(&key),
+                                err
+                            );
+                            None
+                        })
+                });
+                let (field, decoded) = cell.map_or((None, None), |(field, decoded)| {
+                    (Some(field), Some(decoded))
+                });
+                StorageEntry {
+                    key: format!("0x{}", hex::encode(key)),
+                    value: format!("0x{}", hex::encode(value)),
+                    field,
+                    decoded,
+                }
+            })
+            .collect();
+        Ok(StorageResult {
+            contract: address.to_string(),
+            entries,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StorageEntry, StorageResult};
+
+    #[test]
+    fn decoded_values_are_shown_below_the_raw_entries() {
+        let result = StorageResult {
+            contract: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".into(),
+            entries: vec![
+                StorageEntry {
+                    key: "0x01".into(),
+                    value: "0x2a000000".into(),
+                    field: Some("balances.total".into()),
+                    decoded: Some("42".into()),
+                },
+                StorageEntry {
+                    key: "0x02".into(),
+                    value: "0x00".into(),
+                    field: None,
+                    decoded: None,
+                },
+            ],
+        };
+        assert_eq!(
+            result.to_string(),
+            "Contract 5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY: 2 storage items\n  \
+             0x01: 0x2a000000\n    balances.total = 42\n  0x02: 0x00"
+        );
+    }
+}
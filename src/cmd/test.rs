@@ -0,0 +1,66 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+    crate_metadata::CrateMetadata, reporter::Reporter, util, workspace::ManifestPath, Features,
+    Verbosity,
+};
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+/// Executes the off-chain unit tests of the smart-contract.
+///
+/// It does so by invoking `cargo test` for the contract crate, so the tests are compiled for the
+/// host with the default (`std`) features enabled, which is what the ink! off-chain environment
+/// requires, unless `--no-default-features` is supplied.
+///
+/// The output of the test run is forwarded unless `Verbosity::Quiet` is supplied.
+pub fn execute(
+    manifest_path: &ManifestPath,
+    verbosity: Option<Verbosity>,
+    features: &Features,
+) -> Result<String> {
+    let crate_metadata = CrateMetadata::collect(manifest_path)?;
+
+    Reporter::new(verbosity).step(1, 1, "Running tests");
+    let mut args = vec![crate_metadata.manifest_path.cargo_arg()];
+    args.extend(features.cargo_args());
+    util::invoke_cargo_streaming("test", &args, None::<&Path>, verbosity)?;
+
+    Ok(format!(
+        "All tests of {} passed",
+        crate_metadata.package_name.bold()
+    ))
+}
+
+#[cfg(feature = "test-ci-only")]
+#[cfg(test)]
+mod tests {
+    use crate::{
+        util::tests::{new_project, with_tmp_dir},
+        Features,
+    };
+
+    #[test]
+    fn test_template() {
+        with_tmp_dir(|path| {
+            let manifest_path = new_project(path);
+            super::execute(&manifest_path, None, &Features::default()).expect("test failed");
+            Ok(())
+        })
+    }
+}
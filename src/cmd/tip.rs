@@ -0,0 +1,74 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{future::Future, pin::Pin};
+
+use subxt::{
+    balances::Balances, system::System, ChargeTransactionPayment, ContractsTemplateRuntime,
+    Runtime, SignedExtra, Signer, UncheckedExtrinsic,
+};
+
+use crate::ExtrinsicSigner;
+
+type SignedPayload = sp_runtime::generic::SignedPayload<
+    subxt::Encoded,
+    <<ContractsTemplateRuntime as Runtime>::Extra as SignedExtra<ContractsTemplateRuntime>>::Extra,
+>;
+
+/// Signs extrinsics with a tip for the block author, to prioritize them on a congested chain.
+///
+/// The client always creates the signed extensions with a tip of 0, the tip is set here before
+/// the payload is signed.
+pub(crate) struct TipSigner {
+    signer: ExtrinsicSigner,
+    tip: <ContractsTemplateRuntime as Balances>::Balance,
+}
+
+impl TipSigner {
+    pub fn new(
+        signer: ExtrinsicSigner,
+        tip: <ContractsTemplateRuntime as Balances>::Balance,
+    ) -> Self {
+        Self { signer, tip }
+    }
+}
+
+impl Signer<ContractsTemplateRuntime> for TipSigner {
+    fn account_id(&self) -> &<ContractsTemplateRuntime as System>::AccountId {
+        self.signer.account_id()
+    }
+
+    fn nonce(&self) -> Option<<ContractsTemplateRuntime as System>::Index> {
+        self.signer.nonce()
+    }
+
+    fn sign(
+        &self,
+        payload: SignedPayload,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<UncheckedExtrinsic<ContractsTemplateRuntime>, String>>
+                + Send
+                + Sync,
+        >,
+    > {
+        let (call, mut extra, additional_signed) = payload.deconstruct();
+        // the transaction payment extension is the last one of the default signed extensions
+        extra.6 = ChargeTransactionPayment(self.tip);
+        self.signer
+            .sign(SignedPayload::from_raw(call, extra, additional_signed))
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A websocket transport for the RPC client over TLS, for connecting to `wss://` node urls.
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use anyhow::{Context, Result};
+use async_std::net::TcpStream;
+use async_tls::{client::TlsStream, TlsConnector};
+use jsonrpsee::{common, transport::TransportClient};
+use rustls::{
+    Certificate, ClientConfig, RootCertStore, ServerCertVerified, ServerCertVerifier, TLSError,
+};
+use soketto::{
+    connection::{Receiver, Sender},
+    handshake::{Client, ServerResponse},
+};
+
+/// Connects to the node at the supplied `wss://` url, returning the transport for an RPC client.
+///
+/// If `accept_invalid_certs` is set, the certificate of the node is not verified. Only use this
+/// for nodes with self-signed certificates you trust, e.g. on a staging network.
+pub(crate) async fn transport(url: &url::Url, accept_invalid_certs: bool) -> Result<WssTransport> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing host in url {}", url))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let mut config = ClientConfig::new();
+    config
+        .root_store
+        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    if accept_invalid_certs {
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(AcceptAnyCertificate));
+    }
+
+    let socket = TcpStream::connect((host, port))
+        .await
+        .context(format!("Connecting to {}", url))?;
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(host, socket)
+        .await
+        .context(format!("Establishing a TLS connection to {}", url))?;
+
+    let mut client = Client::new(stream, host, url.path());
+    match client.handshake().await? {
+        ServerResponse::Accepted { .. } => (),
+        ServerResponse::Redirect { status_code, .. } | ServerResponse::Rejected { status_code } => {
+            anyhow::bail!(
+                "The websocket handshake with {} failed with status code {}",
+                url,
+                status_code
+            )
+        }
+    }
+    let (sender, receiver) = client.into_builder().finish();
+    Ok(WssTransport { sender, receiver })
+}
+
+/// Sends requests and receives responses over a websocket connection secured by TLS.
+pub(crate) struct WssTransport {
+    sender: Sender<TlsStream<TcpStream>>,
+    receiver: Receiver<TlsStream<TcpStream>>,
+}
+
+impl TransportClient for WssTransport {
+    type Error = soketto::connection::Error;
+
+    fn send_request<'a>(
+        &'a mut self,
+        request: common::Request,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = common::to_string(&request).expect("requests are serializable; qed");
+            self.sender.send_text(request).await?;
+            self.sender.flush().await
+        })
+    }
+
+    fn next_response<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<common::Response, Self::Error>> + Send + 'a>> {
+        Box::pin(async move {
+            loop {
+                let mut message = Vec::new();
+                self.receiver.receive_data(&mut message).await?;
+                match common::from_slice(&message) {
+                    Ok(response) => return Ok(response),
+                    Err(err) => log::warn!("Ignoring invalid response from the node: {}", err),
+                }
+            }
+        })
+    }
+}
+
+/// Accepts any certificate presented by the node, see `--accept-invalid-certs`.
+struct AcceptAnyCertificate;
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        _presented_certs: &[Certificate],
+        _dns_name: webpki::DNSNameRef<'_>,
+        _ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
@@ -0,0 +1,780 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encodes the input data of contract calls from their human readable form, e.g. `new(true, 42)`,
+//! and decodes their return values, events and storage, using the contract metadata.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use codec::{Compact, Decode, Encode};
+use serde_json::Value;
+use sp_core::crypto::{AccountId32, Ss58Codec};
+
+/// The kind of contract call to encode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    /// A constructor, called on instantiation.
+    Constructor,
+    /// A message, called on an instantiated contract.
+    Message,
+}
+
+impl MessageKind {
+    fn spec_key(self) -> &'static str {
+        match self {
+            MessageKind::Constructor => "constructors",
+            MessageKind::Message => "messages",
+        }
+    }
+}
+
+/// Encodes contract calls and decodes their results according to the contract metadata.
+pub struct Transcoder {
+    metadata: Value,
+}
+
+impl Transcoder {
+    /// Loads the contract metadata, as written by `generate-metadata`.
+    pub fn load(metadata_path: &Path) -> Result<Self> {
+        let contents = fs::read(metadata_path).context(format!(
+            "Reading contract metadata {}",
+            metadata_path.display()
+        ))?;
+        let metadata = serde_json::from_slice(&contents).context(format!(
+            "Parsing contract metadata {}",
+            metadata_path.display()
+        ))?;
+        Ok(Self { metadata })
+    }
+
+    /// Encodes a call of the constructor or message `name` with the supplied arguments, as the
+    /// selector followed by the SCALE encoded arguments.
+    ///
+    /// If `args` is empty, `name` may contain the arguments itself, e.g. `new(true, 42)`.
+    pub fn encode(&self, kind: MessageKind, name: &str, args: &[String]) -> Result<Vec<u8>> {
+        let (name, args) = match parse_call(name)? {
+            (name, Some(call_args)) if args.is_empty() => (name, call_args),
+            (_, Some(_)) => {
+                anyhow::bail!("Pass the arguments either in parentheses or with --arg, not both")
+            }
+            (name, None) => (name, args.to_vec()),
+        };
+
+        let specs = self.metadata["spec"][kind.spec_key()]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("No {} found in the metadata", kind.spec_key()))?;
+        let spec = specs
+            .iter()
+            .find(|spec| spec_name(spec).as_deref() == Some(name.as_str()))
+            .ok_or_else(|| {
+                let names = specs.iter().filter_map(spec_name).collect::<Vec<_>>();
+                anyhow::anyhow!(
+                    "No {} named '{}' found, expected one of {}",
+                    kind.spec_key().trim_end_matches('s'),
+                    name,
+                    names.join(", ")
+                )
+            })?;
+
+        let mut encoded = parse_selector(&spec["selector"])?;
+        let spec_args = spec["args"].as_array().cloned().unwrap_or_default();
+        if spec_args.len() != args.len() {
+            anyhow::bail!(
+                "'{}' expects {} arguments, but {} were supplied",
+                name,
+                spec_args.len(),
+                args.len()
+            )
+        }
+        for (spec_arg, arg) in spec_args.iter().zip(&args) {
+            let type_id = arg_type_id(&spec_arg["type"])
+                .ok_or_else(|| anyhow::anyhow!("Missing type of argument {}", spec_arg["name"]))?;
+            self.encode_value(type_id, arg, &mut encoded)
+                .context(format!(
+                    "Encoding argument {} of '{}'",
+                    spec_arg["name"], name
+                ))?;
+        }
+        Ok(encoded)
+    }
+
+    /// Returns the name of the constructor to call if none is selected, which is only defined if
+    /// the contract has a single constructor.
+    pub fn default_constructor(&self) -> Result<String> {
+        let names = self.metadata["spec"]["constructors"]
+            .as_array()
+            .map(|constructors| {
+                constructors
+                    .iter()
+                    .filter_map(spec_name)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        match names.as_slice() {
+            [name] => Ok(name.clone()),
+            [] => anyhow::bail!("No constructors found in the metadata"),
+            names => anyhow::bail!(
+                "The contract has {} constructors, select one with --constructor: {}",
+                names.len(),
+                names.join(", ")
+            ),
+        }
+    }
+
+    /// Decodes the return value of a message call. The message is looked up by the selector at
+    /// the start of the call's input data.
+    pub fn decode_return(&self, input: &[u8], output: &[u8]) -> Result<String> {
+        let message = self.message_by_selector(input)?;
+        let return_type = message
+            .get("returnType")
+            .or_else(|| message.get("return_type"))
+            .and_then(arg_type_id);
+        match return_type {
+            Some(type_id) => self.decode_value(type_id, &mut &output[..]),
+            None => Ok("no return value".to_string()),
+        }
+    }
+
+    /// Returns whether the message called with the `input` data may mutate the storage of the
+    /// contract. Messages which the metadata does not mark as read-only are assumed to mutate.
+    pub fn is_mutating(&self, input: &[u8]) -> Result<bool> {
+        let message = self.message_by_selector(input)?;
+        Ok(message["mutates"].as_bool().unwrap_or(true))
+    }
+
+    /// Looks up the message by the selector at the start of the call's input data.
+    fn message_by_selector(&self, input: &[u8]) -> Result<&Value> {
+        let selector = input
+            .get(..4)
+            .ok_or_else(|| anyhow::anyhow!("Call data is missing the message selector"))?;
+        let messages = self.metadata["spec"]["messages"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("No messages found in the metadata"))?;
+        messages
+            .iter()
+            .find(|message| parse_selector(&message["selector"]).ok().as_deref() == Some(selector))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No message with selector 0x{} found in the metadata",
+                    hex::encode(selector)
+                )
+            })
+    }
+
+    /// Decodes an event emitted by the contract, given as the index of the event in the metadata
+    /// followed by its SCALE encoded arguments.
+    pub fn decode_event(&self, data: &[u8]) -> Result<String> {
+        let events = self.metadata["spec"]["events"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("No events found in the metadata"))?;
+        let (index, mut input) = data
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("Event data is empty"))?;
+        let event = events.get(*index as usize).ok_or_else(|| {
+            anyhow::anyhow!("No event with index {} found in the metadata", index)
+        })?;
+        let mut args = Vec::new();
+        for arg in event["args"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            let type_id = arg_type_id(&arg["type"])
+                .ok_or_else(|| anyhow::anyhow!("Missing type of event argument {}", arg["name"]))?;
+            let value = self.decode_value(type_id, &mut input)?;
+            args.push(format!(
+                "{}: {}",
+                arg["name"].as_str().unwrap_or_default(),
+                value
+            ));
+        }
+        Ok(format!(
+            "{} {{ {} }}",
+            spec_name(event).unwrap_or_default(),
+            args.join(", ")
+        ))
+    }
+
+    /// Decodes a value of the contract storage, given the key it is stored under on chain, i.e.
+    /// the blake2 256 hash of the key of the cell in the storage layout.
+    ///
+    /// Returns the path of the field the cell belongs to, e.g. `balances.total`, and the decoded
+    /// value. `None` is returned if the key is not part of the layout, e.g. for the entries of a
+    /// hash map, whose keys cannot be derived from the metadata alone.
+    pub fn decode_storage(&self, key: &[u8], value: &[u8]) -> Result<Option<(String, String)>> {
+        let mut cells = Vec::new();
+        storage_cells(&self.metadata["storage"], "", &mut cells)?;
+        match cells
+            .into_iter()
+            .find(|(_, cell_key, _)| sp_core::blake2_256(cell_key)[..] == *key)
+        {
+            Some((path, _, type_id)) => {
+                Ok(Some((path, self.decode_value(type_id, &mut &value[..])?)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Looks up the type definition in the type registry of the metadata.
+    fn type_def(&self, type_id: u64) -> Result<&Value> {
+        let types = self.metadata["types"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("No types found in the metadata"))?;
+        // registries with explicit ids are 0-based, older ones are referenced by 1-based index
+        let ty = match types.iter().find(|ty| ty["id"].as_u64() == Some(type_id)) {
+            Some(ty) => Some(&ty["type"]),
+            None => (type_id as usize)
+                .checked_sub(1)
+                .and_then(|index| types.get(index)),
+        };
+        ty.map(|ty| &ty["def"])
+            .ok_or_else(|| anyhow::anyhow!("Type {} not found in the metadata", type_id))
+    }
+
+    fn encode_value(&self, type_id: u64, value: &str, out: &mut Vec<u8>) -> Result<()> {
+        let def = self.type_def(type_id)?;
+        let value = value.trim();
+        if let Some(primitive) = def["primitive"].as_str() {
+            return encode_primitive(primitive, value, out);
+        }
+        if let Some(fields) = def["composite"]["fields"].as_array() {
+            return match fields.as_slice() {
+                [field] => {
+                    let type_id = arg_type_id(&field["type"])
+                        .ok_or_else(|| anyhow::anyhow!("Missing type of composite field"))?;
+                    self.encode_value(type_id, value, out)
+                }
+                _ => anyhow::bail!("Composite types with several fields are not supported"),
+            };
+        }
+        if let Some(array) = def["array"].as_object() {
+            let len = array["len"].as_u64().unwrap_or_default() as usize;
+            let bytes = self.decode_bytes(&array["type"], value)?;
+            if bytes.len() != len {
+                anyhow::bail!("Expected {} bytes, got {}", len, bytes.len())
+            }
+            out.extend_from_slice(&bytes);
+            return Ok(());
+        }
+        if let Some(sequence) = def["sequence"].as_object() {
+            self.decode_bytes(&sequence["type"], value)?.encode_to(out);
+            return Ok(());
+        }
+        anyhow::bail!("Unsupported argument type {}", def)
+    }
+
+    fn decode_value(&self, type_id: u64, input: &mut &[u8]) -> Result<String> {
+        let def = self.type_def(type_id)?;
+        if let Some(primitive) = def["primitive"].as_str() {
+            return decode_primitive(primitive, input);
+        }
+        if let Some(fields) = def["composite"]["fields"].as_array() {
+            return match fields.as_slice() {
+                [field] if !field["name"].is_string() => {
+                    let type_id = arg_type_id(&field["type"])
+                        .ok_or_else(|| anyhow::anyhow!("Missing type of composite field"))?;
+                    self.decode_value(type_id, input)
+                }
+                fields => self.decode_fields(fields, input),
+            };
+        }
+        if let Some(variants) = def["variant"]["variants"].as_array() {
+            let index = u8::decode(input)?;
+            let variant = variants
+                .iter()
+                .enumerate()
+                .find(|(i, variant)| {
+                    variant["discriminant"].as_u64().unwrap_or(*i as u64) == index as u64
+                })
+                .map(|(_, variant)| variant)
+                .ok_or_else(|| anyhow::anyhow!("Invalid variant index {}", index))?;
+            let name = variant["name"].as_str().unwrap_or_default();
+            return match variant["fields"].as_array() {
+                Some(fields) if !fields.is_empty() => {
+                    let fields = self.decode_fields(fields, input)?;
+                    let separator = if fields.starts_with('{') { " " } else { "" };
+                    Ok(format!("{}{}{}", name, separator, fields))
+                }
+                _ => Ok(name.to_string()),
+            };
+        }
+        if let Some(array) = def["array"].as_object() {
+            let len = array["len"].as_u64().unwrap_or_default() as usize;
+            return self.decode_elements(&array["type"], len, input);
+        }
+        if let Some(sequence) = def["sequence"].as_object() {
+            let len = <Compact<u32>>::decode(input)?.0 as usize;
+            return self.decode_elements(&sequence["type"], len, input);
+        }
+        if let Some(tuple) = def["tuple"].as_array() {
+            let values = tuple
+                .iter()
+                .map(|ty| {
+                    let type_id = arg_type_id(ty)
+                        .ok_or_else(|| anyhow::anyhow!("Missing type of tuple element"))?;
+                    self.decode_value(type_id, input)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(format!("({})", values.join(", ")));
+        }
+        anyhow::bail!("Unsupported type {}", def)
+    }
+
+    /// Decodes the fields of a struct or enum variant, as `{ name: value }` if they are named or
+    /// as `(value)` otherwise.
+    fn decode_fields(&self, fields: &[Value], input: &mut &[u8]) -> Result<String> {
+        let named = fields.iter().all(|field| field["name"].is_string());
+        let mut values = Vec::new();
+        for field in fields {
+            let type_id = arg_type_id(&field["type"])
+                .ok_or_else(|| anyhow::anyhow!("Missing type of field {}", field["name"]))?;
+            let value = self.decode_value(type_id, input)?;
+            match field["name"].as_str() {
+                Some(name) if named => values.push(format!("{}: {}", name, value)),
+                _ => values.push(value),
+            }
+        }
+        if named {
+            Ok(format!("{{ {} }}", values.join(", ")))
+        } else {
+            Ok(format!("({})", values.join(", ")))
+        }
+    }
+
+    /// Decodes the elements of an array or sequence, showing byte strings hex encoded.
+    fn decode_elements(
+        &self,
+        element_type: &Value,
+        len: usize,
+        input: &mut &[u8],
+    ) -> Result<String> {
+        let element_type =
+            arg_type_id(element_type).ok_or_else(|| anyhow::anyhow!("Missing element type"))?;
+        if self.type_def(element_type)?["primitive"].as_str() == Some("u8") {
+            if input.len() < len {
+                anyhow::bail!("Expected {} bytes, got {}", len, input.len())
+            }
+            let (bytes, rest) = input.split_at(len);
+            *input = rest;
+            return Ok(format!("0x{}", hex::encode(bytes)));
+        }
+        let values = (0..len)
+            .map(|_| self.decode_value(element_type, input))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(format!("[{}]", values.join(", ")))
+    }
+
+    /// Decodes a byte array or sequence, given as hex or, for 32 bytes accounts, in SS58 format.
+    fn decode_bytes(&self, element_type: &Value, value: &str) -> Result<Vec<u8>> {
+        let element_type =
+            arg_type_id(element_type).ok_or_else(|| anyhow::anyhow!("Missing element type"))?;
+        if self.type_def(element_type)?["primitive"].as_str() != Some("u8") {
+            anyhow::bail!("Only arrays and sequences of bytes are supported")
+        }
+        match value.strip_prefix("0x") {
+            Some(hex_value) => Ok(hex::decode(hex_value)?),
+            None => AccountId32::from_ss58check(value)
+                .map(|account| AsRef::<[u8]>::as_ref(&account).to_vec())
+                .or_else(|_| hex::decode(value))
+                .map_err(|_| anyhow::anyhow!("Expected hex encoded bytes or an SS58 address")),
+        }
+    }
+}
+
+fn encode_primitive(primitive: &str, value: &str, out: &mut Vec<u8>) -> Result<()> {
+    match primitive {
+        "bool" => value.parse::<bool>()?.encode_to(out),
+        "u8" => value.parse::<u8>()?.encode_to(out),
+        "u16" => value.parse::<u16>()?.encode_to(out),
+        "u32" => value.parse::<u32>()?.encode_to(out),
+        "u64" => value.parse::<u64>()?.encode_to(out),
+        "u128" => value.parse::<u128>()?.encode_to(out),
+        "i8" => value.parse::<i8>()?.encode_to(out),
+        "i16" => value.parse::<i16>()?.encode_to(out),
+        "i32" => value.parse::<i32>()?.encode_to(out),
+        "i64" => value.parse::<i64>()?.encode_to(out),
+        "i128" => value.parse::<i128>()?.encode_to(out),
+        "str" => value.trim_matches('"').encode_to(out),
+        _ => anyhow::bail!("Unsupported primitive type {}", primitive),
+    }
+    Ok(())
+}
+
+fn decode_primitive(primitive: &str, input: &mut &[u8]) -> Result<String> {
+    let value = match primitive {
+        "bool" => bool::decode(input)?.to_string(),
+        "u8" => u8::decode(input)?.to_string(),
+        "u16" => u16::decode(input)?.to_string(),
+        "u32" => u32::decode(input)?.to_string(),
+        "u64" => u64::decode(input)?.to_string(),
+        "u128" => u128::decode(input)?.to_string(),
+        "i8" => i8::decode(input)?.to_string(),
+        "i16" => i16::decode(input)?.to_string(),
+        "i32" => i32::decode(input)?.to_string(),
+        "i64" => i64::decode(input)?.to_string(),
+        "i128" => i128::decode(input)?.to_string(),
+        "str" => format!("{:?}", String::decode(input)?),
+        _ => anyhow::bail!("Unsupported primitive type {}", primitive),
+    };
+    Ok(value)
+}
+
+/// Collects the cells of a storage layout as their path, key and type id.
+///
+/// Structs and the variants of enums are descended into. Hash maps and arrays are skipped, the
+/// keys of their elements are computed by the contract.
+fn storage_cells(
+    layout: &Value,
+    path: &str,
+    cells: &mut Vec<(String, Vec<u8>, u64)>,
+) -> Result<()> {
+    let join = |name: &str| match path {
+        "" => name.to_string(),
+        path => format!("{}.{}", path, name),
+    };
+    if let Some(cell) = layout["cell"].as_object() {
+        let key = cell["key"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing key of storage cell {}", path))?;
+        let key = hex::decode(key.trim_start_matches("0x"))
+            .context(format!("Invalid key of storage cell {}", path))?;
+        let type_id = arg_type_id(&cell["ty"])
+            .or_else(|| arg_type_id(&cell["type"]))
+            .ok_or_else(|| anyhow::anyhow!("Missing type of storage cell {}", path))?;
+        cells.push((path.to_string(), key, type_id));
+    }
+    let fields = layout["struct"]["fields"].as_array().into_iter().flatten();
+    for (index, field) in fields.enumerate() {
+        let name = field["name"]
+            .as_str()
+            .map_or_else(|| index.to_string(), ToString::to_string);
+        storage_cells(&field["layout"], &join(&name), cells)?;
+    }
+    let variants = layout["enum"]["variants"].as_object().into_iter().flatten();
+    for (discriminant, variant) in variants {
+        for (index, field) in variant["fields"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let name = field["name"]
+                .as_str()
+                .map_or_else(|| index.to_string(), ToString::to_string);
+            storage_cells(
+                &field["layout"],
+                &join(&format!("{}.{}", discriminant, name)),
+                cells,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of a constructor or message spec, which is either a string or a path.
+fn spec_name(spec: &Value) -> Option<String> {
+    match &spec["name"] {
+        Value::String(name) => Some(name.clone()),
+        Value::Array(path) => path.last().and_then(Value::as_str).map(ToString::to_string),
+        _ => None,
+    }
+}
+
+/// Returns the type id of an argument, which is either a plain id or an object holding it.
+fn arg_type_id(ty: &Value) -> Option<u64> {
+    ty.as_u64()
+        .or_else(|| ty["type"].as_u64())
+        .or_else(|| ty["id"].as_u64())
+}
+
+/// Parses a selector, given as `"0xd183512b"` or as a list of hex encoded bytes.
+fn parse_selector(selector: &Value) -> Result<Vec<u8>> {
+    let selector = selector
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing selector"))?;
+    let hex_selector = selector
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit() || *c == 'x')
+        .collect::<String>()
+        .replace("0x", "");
+    let bytes = hex::decode(&hex_selector).context(format!("Invalid selector {}", selector))?;
+    if bytes.len() != 4 {
+        anyhow::bail!("Invalid selector {}, expected 4 bytes", selector)
+    }
+    Ok(bytes)
+}
+
+/// Splits a call like `new(true, 42)` into its name and arguments.
+///
+/// Returns no arguments if the input is a plain name without parentheses.
+fn parse_call(input: &str) -> Result<(String, Option<Vec<String>>)> {
+    let input = input.trim();
+    let open = match input.find('(') {
+        Some(open) => open,
+        None => return Ok((input.to_string(), None)),
+    };
+    if !input.ends_with(')') {
+        anyhow::bail!("Missing closing parenthesis in '{}'", input)
+    }
+    let name = input[..open].trim().to_string();
+    let inner = &input[open + 1..input.len() - 1];
+
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut current = String::new();
+    for c in inner.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                args.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() || !args.is_empty() {
+        args.push(current.trim().to_string());
+    }
+    Ok((name, Some(args)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = r#"{
+        "spec": {
+            "constructors": [
+                {
+                    "name": ["new"],
+                    "selector": "[\"0xd1\", \"0x83\", \"0x51\", \"0x2b\"]",
+                    "args": [
+                        { "name": "init_value", "type": { "type": 1, "displayName": ["bool"] } },
+                        { "name": "count", "type": { "type": 2, "displayName": ["u32"] } }
+                    ]
+                }
+            ],
+            "messages": [
+                {
+                    "name": ["transfer"],
+                    "selector": "0xfae3a09d",
+                    "args": [
+                        { "name": "to", "type": { "type": 3, "displayName": ["AccountId"] } },
+                        { "name": "memo", "type": { "type": 6, "displayName": ["Vec"] } }
+                    ]
+                },
+                {
+                    "name": ["get"],
+                    "selector": "0x2f865bd9",
+                    "mutates": false,
+                    "args": [],
+                    "returnType": { "type": 2, "displayName": ["u32"] }
+                }
+            ],
+            "events": [
+                {
+                    "name": "Transferred",
+                    "args": [
+                        { "name": "to", "type": { "type": 3, "displayName": ["AccountId"] } },
+                        { "name": "amount", "type": { "type": 2, "displayName": ["u32"] } }
+                    ]
+                }
+            ]
+        },
+        "storage": {
+            "struct": {
+                "fields": [
+                    {
+                        "name": "owner",
+                        "layout": { "cell": { "key": "0x0000000000000000000000000000000000000000000000000000000000000000", "ty": 4 } }
+                    },
+                    {
+                        "name": "balances",
+                        "layout": {
+                            "struct": {
+                                "fields": [
+                                    {
+                                        "name": "total",
+                                        "layout": { "cell": { "key": "0x0100000000000000000000000000000000000000000000000000000000000000", "ty": 2 } }
+                                    },
+                                    {
+                                        "name": "entries",
+                                        "layout": { "hash": { "offset": "0x0200000000000000000000000000000000000000000000000000000000000000" } }
+                                    }
+                                ]
+                            }
+                        }
+                    }
+                ]
+            }
+        },
+        "types": [
+            { "def": { "primitive": "bool" } },
+            { "def": { "primitive": "u32" } },
+            { "def": { "composite": { "fields": [ { "type": 4 } ] } } },
+            { "def": { "array": { "len": 32, "type": 5 } } },
+            { "def": { "primitive": "u8" } },
+            { "def": { "sequence": { "type": 5 } } }
+        ]
+    }"#;
+
+    fn transcoder() -> Transcoder {
+        Transcoder {
+            metadata: serde_json::from_str(METADATA).unwrap(),
+        }
+    }
+
+    #[test]
+    fn encodes_constructor_from_call_syntax() {
+        let encoded = transcoder()
+            .encode(MessageKind::Constructor, "new(true, 42)", &[])
+            .unwrap();
+        assert_eq!(encoded, vec![0xd1, 0x83, 0x51, 0x2b, 1, 42, 0, 0, 0]);
+    }
+
+    #[test]
+    fn encodes_message_from_separate_args() {
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string();
+        let encoded = transcoder()
+            .encode(MessageKind::Message, "transfer", &[alice, "0x0102".into()])
+            .unwrap();
+        assert_eq!(&encoded[..4], &[0xfa, 0xe3, 0xa0, 0x9d]);
+        assert_eq!(encoded.len(), 4 + 32 + 3);
+        assert_eq!(&encoded[36..], &[8, 1, 2]);
+    }
+
+    #[test]
+    fn rejects_unknown_message() {
+        let err = transcoder()
+            .encode(MessageKind::Message, "flip", &[])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "No message named 'flip' found, expected one of transfer, get"
+        );
+    }
+
+    #[test]
+    fn default_constructor_requires_a_single_constructor() {
+        assert_eq!(transcoder().default_constructor().unwrap(), "new");
+
+        let mut metadata: Value = serde_json::from_str(METADATA).unwrap();
+        let mut constructor = metadata["spec"]["constructors"][0].clone();
+        constructor["name"] = serde_json::json!(["default"]);
+        metadata["spec"]["constructors"]
+            .as_array_mut()
+            .unwrap()
+            .push(constructor);
+        let err = Transcoder { metadata }.default_constructor().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The contract has 2 constructors, select one with --constructor: new, default"
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_number_of_args() {
+        let err = transcoder()
+            .encode(MessageKind::Constructor, "new(true)", &[])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "'new' expects 2 arguments, but 1 were supplied"
+        );
+    }
+
+    #[test]
+    fn read_only_messages_do_not_mutate() {
+        let transcoder = transcoder();
+        assert!(!transcoder.is_mutating(&[0x2f, 0x86, 0x5b, 0xd9]).unwrap());
+        // messages without the flag are assumed to mutate
+        assert!(transcoder
+            .is_mutating(&[0xfa, 0xe3, 0xa0, 0x9d, 0])
+            .unwrap());
+        assert!(transcoder.is_mutating(&[0xfa]).is_err());
+    }
+
+    #[test]
+    fn decodes_return_value() {
+        let output = transcoder()
+            .decode_return(&[0x2f, 0x86, 0x5b, 0xd9], &42u32.encode())
+            .unwrap();
+        assert_eq!(output, "42");
+    }
+
+    #[test]
+    fn reports_missing_return_value() {
+        let output = transcoder()
+            .decode_return(&[0xfa, 0xe3, 0xa0, 0x9d, 0x01], &[])
+            .unwrap();
+        assert_eq!(output, "no return value");
+    }
+
+    #[test]
+    fn decodes_event() {
+        let mut data = vec![0];
+        data.extend_from_slice(&[1; 32]);
+        7u32.encode_to(&mut data);
+        let event = transcoder().decode_event(&data).unwrap();
+        assert_eq!(
+            event,
+            format!("Transferred {{ to: 0x{}, amount: 7 }}", "01".repeat(32))
+        );
+    }
+
+    #[test]
+    fn decodes_storage_cells_of_the_layout() {
+        let key = |first_byte: u8| {
+            let mut key = [0; 32];
+            key[0] = first_byte;
+            sp_core::blake2_256(&key)
+        };
+        let transcoder = transcoder();
+        assert_eq!(
+            transcoder.decode_storage(&key(1), &42u32.encode()).unwrap(),
+            Some(("balances.total".to_string(), "42".to_string()))
+        );
+        assert_eq!(
+            transcoder.decode_storage(&key(0), &[1; 32]).unwrap(),
+            Some(("owner".to_string(), format!("0x{}", "01".repeat(32))))
+        );
+        assert_eq!(transcoder.decode_storage(&key(2), &[]).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_call_splits_top_level_commas() {
+        assert_eq!(parse_call("flip").unwrap(), ("flip".to_string(), None));
+        assert_eq!(
+            parse_call(r#"new("a, b", [1, 2])"#).unwrap(),
+            (
+                "new".to_string(),
+                Some(vec![r#""a, b""#.to_string(), "[1, 2]".to_string()])
+            )
+        );
+        assert_eq!(
+            parse_call("get()").unwrap(),
+            ("get".to_string(), Some(vec![]))
+        );
+    }
+}
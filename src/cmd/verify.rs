@@ -0,0 +1,241 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{fs, ops::Range, path::Path, process::Command};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cmd::build::{self, OptimizationPasses},
+    crate_metadata::CrateMetadata,
+    interrupt::CleanupGuard,
+    reporter::Reporter,
+    workspace::ManifestPath,
+    Features, UnstableFlags, Verbosity,
+};
+
+/// The maximum number of differing byte ranges which are reported.
+const MAX_REPORTED_RANGES: usize = 10;
+
+/// The result of verifying a wasm file against the source of the contract.
+#[derive(Debug, Serialize)]
+pub struct VerifyResult {
+    /// Hash of the supplied wasm file, hex encoded.
+    pub expected_hash: String,
+    /// Hash of the wasm file rebuilt from source, hex encoded.
+    pub actual_hash: String,
+    /// The byte ranges in which the rebuilt wasm differs from the supplied one.
+    pub differences: Vec<Range<usize>>,
+    /// Properties of the build environment which the resulting wasm depends upon.
+    pub environment: Vec<String>,
+}
+
+impl VerifyResult {
+    /// Returns whether the rebuilt wasm matches the supplied one.
+    pub fn matches(&self) -> bool {
+        self.expected_hash == self.actual_hash
+    }
+
+    /// Renders the differing byte ranges and the build environment, to track down a mismatch.
+    pub fn mismatch_report(&self) -> String {
+        let mut report = format!(
+            "The wasm rebuilt from source does not match.\n  Expected code hash: {}\n  \
+             Actual code hash:   {}\n  Differing byte ranges:",
+            self.expected_hash, self.actual_hash
+        );
+        for range in self.differences.iter().take(MAX_REPORTED_RANGES) {
+            report.push_str(&format!("\n    {:#x}..{:#x}", range.start, range.end));
+        }
+        if self.differences.len() > MAX_REPORTED_RANGES {
+            report.push_str(&format!(
+                "\n    and {} more",
+                self.differences.len() - MAX_REPORTED_RANGES
+            ));
+        }
+        report.push_str("\nThe result depends on the build environment, make sure it matches:");
+        for note in &self.environment {
+            report.push_str(&format!("\n  - {}", note));
+        }
+        report
+    }
+}
+
+/// Verifies that the wasm file at `wasm_path` was built from the source of the contract.
+///
+/// The contract is rebuilt in release mode into a fresh temporary target directory, so that no
+/// stale artifacts are picked up, and the code hash of the result is compared to the one of the
+/// supplied wasm file. The default optimization passes are applied, see
+/// [`OptimizationPasses`](build::OptimizationPasses).
+///
+/// # Note
+///
+/// The build is only reproducible with the same toolchain and `wasm-opt` version. These, as well
+/// as the location of the source, are reported together with a mismatch.
+pub fn execute(
+    manifest_path: &ManifestPath,
+    wasm_path: &Path,
+    verbosity: Option<Verbosity>,
+    unstable_options: UnstableFlags,
+    features: &Features,
+) -> Result<VerifyResult> {
+    let expected = fs::read(wasm_path).context(format!("Reading {}", wasm_path.display()))?;
+
+    let target_dir = tempfile::Builder::new()
+        .prefix("cargo-contract-verify_")
+        .tempdir()?;
+    let _cleanup = CleanupGuard::new(target_dir.path());
+    let crate_metadata =
+        CrateMetadata::collect(manifest_path)?.with_target_directory(target_dir.path())?;
+    let result = build::execute_with_metadata(
+        &crate_metadata,
+        verbosity,
+        false,
+        OptimizationPasses::default(),
+        true,
+        Default::default(),
+        Default::default(),
+        unstable_options,
+        features,
+    )?;
+    let actual = fs::read(&result.dest_wasm)?;
+
+    let result = VerifyResult {
+        expected_hash: hex_hash(&expected),
+        actual_hash: result.code_hash,
+        differences: differing_ranges(&expected, &actual),
+        environment: build_environment(manifest_path)?,
+    };
+    let reporter = Reporter::new(verbosity);
+    reporter.verbose("Build environment:".bright_blue().bold());
+    for note in &result.environment {
+        reporter.verbose(format_args!("  - {}", note));
+    }
+    reporter.info(format_args!(
+        " {} {}",
+        "Rebuilt code hash:".bold(),
+        result.actual_hash
+    ));
+    Ok(result)
+}
+
+/// Returns the code hash of the wasm, hex encoded with a `0x` prefix.
+fn hex_hash(wasm: &[u8]) -> String {
+    let hash = build::code_hash(wasm)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("0x{}", hash)
+}
+
+/// Returns the ranges of bytes which differ between `expected` and `actual`.
+///
+/// If one is longer than the other, the surplus bytes form the last range.
+fn differing_ranges(expected: &[u8], actual: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (index, (a, b)) in expected.iter().zip(actual).enumerate() {
+        if a == b {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(range) if range.end == index => range.end += 1,
+            _ => ranges.push(index..index + 1),
+        }
+    }
+    let (common, total) = (
+        expected.len().min(actual.len()),
+        expected.len().max(actual.len()),
+    );
+    if common < total {
+        match ranges.last_mut() {
+            Some(range) if range.end == common => range.end = total,
+            _ => ranges.push(common..total),
+        }
+    }
+    ranges
+}
+
+/// Describes the parts of the build environment which can affect the resulting wasm.
+fn build_environment(manifest_path: &ManifestPath) -> Result<Vec<String>> {
+    let rustc = rustc_version::version_meta()?;
+    let mut environment = vec![format!(
+        "rustc {} ({}), on channel {:?}",
+        rustc.semver,
+        rustc.commit_hash.as_deref().unwrap_or("unknown commit"),
+        rustc.channel
+    )];
+    let wasm_opt = Command::new("wasm-opt")
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    environment.push(match wasm_opt {
+        Some(version) => version,
+        None => "wasm-opt is not installed, so the wasm is not optimized".to_string(),
+    });
+    let source_dir = match manifest_path.directory() {
+        Some(dir) => dir.canonicalize()?,
+        None => std::env::current_dir()?,
+    };
+    environment.push(format!(
+        "built from {}, absolute paths of the source can end up in the wasm",
+        source_dir.display()
+    ));
+    Ok(environment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{differing_ranges, VerifyResult};
+
+    #[test]
+    fn identical_wasm_has_no_differences() {
+        assert!(differing_ranges(b"\0asm", b"\0asm").is_empty());
+    }
+
+    #[test]
+    fn differing_bytes_are_merged_into_ranges() {
+        let expected = [0, 1, 2, 3, 4, 5, 6, 7];
+        let actual = [0, 9, 9, 3, 4, 9, 6, 7];
+        assert_eq!(differing_ranges(&expected, &actual), vec![1..3, 5..6]);
+    }
+
+    #[test]
+    fn surplus_bytes_are_a_difference() {
+        assert_eq!(differing_ranges(&[0, 1], &[0, 1, 2, 3]), vec![2..4]);
+        assert_eq!(differing_ranges(&[0, 1, 2], &[0, 9]), vec![1..3]);
+    }
+
+    #[test]
+    fn mismatch_report_lists_ranges_and_environment() {
+        let result = VerifyResult {
+            expected_hash: "0x01".into(),
+            actual_hash: "0x02".into(),
+            differences: vec![0x10..0x20],
+            environment: vec!["rustc 1.49.0-nightly".into()],
+        };
+        assert!(!result.matches());
+        assert_eq!(
+            result.mismatch_report(),
+            "The wasm rebuilt from source does not match.\n  Expected code hash: 0x01\n  \
+             Actual code hash:   0x02\n  Differing byte ranges:\n    0x10..0x20\n\
+             The result depends on the build environment, make sure it matches:\n  \
+             - rustc 1.49.0-nightly"
+        );
+    }
+}
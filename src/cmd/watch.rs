@@ -0,0 +1,208 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::{
+    cmd::{
+        build::{self, OptimizationPasses},
+        metadata::fingerprint,
+    },
+    crate_metadata::CrateMetadata,
+    interrupt,
+    reporter::Reporter,
+    util,
+    workspace::ManifestPath,
+    Features, LockFlags, UnstableFlags, Verbosity,
+};
+
+/// How often the source files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// How long the source files have to be unchanged before a rebuild, so that several files saved
+/// in quick succession trigger a single rebuild.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// The modification times of the source files of the contract.
+type Snapshot = BTreeMap<PathBuf, SystemTime>;
+
+/// Takes a snapshot of the files in the package directory, skipping the target directory.
+fn snapshot(package_dir: &Path, target_directory: &Path) -> Result<Snapshot> {
+    let mut files = Vec::new();
+    fingerprint::collect_files(package_dir, target_directory, &mut files)?;
+    let mut snapshot = Snapshot::new();
+    for file in files {
+        // a file may be removed between listing and reading it, which the next poll picks up
+        if let Ok(modified) = fs::metadata(&file).and_then(|metadata| metadata.modified()) {
+            snapshot.insert(file, modified);
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Waits until the source files differ from `last`, and then until they did not change for the
+/// debounce interval.
+///
+/// Returns the new snapshot, or `None` if interrupted while waiting.
+fn wait_for_change(
+    package_dir: &Path,
+    target_directory: &Path,
+    last: &Snapshot,
+    interrupted: &AtomicBool,
+) -> Result<Option<Snapshot>> {
+    let mut current = loop {
+        thread::sleep(POLL_INTERVAL);
+        if interrupted.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let current = snapshot(package_dir, target_directory)?;
+        if &current != last {
+            break current;
+        }
+    };
+    loop {
+        thread::sleep(DEBOUNCE);
+        if interrupted.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let next = snapshot(package_dir, target_directory)?;
+        if next == current {
+            return Ok(Some(current));
+        }
+        current = next;
+    }
+}
+
+/// Builds the contract, and rebuilds it whenever one of its source files changes until Ctrl-C is
+/// pressed.
+///
+/// The screen is cleared before each build. A failed build is reported, but does not stop
+/// watching. Changes to the target directory and to hidden files are ignored.
+pub fn execute(
+    manifest_path: &ManifestPath,
+    verbosity: Option<Verbosity>,
+    debug: bool,
+    optimization_passes: OptimizationPasses,
+    target_dir: Option<&Path>,
+    toolchain: Option<&str>,
+    lock: LockFlags,
+    unstable_options: UnstableFlags,
+    features: &Features,
+) -> Result<String> {
+    let _toolchain = toolchain.map(util::select_toolchain).transpose()?;
+    let mut crate_metadata = CrateMetadata::collect_locked(manifest_path, lock)?;
+    if let Some(target_dir) = target_dir {
+        crate_metadata = crate_metadata.with_target_directory(target_dir)?;
+    }
+    let package_dir = manifest_path
+        .directory()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    // a build in progress is completed, and watching stops afterwards
+    interrupt::install_handler()?;
+    interrupt::defer_exit();
+    let interrupted = interrupt::interrupted();
+
+    let reporter = Reporter::new(verbosity);
+    let mut last = snapshot(&package_dir, &crate_metadata.target_directory)?;
+    loop {
+        // clear the screen and move the cursor to the top left corner
+        reporter.info("\x1B[2J\x1B[1;1H");
+        match build::execute_with_metadata(
+            &crate_metadata,
+            verbosity,
+            debug,
+            optimization_passes,
+            false,
+            Default::default(),
+            lock,
+            unstable_options.clone(),
+            features,
+        ) {
+            Ok(result) => reporter.info(format_args!(
+                "\n{} {} in {:.1}s",
+                "Wasm size:".bold(),
+                util::format_size(result.optimized_size),
+                result.build_duration_ms as f64 / 1000.0
+            )),
+            Err(err) => reporter.error(format_args!("{:?}", err)),
+        }
+        if interrupted.load(Ordering::SeqCst) {
+            break;
+        }
+        reporter.info(format_args!(
+            "\nWatching {} for changes, press Ctrl-C to stop",
+            package_dir.display()
+        ));
+        match wait_for_change(
+            &package_dir,
+            &crate_metadata.target_directory,
+            &last,
+            interrupted,
+        )? {
+            Some(snapshot) => last = snapshot,
+            None => break,
+        }
+    }
+    Ok("Stopped watching".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::atomic::AtomicBool};
+
+    use super::{snapshot, wait_for_change};
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn changes_in_target_directory_are_ignored() {
+        with_tmp_dir(|path| {
+            let target = path.join("target");
+            fs::create_dir_all(&target)?;
+            fs::write(path.join("lib.rs"), "")?;
+            let before = snapshot(path, &target)?;
+
+            fs::write(target.join("contract.wasm"), [0u8])?;
+            assert_eq!(snapshot(path, &target)?, before);
+
+            fs::write(path.join("helper.rs"), "")?;
+            assert_ne!(snapshot(path, &target)?, before);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn wait_for_change_returns_when_interrupted() {
+        with_tmp_dir(|path| {
+            let target = path.join("target");
+            let last = snapshot(path, &target)?;
+            let interrupted = AtomicBool::new(true);
+            assert!(wait_for_change(path, &target, &last, &interrupted)?.is_none());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Defaults for the flags of the extrinsic commands, read from a config file in the project
+//! directory.
+//!
+//! Flags passed on the command line take precedence over the values of the config file, which
+//! take precedence over the built-in defaults of the flags. The secret key URI set with the
+//! `CARGO_CONTRACT_SURI` environment variable takes precedence over `suri` and `keyring`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use toml::value::{Table, Value};
+
+/// The names of the config file, looked up in this order.
+pub(crate) const CONFIG_FILES: [&str; 2] = ["contract.toml", ".cargo-contract.toml"];
+
+/// The global flags of `cargo contract` which are followed by a value, unless passed as
+/// `--flag=value`.
+const GLOBAL_FLAGS_WITH_VALUE: &[&str] = &["--color"];
+
+/// The subcommands submitting extrinsics.
+const EXTRINSIC_COMMANDS: &[&str] = &[
+    "deploy",
+    "upload",
+    "remove-code",
+    "instantiate",
+    "call-runtime-gateway",
+    "call-contracts-gateway",
+    "call-contract",
+];
+
+/// The subcommands calling a contract.
+const CALL_COMMANDS: &[&str] = &[
+    "instantiate",
+    "call-runtime-gateway",
+    "call-contracts-gateway",
+    "call-contract",
+];
+
+/// A supported config key and the flag it supplies a value for.
+struct ConfigKey {
+    key: &'static str,
+    flag: &'static str,
+    /// Further flags which, if passed, mean the key is not applied, e.g. `--keyring` for `suri`.
+    overridden_by: &'static [&'static str],
+    /// The environment variable which, if set, means the key is not applied.
+    overridden_by_env: Option<&'static str>,
+    commands: &'static [&'static str],
+}
+
+const KEYS: [ConfigKey; 12] = [
+    ConfigKey {
+        key: "url",
+        flag: "--url",
+        overridden_by: &["--network"],
+        overridden_by_env: None,
+        commands: &[
+            "deploy",
+            "upload",
+            "remove-code",
+            "instantiate",
+            "call-runtime-gateway",
+            "call-contracts-gateway",
+            "call-contract",
+            "submit",
+            "info",
+        ],
+    },
+    ConfigKey {
+        key: "network",
+        flag: "--network",
+        overridden_by: &["--url"],
+        overridden_by_env: None,
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "suri",
+        flag: "--suri",
+        overridden_by: &["-s", "--keyring", "--keystore-path"],
+        overridden_by_env: Some("CARGO_CONTRACT_SURI"),
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "keyring",
+        flag: "--keyring",
+        overridden_by: &["-s", "--suri", "--keystore-path"],
+        overridden_by_env: Some("CARGO_CONTRACT_SURI"),
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "scheme",
+        flag: "--scheme",
+        overridden_by: &[],
+        overridden_by_env: None,
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "retries",
+        flag: "--retries",
+        overridden_by: &[],
+        overridden_by_env: None,
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "timeout",
+        flag: "--timeout",
+        overridden_by: &[],
+        overridden_by_env: None,
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "tip",
+        flag: "--tip",
+        overridden_by: &[],
+        overridden_by_env: None,
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "decimals",
+        flag: "--decimals",
+        overridden_by: &[],
+        overridden_by_env: None,
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "ss58-prefix",
+        flag: "--ss58-prefix",
+        overridden_by: &[],
+        overridden_by_env: None,
+        commands: EXTRINSIC_COMMANDS,
+    },
+    ConfigKey {
+        key: "gas",
+        flag: "--gas",
+        overridden_by: &[],
+        overridden_by_env: None,
+        commands: CALL_COMMANDS,
+    },
+    ConfigKey {
+        key: "endowment",
+        flag: "--endowment",
+        overridden_by: &[],
+        overridden_by_env: None,
+        commands: &["instantiate"],
+    },
+];
+
+/// Returns the command line arguments with the values of the config file in the current
+/// directory added for the flags which are not passed.
+///
+/// The arguments are returned unchanged if there is no config file, or if the subcommand does
+/// not accept any of the flags of the config file.
+pub fn apply(args: Vec<String>) -> Result<Vec<String>> {
+    if subcommand_position(&args).is_none() {
+        return Ok(args);
+    }
+    match load(&env::current_dir()?)? {
+        Some((path, config)) => {
+            apply_config(args, &config).context(format!("Applying config file {}", path.display()))
+        }
+        None => Ok(args),
+    }
+}
+
+/// Reads the first of the config files which exists in the directory.
+fn load(dir: &Path) -> Result<Option<(PathBuf, Table)>> {
+    for file in CONFIG_FILES.iter() {
+        let path = dir.join(file);
+        if path.is_file() {
+            let contents =
+                fs::read_to_string(&path).context(format!("Reading {}", path.display()))?;
+            let config =
+                toml::from_str(&contents).context(format!("Parsing {}", path.display()))?;
+            return Ok(Some((path, config)));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the position of the subcommand, i.e. the first argument after `contract` which is
+/// neither a global flag nor its value, if it accepts any of the config keys.
+fn subcommand_position(args: &[String]) -> Option<usize> {
+    let contract = args.iter().position(|arg| arg == "contract")?;
+    let mut position = contract + 1;
+    while args.get(position)?.starts_with('-') {
+        if GLOBAL_FLAGS_WITH_VALUE.contains(&args[position].as_str()) {
+            position += 1;
+        }
+        position += 1;
+    }
+    let subcommand = args[position].as_str();
+    if KEYS.iter().any(|key| key.commands.contains(&subcommand)) {
+        Some(position)
+    } else {
+        None
+    }
+}
+
+/// Returns whether the flag is passed, either followed by its value or as `--flag=value`.
+fn is_passed(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| {
+        arg == flag
+            || arg.starts_with(&format!("{}=", flag))
+            || (!flag.starts_with("--") && arg.starts_with(flag))
+    })
+}
+
+fn unknown_key_error(key: &str) -> anyhow::Error {
+    let supported = KEYS
+        .iter()
+        .map(|key| format!("  {} = {}", key.key, key.flag))
+        .collect::<Vec<_>>()
+        .join("\n");
+    anyhow::anyhow!(
+        "Unknown key '{}'. Flags passed on the command line take precedence over the config \
+        file, which takes precedence over the built-in defaults. The supported keys and the flags \
+        they set are:\n{}",
+        key,
+        supported
+    )
+}
+
+fn apply_config(mut args: Vec<String>, config: &Table) -> Result<Vec<String>> {
+    let position = match subcommand_position(&args) {
+        Some(position) => position,
+        None => return Ok(args),
+    };
+    let subcommand = args[position].clone();
+
+    let mut defaults = Vec::new();
+    for (key, value) in config {
+        let config_key = KEYS
+            .iter()
+            .find(|config_key| config_key.key == key)
+            .ok_or_else(|| unknown_key_error(key))?;
+        let value = match value {
+            Value::String(value) => value.clone(),
+            Value::Integer(value) => value.to_string(),
+            _ => anyhow::bail!("The value of '{}' should be a string or an integer", key),
+        };
+        let passed = is_passed(&args, config_key.flag)
+            || config_key
+                .overridden_by
+                .iter()
+                .any(|flag| is_passed(&args, flag))
+            || config_key
+                .overridden_by_env
+                .map_or(false, |var| env::var_os(var).is_some());
+        if config_key.commands.contains(&subcommand.as_str()) && !passed {
+            defaults.push(config_key.flag.to_string());
+            defaults.push(value);
+        }
+    }
+    args.splice(position + 1..position + 1, defaults);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(ToString::to_string).collect()
+    }
+
+    fn config(toml: &str) -> Table {
+        toml::from_str(toml).expect("valid toml")
+    }
+
+    #[test]
+    fn config_values_are_added_for_missing_flags() {
+        let config = config("url = \"ws://node:9944\"\ngas = 1000\nendowment = \"5\"");
+        let result = apply_config(args("cargo contract instantiate --gas 42"), &config).unwrap();
+        assert_eq!(
+            result,
+            args("cargo contract instantiate --endowment 5 --url ws://node:9944 --gas 42")
+        );
+    }
+
+    #[test]
+    fn flags_take_precedence_over_config() {
+        let config = config("url = \"ws://node:9944\"\nsuri = \"//Alice\"");
+        let result = apply_config(
+            args("cargo contract deploy --url=ws://other:9944 --keyring bob"),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            args("cargo contract deploy --url=ws://other:9944 --keyring bob")
+        );
+    }
+
+    #[test]
+    fn keys_not_accepted_by_the_subcommand_are_skipped() {
+        let config = config("url = \"ws://node:9944\"\nendowment = 5");
+        let result = apply_config(args("cargo contract --output-json deploy"), &config).unwrap();
+        assert_eq!(
+            result,
+            args("cargo contract --output-json deploy --url ws://node:9944")
+        );
+
+        let result = apply_config(args("cargo contract build"), &config).unwrap();
+        assert_eq!(result, args("cargo contract build"));
+    }
+
+    #[test]
+    fn values_of_global_flags_are_not_taken_as_subcommand() {
+        let config = config("url = \"ws://node:9944\"");
+        let result = apply_config(args("cargo contract --color always deploy"), &config).unwrap();
+        assert_eq!(
+            result,
+            args("cargo contract --color always deploy --url ws://node:9944")
+        );
+
+        let result = apply_config(args("cargo contract --color=never deploy"), &config).unwrap();
+        assert_eq!(
+            result,
+            args("cargo contract --color=never deploy --url ws://node:9944")
+        );
+    }
+
+    #[test]
+    fn unknown_key_lists_supported_keys() {
+        let config = config("gas_limit = 1000");
+        let error = apply_config(args("cargo contract deploy"), &config).unwrap_err();
+        let message = error.to_string();
+        assert!(message.starts_with("Unknown key 'gas_limit'. Flags passed on the command line"));
+        assert!(message.contains("  gas = --gas\n"));
+    }
+
+    #[test]
+    fn load_prefers_contract_toml() {
+        with_tmp_dir(|path| {
+            assert!(load(path)?.is_none());
+
+            fs::write(path.join(".cargo-contract.toml"), "tip = 1")?;
+            let (loaded, _) = load(path)?.expect("config file exists");
+            assert_eq!(loaded, path.join(".cargo-contract.toml"));
+
+            fs::write(path.join("contract.toml"), "tip = 2")?;
+            let (loaded, config) = load(path)?.expect("config file exists");
+            assert_eq!(loaded, path.join("contract.toml"));
+            assert_eq!(config.get("tip"), Some(&Value::Integer(2)));
+            Ok(())
+        })
+    }
+}
@@ -14,14 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::workspace::ManifestPath;
+use crate::{workspace::ManifestPath, LockFlags};
 use anyhow::{Context, Result};
 use cargo_metadata::{Metadata as CargoMetadata, MetadataCommand, Package};
-use colored::Colorize;
 use semver::Version;
 use serde::Deserialize;
 use serde_json::{Map, Value};
-use std::{fs, path::PathBuf};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 use toml::value;
 use url::Url;
 
@@ -62,18 +64,25 @@ pub struct CrateMetadata {
     pub documentation: Option<Url>,
     pub homepage: Option<Url>,
     pub user: Option<Map<String, Value>>,
+    /// The command to run after a successful build, from `post-build` of the
+    /// `[package.metadata.contract]` section.
+    pub post_build: Option<String>,
 }
 
 impl CrateMetadata {
     /// Parses the contract manifest and returns relevant metadata.
     pub fn collect(manifest_path: &ManifestPath) -> Result<Self> {
-        let (metadata, root_package) = get_cargo_metadata(manifest_path)?;
+        Self::collect_locked(manifest_path, LockFlags::default())
+    }
+
+    /// Parses the contract manifest and returns relevant metadata, forwarding the `lock` flags to
+    /// `cargo metadata` so that it does not update `Cargo.lock` or access the network either.
+    pub fn collect_locked(manifest_path: &ManifestPath, lock: LockFlags) -> Result<Self> {
+        let (metadata, root_package) = get_cargo_metadata(manifest_path, lock)?;
 
         // Normalize the package name.
         let package_name = root_package.name.replace("-", "_");
 
-
-
         // {target_dir}/wasm32-unknown-unknown/release/{package_name}.wasm
         let mut original_wasm = metadata.target_directory.clone();
         original_wasm.push("wasm32-unknown-unknown");
@@ -86,28 +95,16 @@ impl CrateMetadata {
         dest_wasm.push(package_name.clone());
         dest_wasm.set_extension("wasm");
 
-        let mut composable_schedule: Option<ComposableScheduleMetadata> = None;
+        let composable_schedule =
+            parse_composable_schedule(&root_package.metadata).context(format!(
+                "Invalid composable schedule in {}",
+                manifest_path.as_ref().display()
+            ))?;
 
         let ink_version = metadata
             .packages
             .iter()
             .find_map(|package| {
-                // Extract the composable metadata which should be place in the Cargo.toml of package_name.
-                if package.name == package_name.clone() {
-                    composable_schedule = match serde_json::from_value(package.metadata.clone()) {
-                        Ok(composable_schedule) => Some(composable_schedule),
-                        Err(_) => None,
-                    };
-                    if let Some(t3rn_schedule) = composable_schedule.clone() {
-                        println!(
-                            "{} {:?}",
-                            "Detected t3rn schedule with following components:"
-                                .bright_blue()
-                                .bold(),
-                            t3rn_schedule.composables
-                        );
-                    }
-                }
                 if package.name == "ink_lang" {
                     Some(
                         Version::parse(&package.version.to_string())
@@ -120,6 +117,10 @@ impl CrateMetadata {
             .ok_or(anyhow::anyhow!("No 'ink_lang' dependency found"))?;
 
         let (documentation, homepage, user) = get_cargo_toml_metadata(manifest_path)?;
+        let post_build = parse_post_build(&root_package.metadata).context(format!(
+            "Invalid post-build hook in {}",
+            manifest_path.as_ref().display()
+        ))?;
 
         let crate_metadata = CrateMetadata {
             manifest_path: manifest_path.clone(),
@@ -132,35 +133,210 @@ impl CrateMetadata {
             documentation,
             homepage,
             user,
+            post_build,
             t3rn_composable_schedule: composable_schedule,
             target_directory: metadata.target_directory.clone(),
         };
         Ok(crate_metadata)
     }
+
+    /// Overrides the directory the wasm and metadata artifacts are written to.
+    ///
+    /// A relative path is resolved against the current working directory. The directory is
+    /// created if it does not exist yet.
+    pub fn with_target_directory(mut self, target_directory: &Path) -> Result<Self> {
+        let target_directory = env::current_dir()?.join(target_directory);
+        fs::create_dir_all(&target_directory).context(format!(
+            "Creating target directory {}",
+            target_directory.display()
+        ))?;
+        let target_directory = target_directory.canonicalize()?;
+
+        // {target_dir}/wasm32-unknown-unknown/release/{package_name}.wasm
+        self.original_wasm = target_directory
+            .join("wasm32-unknown-unknown")
+            .join("release")
+            .join(format!("{}.wasm", self.package_name));
+        // {target_dir}/{package_name}.wasm
+        self.dest_wasm = target_directory.join(format!("{}.wasm", self.package_name));
+        self.target_directory = target_directory;
+        Ok(self)
+    }
+}
+
+/// Parses the composable schedule from the `[package.metadata]` section of the contract.
+///
+/// Returns `None` if the section does not contain a schedule, i.e. has no `composables` key. Other
+/// keys such as `deploy` may be used by other tools, and are only read as part of a schedule. A
+/// malformed schedule is an error naming the offending component and field.
+fn parse_composable_schedule(metadata: &Value) -> Result<Option<ComposableScheduleMetadata>> {
+    let section = match metadata.as_object() {
+        Some(section) => section,
+        None => return Ok(None),
+    };
+
+    let composables = match section.get("composables") {
+        Some(Value::Array(composables)) => composables
+            .iter()
+            .map(|compose| {
+                compose
+                    .as_str()
+                    .map(ToString::to_string)
+                    .ok_or_else(|| anyhow::anyhow!("'composables' should only contain strings"))
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Some(_) => anyhow::bail!("'composables' should be a list of component names"),
+        None => return Ok(None),
+    };
+    let schedule = match section.get("schedule") {
+        Some(Value::String(schedule)) => Some(schedule.clone()),
+        Some(_) => anyhow::bail!("'schedule' should be a string"),
+        None => None,
+    };
+    let deploy =
+        parse_schedule_entries(section, "deploy", &["compose", "vm", "url"], &composables)?;
+    let exec = parse_schedule_entries(
+        section,
+        "exec",
+        &["compose", "gateway", "url"],
+        &composables,
+    )?;
+
+    Ok(Some(ComposableScheduleMetadata {
+        composables,
+        schedule,
+        deploy,
+        exec,
+    }))
+}
+
+/// Parses the entries of the `deploy` or `exec` schedule, checking that each has all `fields`
+/// and refers to one of the `composables`.
+fn parse_schedule_entries<T: serde::de::DeserializeOwned>(
+    section: &Map<String, Value>,
+    key: &str,
+    fields: &[&str],
+    composables: &[String],
+) -> Result<Option<Vec<T>>> {
+    let entries = match section.get(key) {
+        Some(Value::Array(entries)) => entries,
+        Some(_) => anyhow::bail!("'{}' should be a list of tables", key),
+        None => return Ok(None),
+    };
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let entry = entry
+                .as_object()
+                .ok_or_else(|| anyhow::anyhow!("entry {} of '{}' should be a table", index, key))?;
+            let component = match entry.get("compose").and_then(Value::as_str) {
+                Some(compose) => format!("component '{}'", compose),
+                None => format!("entry {}", index),
+            };
+            for field in fields {
+                match entry.get(*field) {
+                    Some(Value::String(_)) => (),
+                    Some(_) => anyhow::bail!(
+                        "{} in '{}' should have a string '{}'",
+                        component,
+                        key,
+                        field
+                    ),
+                    None => {
+                        anyhow::bail!("{} in '{}' missing required '{}'", component, key, field)
+                    }
+                }
+            }
+            let compose = entry["compose"].as_str().unwrap_or_default();
+            if !composables.iter().any(|composable| composable == compose) {
+                anyhow::bail!("{} in '{}' is not listed in 'composables'", component, key)
+            }
+            serde_json::from_value(Value::Object(entry.clone()))
+                .context(format!("{} in '{}'", component, key))
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+/// Parses the `post-build` command of the `[package.metadata.contract]` section, if any.
+fn parse_post_build(metadata: &Value) -> Result<Option<String>> {
+    match metadata
+        .get("contract")
+        .and_then(|contract| contract.get("post-build"))
+    {
+        Some(Value::String(command)) => Ok(Some(command.clone())),
+        Some(_) => anyhow::bail!("'post-build' should be a string"),
+        None => Ok(None),
+    }
 }
 
-/// Get the result of `cargo metadata`, together with the root package id.
-fn get_cargo_metadata(manifest_path: &ManifestPath) -> Result<(CargoMetadata, Package)> {
+/// Get the result of `cargo metadata`, together with the contract package.
+///
+/// If the contract is a member of a larger workspace, the package is the member whose manifest is
+/// at `manifest_path`, rather than the root of the workspace.
+fn get_cargo_metadata(
+    manifest_path: &ManifestPath,
+    lock: LockFlags,
+) -> Result<(CargoMetadata, Package)> {
     let mut cmd = MetadataCommand::new();
+    let lock_args = lock
+        .cargo_args()
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
     let metadata = cmd
         .manifest_path(manifest_path)
+        .other_options(lock_args)
         .exec()
         .context("Error invoking `cargo metadata`")?;
+    let root_package = find_contract_package(&metadata, manifest_path)?.clone();
+    Ok((metadata, root_package))
+}
+
+/// Find the package of the contract at `manifest_path` among the workspace members.
+///
+/// Falls back to the root of the resolved dependency graph, and fails for a virtual workspace
+/// manifest, which does not define a package itself.
+fn find_contract_package<'a>(
+    metadata: &'a CargoMetadata,
+    manifest_path: &ManifestPath,
+) -> Result<&'a Package> {
+    let manifest_path = manifest_path
+        .as_ref()
+        .canonicalize()
+        .unwrap_or_else(|_| manifest_path.as_ref().to_path_buf());
+    let member = metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .find(|package| {
+            package
+                .manifest_path
+                .canonicalize()
+                .map_or(false, |path| path == manifest_path)
+        });
+    if let Some(member) = member {
+        return Ok(member);
+    }
     let root_package_id = metadata
         .resolve
         .as_ref()
         .and_then(|resolve| resolve.root.as_ref())
-        .context("Cannot infer the root project id")?
-        .clone();
+        .with_context(|| {
+            format!(
+                "'{}' is a virtual workspace manifest, pass the manifest of the contract member \
+                with --manifest-path",
+                manifest_path.display()
+            )
+        })?;
     // Find the root package by id in the list of packages. It is logical error if the root
     // package is not found in the list.
-    let root_package = metadata
+    Ok(metadata
         .packages
         .iter()
-        .find(|package| package.id == root_package_id)
-        .expect("The package is not found in the `cargo metadata` output")
-        .clone();
-    Ok((metadata, root_package))
+        .find(|package| package.id == *root_package_id)
+        .expect("The package is not found in the `cargo metadata` output"))
 }
 
 /// Read extra metadata not available via `cargo metadata` directly from `Cargo.toml`
@@ -198,3 +374,80 @@ fn get_cargo_toml_metadata(
 
     Ok((documentation, homepage, user))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_composable_schedule, parse_post_build};
+    use serde_json::json;
+
+    fn error(metadata: serde_json::Value) -> String {
+        parse_composable_schedule(&metadata)
+            .err()
+            .expect("schedule should be invalid")
+            .to_string()
+    }
+
+    #[test]
+    fn metadata_without_schedule_is_none() {
+        assert!(parse_composable_schedule(&json!(null)).unwrap().is_none());
+        let metadata = json!({ "contract": { "user": {} } });
+        assert!(parse_composable_schedule(&metadata).unwrap().is_none());
+    }
+
+    #[test]
+    fn valid_schedule_is_parsed() {
+        let metadata = json!({
+            "composables": ["flipper", "incrementer"],
+            "deploy": [{ "compose": "flipper", "vm": "pallet-contracts", "url": "ws://localhost:9944" }],
+        });
+        let schedule = parse_composable_schedule(&metadata).unwrap().unwrap();
+        assert_eq!(schedule.composables, vec!["flipper", "incrementer"]);
+        assert_eq!(schedule.deploy.unwrap()[0].url, "ws://localhost:9944");
+        assert!(schedule.exec.is_none());
+    }
+
+    #[test]
+    fn missing_field_names_component() {
+        let metadata = json!({
+            "composables": ["foo"],
+            "deploy": [{ "compose": "foo", "vm": "pallet-contracts" }],
+        });
+        assert_eq!(
+            error(metadata),
+            "component 'foo' in 'deploy' missing required 'url'"
+        );
+    }
+
+    #[test]
+    fn unknown_component_is_rejected() {
+        let metadata = json!({
+            "composables": ["foo"],
+            "exec": [{ "compose": "bar", "gateway": "runtime", "url": "ws://localhost:9944" }],
+        });
+        assert_eq!(
+            error(metadata),
+            "component 'bar' in 'exec' is not listed in 'composables'"
+        );
+    }
+
+    #[test]
+    fn post_build_is_read_from_contract_section() {
+        let metadata = json!({ "contract": { "post-build": "./sign.sh" } });
+        assert_eq!(
+            parse_post_build(&metadata).unwrap().as_deref(),
+            Some("./sign.sh")
+        );
+        assert!(parse_post_build(&json!(null)).unwrap().is_none());
+        let metadata = json!({ "contract": { "post-build": ["./sign.sh"] } });
+        assert_eq!(
+            parse_post_build(&metadata).err().unwrap().to_string(),
+            "'post-build' should be a string"
+        );
+    }
+
+    #[test]
+    fn metadata_of_other_tools_is_not_a_schedule() {
+        let metadata = json!({ "deploy": [{ "script": "deploy.sh" }], "exec": "run.sh" });
+        assert!(parse_composable_schedule(&metadata).unwrap().is_none());
+    }
+}
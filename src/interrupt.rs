@@ -0,0 +1,147 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Handling of Ctrl-C during long running operations.
+//!
+//! The manifests of a contract are never amended in place, but in a copy of the workspace in a
+//! temporary directory, see [`Workspace::using_temp`](crate::workspace::Workspace::using_temp).
+//! A temporary directory is only removed when it is dropped though, which does not happen if the
+//! process is terminated by SIGINT. Paths which have to be removed in this case are therefore
+//! registered with a [`CleanupGuard`] for as long as they are in use.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, Once,
+    },
+};
+
+use anyhow::{Context, Result};
+
+/// The exit code of a command interrupted by Ctrl-C, by convention 128 + SIGINT.
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+lazy_static::lazy_static! {
+    /// The paths which are removed when the process is interrupted.
+    static ref CLEANUP_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+}
+
+static INSTALL_HANDLER: Once = Once::new();
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static DEFER_EXIT: AtomicBool = AtomicBool::new(false);
+
+/// Installs the Ctrl-C handler, which removes all registered paths and exits the process.
+///
+/// Installing the handler more than once has no effect.
+pub fn install_handler() -> Result<()> {
+    let mut result = Ok(());
+    INSTALL_HANDLER.call_once(|| {
+        result = ctrlc::set_handler(handle_interrupt).context("Setting the Ctrl-C handler");
+    });
+    result
+}
+
+/// Instead of exiting the process on Ctrl-C, only sets the flag returned by [`interrupted`], for
+/// callers which stop gracefully between operations.
+pub(crate) fn defer_exit() {
+    DEFER_EXIT.store(true, Ordering::SeqCst);
+}
+
+/// The flag which is set once Ctrl-C was pressed.
+pub(crate) fn interrupted() -> &'static AtomicBool {
+    &INTERRUPTED
+}
+
+fn handle_interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    if DEFER_EXIT.load(Ordering::SeqCst) {
+        return;
+    }
+    cleanup();
+    eprintln!("Interrupted");
+    std::process::exit(EXIT_INTERRUPTED);
+}
+
+/// Removes all registered paths.
+fn cleanup() {
+    // the lock is only poisoned if a guard panicked, in which case there is nothing to clean up
+    let paths = match CLEANUP_PATHS.lock() {
+        Ok(mut paths) => std::mem::take(&mut *paths),
+        Err(_) => return,
+    };
+    for path in paths {
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(err) = removed {
+            log::debug!("Removing {} failed: {}", path.display(), err);
+        }
+    }
+}
+
+/// Registers a path to be removed if the process is interrupted, until the guard is dropped.
+///
+/// Dropping the guard does not remove the path, this is left to its owner.
+#[must_use = "the path is unregistered once the guard is dropped"]
+pub(crate) struct CleanupGuard {
+    path: PathBuf,
+}
+
+impl CleanupGuard {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        if let Ok(mut paths) = CLEANUP_PATHS.lock() {
+            paths.push(path.clone());
+        }
+        Self { path }
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        if let Ok(mut paths) = CLEANUP_PATHS.lock() {
+            if let Some(index) = paths.iter().rposition(|path| *path == self.path) {
+                paths.remove(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CleanupGuard, CLEANUP_PATHS};
+    use std::path::PathBuf;
+
+    fn is_registered(path: &str) -> bool {
+        CLEANUP_PATHS.lock().unwrap().contains(&PathBuf::from(path))
+    }
+
+    #[test]
+    fn guard_registers_path_until_dropped() {
+        let path = "/tmp/cargo-contract-interrupt-test";
+        let guard = CleanupGuard::new(path);
+        let nested = CleanupGuard::new(path);
+        assert!(is_registered(path));
+        drop(nested);
+        assert!(is_registered(path));
+        drop(guard);
+        assert!(!is_registered(path));
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Loading keys from the keystore directory of a Substrate node.
+//!
+//! The keystore stores each key in a file named after the hex encoded key type and public key,
+//! containing the secret phrase or seed of the key as JSON string. A key added with a password
+//! can only be derived again with the same password.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+
+/// Parses a key type like `acco` or `babe`, which is always four characters long.
+pub(crate) fn parse_key_type(input: &str) -> Result<[u8; 4]> {
+    let bytes = input.as_bytes();
+    if bytes.len() != 4 {
+        anyhow::bail!(
+            "Key type '{}' should be four characters long, e.g. acco or babe",
+            input
+        )
+    }
+    let mut key_type = [0u8; 4];
+    key_type.copy_from_slice(bytes);
+    Ok(key_type)
+}
+
+/// Parses a public key given either hex encoded or as SS58 address.
+pub(crate) fn parse_public(input: &str) -> Result<Vec<u8>> {
+    if input.starts_with("0x") {
+        return hex::decode(input.trim_start_matches("0x"))
+            .context(format!("Invalid hex encoded public key '{}'", input));
+    }
+    let account = AccountId32::from_ss58check(input).map_err(|err| {
+        anyhow::anyhow!(
+            "Invalid public key '{}', expected an SS58 address or a 0x prefixed public key: {:?}",
+            input,
+            err
+        )
+    })?;
+    Ok(AsRef::<[u8]>::as_ref(&account).to_vec())
+}
+
+/// Reads the secret of a key of `key_type` from the keystore at `path`, returning it together with
+/// the public key it is stored under.
+///
+/// Without a `public` key the keystore must contain exactly one key of the type.
+pub(crate) fn load(
+    path: &Path,
+    key_type: [u8; 4],
+    public: Option<&[u8]>,
+) -> Result<(String, Vec<u8>)> {
+    let prefix = hex::encode(key_type);
+    let key_type = String::from_utf8_lossy(&key_type).into_owned();
+    let file_name = match public {
+        Some(public) => format!("{}{}", prefix, hex::encode(public)),
+        None => {
+            let mut candidates = key_files(path, &prefix)?;
+            match candidates.len() {
+                1 => candidates.remove(0),
+                0 => anyhow::bail!(
+                    "No key of type '{}' found in the keystore {}",
+                    key_type,
+                    path.display()
+                ),
+                _ => anyhow::bail!(
+                    "The keystore {} contains {} keys of type '{}', select one with --public",
+                    path.display(),
+                    candidates.len(),
+                    key_type
+                ),
+            }
+        }
+    };
+    let public = hex::decode(&file_name[prefix.len()..])
+        .context(format!("Invalid key file name {}", file_name))?;
+    let file = path.join(&file_name);
+    if !file.is_file() {
+        anyhow::bail!(
+            "No key of type '{}' with public key 0x{} found in the keystore {}",
+            key_type,
+            hex::encode(&public),
+            path.display()
+        )
+    }
+    let contents = fs::read_to_string(&file).context(format!("Reading {}", file.display()))?;
+    let secret = serde_json::from_str::<String>(&contents)
+        .context(format!("Invalid key file {}", file.display()))?;
+    Ok((secret, public))
+}
+
+/// Returns the names of the key files in the keystore at `path` whose key type is `prefix`.
+fn key_files(path: &Path, prefix: &str) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(path).context(format!("Reading keystore {}", path.display()))? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name.starts_with(prefix) && name.len() > prefix.len() {
+            files.push(name);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{load, parse_key_type, parse_public};
+    use crate::util::tests::with_tmp_dir;
+
+    const ALICE_PUBLIC_KEY: &str =
+        "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+    #[test]
+    fn key_is_loaded_by_type_and_public_key() {
+        with_tmp_dir(|path| {
+            let file_name = format!("{}{}", hex::encode(b"acco"), ALICE_PUBLIC_KEY);
+            fs::write(path.join(&file_name), r#""//Alice""#)?;
+            let public = hex::decode(ALICE_PUBLIC_KEY)?;
+
+            let (secret, loaded) = load(path, *b"acco", Some(&public))?;
+            assert_eq!(secret, "//Alice");
+            assert_eq!(loaded, public);
+
+            // the only key of the type is selected without --public
+            let (secret, _) = load(path, *b"acco", None)?;
+            assert_eq!(secret, "//Alice");
+
+            let err = load(path, *b"babe", None).unwrap_err();
+            assert!(err.to_string().starts_with("No key of type 'babe' found"));
+            let err = load(path, *b"acco", Some(&[1; 32])).unwrap_err();
+            assert!(err
+                .to_string()
+                .starts_with("No key of type 'acco' with public key 0x0101"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn key_type_and_public_key_are_parsed() {
+        assert_eq!(parse_key_type("acco").unwrap(), *b"acco");
+        assert!(parse_key_type("account").is_err());
+        let alice = parse_public("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+        assert_eq!(hex::encode(alice), ALICE_PUBLIC_KEY);
+        assert_eq!(parse_public("0x0102").unwrap(), vec![1, 2]);
+    }
+}
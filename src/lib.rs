@@ -0,0 +1,933 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Build, test and deploy ink! smart contracts.
+//!
+//! The commands of `cargo contract` are available as functions in [`cmd`], so that they can be
+//! driven from other tools without spawning the binary. All of them take the manifest of the
+//! contract and their options explicitly, e.g. to build a contract:
+//!
+//! ```no_run
+//! use cargo_t3rn_contract::{
+//!     cmd::{self, build::BuildOptions},
+//!     workspace::ManifestPath,
+//!     Verbosity,
+//! };
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let manifest_path = ManifestPath::new("flipper/Cargo.toml")?;
+//! let options = BuildOptions {
+//!     verbosity: Some(Verbosity::Quiet),
+//!     ..Default::default()
+//! };
+//! let result = cmd::build::execute(&manifest_path, options)?;
+//! println!("{} ({})", result.dest_wasm.display(), result.code_hash);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The commands submitting extrinsics to a node require the `extrinsics` feature.
+
+pub mod cmd;
+pub mod config;
+pub mod crate_metadata;
+pub mod interrupt;
+mod reporter;
+pub mod util;
+pub mod workspace;
+
+#[cfg(feature = "extrinsics")]
+pub mod balance;
+#[cfg(feature = "extrinsics")]
+mod keystore;
+
+#[cfg(feature = "extrinsics")]
+use sp_core::{
+    crypto::{AccountId32, Pair},
+    ecdsa, ed25519, sr25519, H256,
+};
+
+use std::convert::TryFrom;
+#[cfg(feature = "extrinsics")]
+use std::path::PathBuf;
+#[cfg(feature = "extrinsics")]
+use subxt::{ContractsTemplateRuntime, PairSigner, Signer};
+
+#[cfg(feature = "extrinsics")]
+use anyhow::Context;
+use anyhow::{Error, Result};
+use structopt::StructOpt;
+
+#[cfg(feature = "extrinsics")]
+use crate::balance::BalanceArg;
+pub use crate::reporter::{Reporter, Spinner};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HexData(pub Vec<u8>);
+
+/// Parses hex encoded, optionally `0x` prefixed, data.
+///
+/// Large payloads can be read from a file instead: `@<path>` reads the raw bytes of the file and
+/// `@hex:<path>` its hex encoded contents, ignoring surrounding whitespace.
+#[cfg(feature = "extrinsics")]
+impl std::str::FromStr for HexData {
+    type Err = Error;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        let decode = |hex_data: &str| {
+            hex::decode(hex_data.trim_start_matches("0x"))
+                .map(HexData)
+                .map_err(|err| anyhow::anyhow!("Invalid hex data: {}", err))
+        };
+        match input.strip_prefix('@') {
+            Some(path) => match path.strip_prefix("hex:") {
+                Some(path) => {
+                    let contents = std::fs::read_to_string(path)
+                        .context(format!("Reading data file {}", path))?;
+                    decode(contents.trim()).context(format!("Decoding data file {}", path))
+                }
+                None => std::fs::read(path)
+                    .map(HexData)
+                    .context(format!("Reading data file {}", path)),
+            },
+            None => decode(input),
+        }
+    }
+}
+
+/// Well known development accounts, derived from the substrate development seed phrase.
+#[cfg(feature = "extrinsics")]
+#[derive(Debug, Clone, Copy)]
+pub enum Keyring {
+    Alice,
+    Bob,
+    Charlie,
+    Dave,
+    Eve,
+    Ferdie,
+}
+
+#[cfg(feature = "extrinsics")]
+impl Keyring {
+    const NAMES: [&'static str; 6] = ["alice", "bob", "charlie", "dave", "eve", "ferdie"];
+
+    /// The secret key URI of the development account.
+    pub fn suri(self) -> String {
+        let name = format!("{:?}", self);
+        format!("{}//{}", sp_core::crypto::DEV_PHRASE, name)
+    }
+}
+
+#[cfg(feature = "extrinsics")]
+impl std::str::FromStr for Keyring {
+    type Err = Error;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "alice" => Ok(Keyring::Alice),
+            "bob" => Ok(Keyring::Bob),
+            "charlie" => Ok(Keyring::Charlie),
+            "dave" => Ok(Keyring::Dave),
+            "eve" => Ok(Keyring::Eve),
+            "ferdie" => Ok(Keyring::Ferdie),
+            _ => anyhow::bail!(
+                "Unknown keyring account '{}', expected one of {}",
+                input,
+                Keyring::NAMES.join(", ")
+            ),
+        }
+    }
+}
+
+/// The cryptographic schemes supported for the key pair signing extrinsics.
+#[cfg(feature = "extrinsics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    Sr25519,
+    Ed25519,
+    Ecdsa,
+}
+
+#[cfg(feature = "extrinsics")]
+impl std::str::FromStr for Scheme {
+    type Err = Error;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "sr25519" => Ok(Scheme::Sr25519),
+            "ed25519" => Ok(Scheme::Ed25519),
+            "ecdsa" => Ok(Scheme::Ecdsa),
+            _ => anyhow::bail!(
+                "Unknown key scheme '{}', expected one of sr25519, ed25519, ecdsa",
+                input
+            ),
+        }
+    }
+}
+
+/// Signs extrinsics with a key pair of any of the supported schemes.
+#[cfg(feature = "extrinsics")]
+pub type ExtrinsicSigner = Box<dyn Signer<ContractsTemplateRuntime> + Send + Sync>;
+
+/// The node url used if neither `--url` nor `--network` is passed.
+#[cfg(feature = "extrinsics")]
+const DEFAULT_URL: &str = "ws://localhost:9944";
+
+/// The networks which can be selected with `--network`, and the urls of their nodes.
+#[cfg(feature = "extrinsics")]
+const NETWORKS: &[(&str, &str)] = &[
+    ("local", DEFAULT_URL),
+    ("rococo", "wss://rococo-rpc.polkadot.io"),
+    ("westend", "wss://westend-rpc.polkadot.io"),
+    ("canvas", "wss://canvas-rpc.parity.io"),
+];
+
+/// Arguments required for creating and sending an extrinsic to a substrate node
+#[cfg(feature = "extrinsics")]
+#[derive(Clone, Debug, StructOpt)]
+pub struct ExtrinsicOpts {
+    /// Url of the RPC interface of a substrate node, defaults to ws://localhost:9944. Extrinsics
+    /// can only be watched over ws:// or wss://, http:// and https:// only support queries
+    #[structopt(name = "url", long, parse(try_from_str))]
+    pub url: Option<url::Url>,
+    /// Connect to the node of a well known network instead of passing --url: local, rococo,
+    /// westend or canvas
+    #[structopt(long, conflicts_with = "url", parse(try_from_str = parse_network))]
+    pub network: Option<url::Url>,
+    /// Secret key URI for the account deploying the contract.
+    #[structopt(
+        name = "suri",
+        long,
+        short,
+        env = "CARGO_CONTRACT_SURI",
+        hide_env_values = true
+    )]
+    pub suri: Option<String>,
+    /// Use a well known development account instead of --suri: alice, bob, charlie, dave, eve or
+    /// ferdie
+    #[structopt(long)]
+    pub keyring: Option<Keyring>,
+    #[structopt(flatten)]
+    pub keystore: KeystoreOpts,
+    /// Password for the secret key
+    #[structopt(
+        name = "password",
+        long,
+        short,
+        env = "CARGO_CONTRACT_PASSWORD",
+        hide_env_values = true
+    )]
+    pub password: Option<String>,
+    /// Prompt for the password of the secret key instead of passing it with --password
+    #[structopt(long)]
+    pub password_interactive: bool,
+    /// The cryptographic scheme of the secret key: sr25519, ed25519 or ecdsa
+    #[structopt(long, default_value = "sr25519")]
+    pub scheme: Scheme,
+    /// Number of times to retry connecting to the node after a network error, waiting twice as
+    /// long before each retry. The extrinsic itself is never submitted twice
+    #[structopt(long, default_value = "0")]
+    pub retries: u32,
+    /// Seconds to wait for the node to respond, when connecting and submitting the extrinsic, and
+    /// for the block including it to be finalized with --wait-for-finalized
+    #[structopt(long, default_value = "60")]
+    pub timeout: u64,
+    /// Do not verify the TLS certificate of a `wss://` node url, e.g. for staging nodes with a
+    /// self-signed certificate
+    #[structopt(long)]
+    pub accept_invalid_certs: bool,
+    /// Tip for the block author, to prioritize the extrinsic on a congested chain. Either an
+    /// integer, or an amount of the token like 1.5UNIT or 100mUNIT
+    #[structopt(long, default_value = "0")]
+    pub tip: BalanceArg,
+    /// Number of decimals of the token, used to convert amounts like 1.5UNIT. Queried from the
+    /// node if not passed
+    #[structopt(long)]
+    pub decimals: Option<u8>,
+    /// SS58 address prefix of the chain, used to print account addresses. Queried from the node
+    /// if not passed
+    #[structopt(long)]
+    pub ss58_prefix: Option<u8>,
+    /// Wait until the block including the extrinsic is finalized, instead of returning as soon as
+    /// it is included in a block
+    #[structopt(long)]
+    pub wait_for_finalized: bool,
+    #[structopt(flatten)]
+    pub offline: OfflineOpts,
+    #[structopt(flatten)]
+    pub verbosity: VerbosityFlags,
+}
+
+/// Arguments for loading the signing key from the keystore of a node
+#[cfg(feature = "extrinsics")]
+#[derive(Clone, Debug, Default, StructOpt)]
+pub struct KeystoreOpts {
+    /// Load the secret key from the keystore directory of a node instead of passing --suri. The
+    /// password of the key is passed with --password
+    #[structopt(long, parse(from_os_str))]
+    pub keystore_path: Option<PathBuf>,
+    /// The four character type of the key in the keystore, e.g. acco or babe
+    #[structopt(long, parse(try_from_str = keystore::parse_key_type))]
+    pub key_type: Option<[u8; 4]>,
+    /// The public key, hex encoded or as SS58 address, of the key in the keystore. Only required
+    /// if the keystore contains several keys of the type
+    #[structopt(long)]
+    pub public: Option<String>,
+}
+
+/// Arguments for signing an extrinsic without connecting to a node
+#[cfg(feature = "extrinsics")]
+#[derive(Clone, Debug, Default, StructOpt)]
+pub struct OfflineOpts {
+    /// Sign the extrinsic without connecting to a node and print it hex encoded, instead of
+    /// submitting it. Broadcast it later with the `submit` command
+    #[structopt(long)]
+    pub offline: bool,
+    /// Nonce of the signing account, overriding the one queried from the node, e.g. to submit
+    /// several extrinsics in a row. Required with --offline
+    #[structopt(long)]
+    pub nonce: Option<u32>,
+    /// Genesis hash of the chain, required with --offline
+    #[structopt(long, parse(try_from_str = parse_code_hash))]
+    pub genesis_hash: Option<H256>,
+    /// Spec version of the runtime, required with --offline
+    #[structopt(long)]
+    pub spec_version: Option<u32>,
+    /// Transaction version of the runtime, required with --offline
+    #[structopt(long)]
+    pub transaction_version: Option<u32>,
+    /// Path to the runtime metadata, as returned hex encoded by the `state_getMetadata` RPC
+    /// method, required with --offline
+    #[structopt(long, parse(from_os_str))]
+    pub runtime_metadata: Option<PathBuf>,
+}
+
+#[cfg(feature = "extrinsics")]
+impl ExtrinsicOpts {
+    /// Returns the url of the node, given either with --url or --network.
+    pub fn url(&self) -> url::Url {
+        self.url
+            .as_ref()
+            .or_else(|| self.network.as_ref())
+            .cloned()
+            .unwrap_or_else(|| url::Url::parse(DEFAULT_URL).expect("the default url is valid; qed"))
+    }
+
+    pub fn signer(&self) -> Result<ExtrinsicSigner> {
+        // the public key the secret must derive, for a key loaded from the keystore
+        let mut public = None;
+        let suri = match (&self.suri, self.keyring, &self.keystore.keystore_path) {
+            (Some(suri), None, None) => suri.clone(),
+            (None, Some(keyring), None) => keyring.suri(),
+            (None, None, Some(keystore_path)) => {
+                let (suri, key_public) = self.keystore.load(keystore_path)?;
+                public = Some(key_public);
+                suri
+            }
+            (Some(_), Some(_), _) => anyhow::bail!(
+                "Cannot pass both --keyring and --suri (or the CARGO_CONTRACT_SURI environment \
+                variable)"
+            ),
+            (_, _, Some(_)) => anyhow::bail!(
+                "Cannot pass both --keystore-path and --keyring or --suri (or the \
+                CARGO_CONTRACT_SURI environment variable)"
+            ),
+            (None, None, None) => anyhow::bail!(
+                "No secret key URI supplied. Pass it with --suri or set the CARGO_CONTRACT_SURI \
+                environment variable, --suri takes precedence over the environment variable"
+            ),
+        };
+        let password = match (&self.password, self.password_interactive) {
+            (password, false) => password.clone(),
+            (None, true) => Some(Self::prompt_password()?),
+            (Some(_), true) => anyhow::bail!(
+                "Cannot pass both --password-interactive and --password (or the \
+                CARGO_CONTRACT_PASSWORD environment variable)"
+            ),
+        };
+        let password = password.as_ref().map(String::as_ref);
+        let public = public.as_deref();
+        let signer: ExtrinsicSigner = match self.scheme {
+            Scheme::Sr25519 => Box::new(PairSigner::new(signing_pair::<sr25519::Pair>(
+                &suri, password, public,
+            )?)),
+            Scheme::Ed25519 => Box::new(PairSigner::new(signing_pair::<ed25519::Pair>(
+                &suri, password, public,
+            )?)),
+            Scheme::Ecdsa => Box::new(PairSigner::new(signing_pair::<ecdsa::Pair>(
+                &suri, password, public,
+            )?)),
+        };
+        let signer: ExtrinsicSigner = match self.offline.nonce {
+            Some(nonce) => {
+                self.reporter()?.verbose(format_args!("Nonce: {}", nonce));
+                Box::new(cmd::NonceSigner::new(signer, nonce))
+            }
+            None => signer,
+        };
+        let tip = self.balance(&self.tip)?;
+        self.reporter()?.verbose(format_args!("Tip: {}", tip));
+        if tip > 0 {
+            return Ok(Box::new(cmd::TipSigner::new(signer, tip)));
+        }
+        Ok(signer)
+    }
+
+    /// Converts a balance argument to the smallest unit of the chain.
+    ///
+    /// Amounts like `1.5UNIT` are converted with the decimals passed with --decimals, or else
+    /// with the decimals of the token queried from the node.
+    pub fn balance(&self, balance: &BalanceArg) -> Result<u128> {
+        if !balance.has_units() {
+            return balance.to_raw(0);
+        }
+        let decimals = match self.decimals {
+            Some(decimals) => decimals,
+            None if self.offline.offline => anyhow::bail!(
+                "Converting {} requires the decimals of the token, pass them with --decimals when \
+                signing offline",
+                balance
+            ),
+            None => async_std::task::block_on(cmd::token_decimals(self))
+                .context("Querying the decimals of the token")?,
+        };
+        balance.to_raw(decimals)
+    }
+
+    /// Renders the account as SS58 address of the chain.
+    ///
+    /// Uses the prefix passed with --ss58-prefix, or else the one queried from the node. The
+    /// default prefix is used when signing offline, or if the node does not report a prefix.
+    pub fn ss58_address(&self, account: &AccountId32) -> Result<String> {
+        let prefix = match self.ss58_prefix {
+            Some(prefix) => Some(prefix),
+            None if self.offline.offline => None,
+            None => async_std::task::block_on(cmd::ss58_prefix(self))
+                .context("Querying the SS58 prefix of the chain")?,
+        };
+        match prefix {
+            Some(prefix) => cmd::account::ss58_address(account, prefix),
+            None => Ok(account.to_string()),
+        }
+    }
+
+    /// Renders the account as SS58 address like [`Self::ss58_address`], but falls back to the
+    /// default prefix with a warning if the prefix of the chain cannot be queried.
+    ///
+    /// Used for the output of extrinsics which were already included, which must not fail then.
+    pub fn ss58_address_or_default(&self, account: &AccountId32) -> String {
+        self.ss58_address(account).unwrap_or_else(|err| {
+            if let Ok(reporter) = self.reporter() {
+                reporter.warning(format_args!("{:#}, using the default SS58 format", err));
+            }
+            account.to_string()
+        })
+    }
+
+    /// Returns the verbosity of the output, e.g. whether retries are logged.
+    pub fn verbosity(&self) -> Result<Option<Verbosity>> {
+        TryFrom::try_from(&self.verbosity)
+    }
+
+    /// Returns the reporter for the status messages of the extrinsic commands.
+    pub fn reporter(&self) -> Result<Reporter> {
+        Ok(Reporter::new(self.verbosity()?))
+    }
+
+    /// Derives the account id of the supplied secret key URI, using the configured scheme.
+    pub fn account_id(&self, suri: &str) -> Result<AccountId32> {
+        let account_id = match self.scheme {
+            Scheme::Sr25519 => {
+                AccountId32::from(pair_from_suri::<sr25519::Pair>(suri, None)?.public())
+            }
+            Scheme::Ed25519 => {
+                AccountId32::from(pair_from_suri::<ed25519::Pair>(suri, None)?.public())
+            }
+            // ecdsa public keys are 33 bytes, the account id is their hash
+            Scheme::Ecdsa => AccountId32::from(sp_core::blake2_256(
+                pair_from_suri::<ecdsa::Pair>(suri, None)?.public().as_ref(),
+            )),
+        };
+        Ok(account_id)
+    }
+
+    /// Reads the password of the secret key from the terminal, without echoing it.
+    fn prompt_password() -> Result<String> {
+        if !atty::is(atty::Stream::Stdin) {
+            anyhow::bail!("--password-interactive requires stdin to be a terminal")
+        }
+        rpassword::prompt_password_stderr("Password for the secret key: ")
+            .context("Reading the password from the terminal")
+    }
+}
+
+#[cfg(feature = "extrinsics")]
+impl KeystoreOpts {
+    /// Reads the secret of the selected key from the keystore at `keystore_path`, returning it
+    /// together with the public key of the key.
+    fn load(&self, keystore_path: &std::path::Path) -> Result<(String, Vec<u8>)> {
+        let key_type = self
+            .key_type
+            .ok_or_else(|| anyhow::anyhow!("--keystore-path requires --key-type, e.g. acco"))?;
+        let public = self
+            .public
+            .as_deref()
+            .map(keystore::parse_public)
+            .transpose()?;
+        keystore::load(keystore_path, key_type, public.as_deref())
+    }
+}
+
+#[cfg(feature = "extrinsics")]
+fn pair_from_suri<P: Pair>(suri: &str, password: Option<&str>) -> Result<P> {
+    P::from_string(suri, password).map_err(|_| anyhow::anyhow!("Secret string error"))
+}
+
+/// Derives the key pair signing the extrinsics from the secret.
+///
+/// The pair of a key loaded from the keystore must match its `public` key, otherwise the password
+/// does not unlock the key.
+#[cfg(feature = "extrinsics")]
+fn signing_pair<P: Pair>(suri: &str, password: Option<&str>, public: Option<&[u8]>) -> Result<P> {
+    let pair = pair_from_suri::<P>(suri, password)?;
+    if let Some(public) = public {
+        if AsRef::<[u8]>::as_ref(&pair.public()) != public {
+            anyhow::bail!(
+                "The key 0x{} in the keystore is locked, pass the password it was added with \
+                with --password",
+                hex::encode(public)
+            )
+        }
+    }
+    Ok(pair)
+}
+
+#[derive(Clone, Debug, Default, StructOpt)]
+pub struct VerbosityFlags {
+    #[structopt(long)]
+    pub quiet: bool,
+    #[structopt(long)]
+    pub verbose: bool,
+}
+
+/// The verbosity of the status messages printed by the commands.
+#[derive(Clone, Copy)]
+pub enum Verbosity {
+    Quiet,
+    Verbose,
+}
+
+impl TryFrom<&VerbosityFlags> for Option<Verbosity> {
+    type Error = Error;
+
+    fn try_from(value: &VerbosityFlags) -> Result<Self, Self::Error> {
+        match (value.quiet, value.verbose) {
+            (false, false) => Ok(None),
+            (true, false) => Ok(Some(Verbosity::Quiet)),
+            (false, true) => Ok(Some(Verbosity::Verbose)),
+            (true, true) => anyhow::bail!("Cannot pass both --quiet and --verbose flags"),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct UnstableOptions {
+    /// Use the original manifest (Cargo.toml), do not modify for build optimizations
+    #[structopt(long = "unstable-options", short = "Z", number_of_values = 1)]
+    pub options: Vec<String>,
+}
+
+/// Unstable options of the build, passed with `-Z`.
+#[derive(Clone, Default)]
+pub struct UnstableFlags {
+    /// Use the original manifest (Cargo.toml), do not modify for build optimizations
+    pub original_manifest: bool,
+}
+
+impl TryFrom<&UnstableOptions> for UnstableFlags {
+    type Error = Error;
+
+    fn try_from(value: &UnstableOptions) -> Result<Self, Self::Error> {
+        let valid_flags = ["original-manifest"];
+        let invalid_flags = value
+            .options
+            .iter()
+            .filter(|o| !valid_flags.contains(&o.as_str()))
+            .map(|o| match util::closest_match(o, &valid_flags) {
+                Some(suggestion) => format!(
+                    "Unknown unstable-option '{}', did you mean '{}'?",
+                    o, suggestion
+                ),
+                None => format!(
+                    "Unknown unstable-option '{}', expected one of {}",
+                    o,
+                    valid_flags.join(", ")
+                ),
+            })
+            .collect::<Vec<_>>();
+        if !invalid_flags.is_empty() {
+            anyhow::bail!("{}", invalid_flags.join("\n"))
+        }
+        Ok(UnstableFlags {
+            original_manifest: value.options.contains(&"original-manifest".to_owned()),
+        })
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct FeatureOptions {
+    /// Comma separated list of features of the contract to activate
+    #[structopt(long, use_delimiter = true)]
+    pub features: Vec<String>,
+    /// Do not activate the default features of the contract. The wasm of `build` and `check` is
+    /// always compiled without them, since they enable `std`
+    #[structopt(long)]
+    pub no_default_features: bool,
+}
+
+/// The cargo features to compile the contract with.
+#[derive(Clone, Debug, Default)]
+pub struct Features {
+    pub features: Vec<String>,
+    pub no_default_features: bool,
+}
+
+impl From<&FeatureOptions> for Features {
+    fn from(value: &FeatureOptions) -> Self {
+        Features {
+            features: value.features.clone(),
+            no_default_features: value.no_default_features,
+        }
+    }
+}
+
+impl Features {
+    /// Returns the `--features` argument for cargo, if any features are activated.
+    fn features_arg(&self) -> Option<String> {
+        if self.features.is_empty() {
+            None
+        } else {
+            Some(format!("--features={}", self.features.join(",")))
+        }
+    }
+
+    /// Returns the cargo arguments selecting the features.
+    fn cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        args.extend(self.features_arg());
+        args
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct LockOptions {
+    /// Require Cargo.lock to be up to date, fail instead of updating it
+    #[structopt(long)]
+    pub locked: bool,
+    /// Run without accessing the network, dependencies must be available locally
+    #[structopt(long)]
+    pub offline: bool,
+    /// Require Cargo.lock to be up to date and run without accessing the network, same as
+    /// --locked --offline
+    #[structopt(long)]
+    pub frozen: bool,
+}
+
+/// Whether cargo may update the lock file of the contract and access the network.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LockFlags {
+    /// Fail if `Cargo.lock` is missing or would be updated.
+    pub locked: bool,
+    /// Do not access the network.
+    pub offline: bool,
+}
+
+impl From<&LockOptions> for LockFlags {
+    fn from(value: &LockOptions) -> Self {
+        // `--frozen` is both `--locked` and `--offline`, combining them is redundant but valid
+        LockFlags {
+            locked: value.locked || value.frozen,
+            offline: value.offline || value.frozen,
+        }
+    }
+}
+
+impl LockFlags {
+    /// Returns the cargo arguments restricting the lock file and network access.
+    fn cargo_args(&self) -> Vec<&'static str> {
+        match (self.locked, self.offline) {
+            (true, true) => vec!["--frozen"],
+            (true, false) => vec!["--locked"],
+            (false, true) => vec!["--offline"],
+            (false, false) => vec![],
+        }
+    }
+}
+
+#[cfg(feature = "extrinsics")]
+fn parse_network(input: &str) -> Result<url::Url> {
+    match NETWORKS.iter().find(|(name, _)| *name == input) {
+        Some((_, url)) => Ok(url::Url::parse(url)?),
+        None => {
+            let names = NETWORKS.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+            match util::closest_match(input, &names) {
+                Some(suggestion) => anyhow::bail!(
+                    "Unknown network '{}', did you mean '{}'?",
+                    input,
+                    suggestion
+                ),
+                None => anyhow::bail!(
+                    "Unknown network '{}', expected one of {}",
+                    input,
+                    names.join(", ")
+                ),
+            }
+        }
+    }
+}
+
+/// Parses a hex encoded, optionally `0x` prefixed, 32 byte code hash.
+#[cfg(feature = "extrinsics")]
+pub fn parse_code_hash(input: &str) -> Result<H256> {
+    let bytes = hex::decode(input.trim_start_matches("0x"))?;
+    if bytes.len() != 32 {
+        anyhow::bail!("Code hash should be 32 bytes in length")
+    }
+    let mut arr = [0u8; 32];
+    arr.copy_from_slice(&bytes);
+    Ok(H256(arr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FeatureOptions, Features, LockFlags, LockOptions, UnstableFlags, UnstableOptions};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn frozen_implies_locked_and_offline() {
+        let lock = |locked, offline, frozen| {
+            LockFlags::from(&LockOptions {
+                locked,
+                offline,
+                frozen,
+            })
+        };
+        assert!(lock(false, false, false).cargo_args().is_empty());
+        assert_eq!(lock(true, false, false).cargo_args(), vec!["--locked"]);
+        assert_eq!(lock(false, true, false).cargo_args(), vec!["--offline"]);
+        assert_eq!(lock(false, false, true), lock(true, true, false));
+        assert_eq!(lock(true, true, true).cargo_args(), vec!["--frozen"]);
+    }
+
+    #[test]
+    fn features_are_forwarded_to_cargo() {
+        assert!(Features::default().cargo_args().is_empty());
+
+        let options = FeatureOptions {
+            features: vec!["ink-debug".to_string(), "dummy".to_string()],
+            no_default_features: true,
+        };
+        assert_eq!(
+            Features::from(&options).cargo_args(),
+            vec!["--no-default-features", "--features=ink-debug,dummy"]
+        );
+    }
+
+    fn unstable_flags(options: &[&str]) -> anyhow::Result<UnstableFlags> {
+        let options = UnstableOptions {
+            options: options.iter().map(ToString::to_string).collect(),
+        };
+        UnstableFlags::try_from(&options)
+    }
+
+    #[test]
+    fn unstable_options_accepts_exact_match() {
+        let flags = unstable_flags(&["original-manifest"]).expect("flag is valid");
+        assert!(flags.original_manifest);
+    }
+
+    #[test]
+    fn unstable_options_suggests_near_match() {
+        let err = unstable_flags(&["orignal-manifest"]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Unknown unstable-option 'orignal-manifest', did you mean 'original-manifest'?"
+        );
+    }
+
+    #[test]
+    fn unstable_options_lists_valid_options_for_far_match() {
+        let err = unstable_flags(&["optimize"]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Unknown unstable-option 'optimize', expected one of original-manifest"
+        );
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn data_is_read_from_file() {
+        use super::HexData;
+        use crate::util::tests::with_tmp_dir;
+
+        assert_eq!("0x0102".parse::<HexData>().unwrap(), HexData(vec![1, 2]));
+        with_tmp_dir(|path| {
+            let raw = path.join("data.bin");
+            std::fs::write(&raw, [0xde, 0xad])?;
+            let hex = path.join("data.hex");
+            std::fs::write(&hex, "0xbeef\n")?;
+
+            let parse = |input: String| input.parse::<HexData>();
+            assert_eq!(parse(format!("@{}", raw.display()))?.0, vec![0xde, 0xad]);
+            assert_eq!(
+                parse(format!("@hex:{}", hex.display()))?.0,
+                vec![0xbe, 0xef]
+            );
+            assert!(parse(format!("@hex:{}", raw.display())).is_err());
+            assert!(parse(format!("@{}", path.join("missing").display())).is_err());
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn account_id_depends_on_scheme() {
+        use super::ExtrinsicOpts;
+        use structopt::StructOpt;
+
+        let account_id = |scheme: &str| {
+            ExtrinsicOpts::from_iter(&["extrinsic-opts", "--scheme", scheme])
+                .account_id("//Alice")
+                .expect("valid suri")
+        };
+        assert_eq!(
+            account_id("sr25519").to_string(),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+        assert_ne!(account_id("sr25519"), account_id("ed25519"));
+        assert_ne!(account_id("sr25519"), account_id("ecdsa"));
+        assert!("secp256k1".parse::<super::Scheme>().is_err());
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn nonce_overrides_the_queried_one() {
+        use super::ExtrinsicOpts;
+        use structopt::StructOpt;
+        use subxt::Signer;
+
+        let signer = |args: &[&str]| {
+            let base = ["extrinsic-opts", "--suri", "//Alice"];
+            ExtrinsicOpts::from_iter(base.iter().chain(args))
+                .signer()
+                .expect("valid suri")
+        };
+        assert_eq!(signer(&[]).nonce(), None);
+        assert_eq!(signer(&["--nonce", "7"]).nonce(), Some(7));
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn ss58_address_falls_back_to_the_default_format() {
+        use super::ExtrinsicOpts;
+        use sp_core::crypto::AccountId32;
+        use structopt::StructOpt;
+
+        let opts =
+            ExtrinsicOpts::from_iter(&["extrinsic-opts", "--url", "ws://127.0.0.1:1", "--quiet"]);
+        let account = AccountId32::from([1; 32]);
+        assert!(opts.ss58_address(&account).is_err());
+        assert_eq!(opts.ss58_address_or_default(&account), account.to_string());
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn keystore_key_requires_its_password() {
+        use super::{signing_pair, ExtrinsicOpts};
+        use crate::util::tests::with_tmp_dir;
+        use sp_core::{sr25519, Pair};
+        use structopt::StructOpt;
+
+        let alice = sr25519::Pair::from_string("//Alice", None)
+            .unwrap()
+            .public();
+        let alice: &[u8] = alice.as_ref();
+        assert!(signing_pair::<sr25519::Pair>("//Alice", None, Some(alice)).is_ok());
+        let err = signing_pair::<sr25519::Pair>("//Alice", Some("wrong"), Some(alice))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("is locked"));
+
+        with_tmp_dir(|path| {
+            let file_name = format!("{}{}", hex::encode(b"acco"), hex::encode(alice));
+            std::fs::write(path.join(file_name), r#""//Alice""#)?;
+            let keystore_path = path.to_string_lossy().into_owned();
+            let opts = |args: &[&str]| {
+                let base = ["extrinsic-opts", "--keystore-path", keystore_path.as_str()];
+                ExtrinsicOpts::from_iter(base.iter().chain(args))
+            };
+            assert!(opts(&["--key-type", "acco"]).signer().is_ok());
+            let err = opts(&["--suri", "//Bob"]).signer().err().unwrap();
+            assert!(err
+                .to_string()
+                .starts_with("Cannot pass both --keystore-path"));
+            let err = opts(&[]).signer().err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                "--keystore-path requires --key-type, e.g. acco"
+            );
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn network_presets_map_to_urls() {
+        use super::{parse_network, ExtrinsicOpts};
+        use structopt::StructOpt;
+
+        assert_eq!(
+            parse_network("rococo").unwrap().as_str(),
+            "wss://rococo-rpc.polkadot.io/"
+        );
+        let err = parse_network("canvsa").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown network 'canvsa', did you mean 'canvas'?"
+        );
+        let err = parse_network("kusama").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown network 'kusama', expected one of local, rococo, westend, canvas"
+        );
+
+        let opts = ExtrinsicOpts::from_iter(&["extrinsic-opts"]);
+        assert_eq!(opts.url().as_str(), "ws://localhost:9944/");
+        let opts = ExtrinsicOpts::from_iter(&["extrinsic-opts", "--network", "westend"]);
+        assert_eq!(opts.url().as_str(), "wss://westend-rpc.polkadot.io/");
+        assert!(ExtrinsicOpts::from_iter_safe(&[
+            "extrinsic-opts",
+            "--network",
+            "local",
+            "--url",
+            "ws://node:9944"
+        ])
+        .is_err());
+    }
+}
@@ -14,29 +14,36 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-mod cmd;
-mod crate_metadata;
-mod util;
-mod workspace;
-
 #[cfg(feature = "extrinsics")]
-use sp_core::{
-    crypto::{AccountId32, Pair},
-    sr25519, Public, H256,
-};
+use sp_core::{crypto::AccountId32, H256};
 
 use std::{
     convert::{TryFrom, TryInto},
     path::PathBuf,
 };
-#[cfg(feature = "extrinsics")]
-use subxt::PairSigner;
 
+#[cfg(feature = "extrinsics")]
+use anyhow::Context;
 use anyhow::{Error, Result};
 use colored::Colorize;
 use structopt::{clap, StructOpt};
 
-use crate::crate_metadata::CrateMetadata;
+#[cfg(feature = "extrinsics")]
+use cargo_t3rn_contract::{
+    balance::BalanceArg,
+    cmd::{receipt::Receipt, registry},
+    parse_code_hash, ExtrinsicOpts, HexData,
+};
+use cargo_t3rn_contract::{
+    cmd::{
+        self,
+        build::{MessageFormat, OptimizationPasses},
+        metadata::MetadataVersion,
+    },
+    config, interrupt, util,
+    workspace::ManifestPath,
+    FeatureOptions, LockOptions, UnstableOptions, Verbosity, VerbosityFlags,
+};
 
 #[derive(Debug, StructOpt)]
 #[structopt(bin_name = "cargo")]
@@ -51,107 +58,59 @@ pub(crate) enum Opts {
 
 #[derive(Debug, StructOpt)]
 pub(crate) struct ContractArgs {
+    /// Print the result or error of the command as a JSON object to stdout; implies --quiet
+    #[structopt(long, global = true)]
+    output_json: bool,
+    /// When to color the output: auto, always or never. With auto, the output is colored if
+    /// stdout is a terminal, unless the NO_COLOR or CLICOLOR=0 environment variables are set
+    #[structopt(long, global = true, default_value = "auto")]
+    color: ColorChoice,
     #[structopt(subcommand)]
     cmd: Command,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub(crate) struct HexData(pub Vec<u8>);
-
-#[cfg(feature = "extrinsics")]
-impl std::str::FromStr for HexData {
-    type Err = hex::FromHexError;
-
-    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
-        hex::decode(input).map(HexData)
-    }
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
 }
 
-/// Arguments required for creating and sending an extrinsic to a substrate node
-#[cfg(feature = "extrinsics")]
-#[derive(Debug, StructOpt)]
-pub(crate) struct ExtrinsicOpts {
-    /// Websockets url of a substrate node
-    #[structopt(
-        name = "url",
-        long,
-        parse(try_from_str),
-        default_value = "ws://localhost:9944"
-    )]
-    url: url::Url,
-    /// Secret key URI for the account deploying the contract.
-    #[structopt(name = "suri", long, short)]
-    suri: String,
-    /// Password for the secret key
-    #[structopt(name = "password", long, short)]
-    password: Option<String>,
-}
-
-#[cfg(feature = "extrinsics")]
-impl ExtrinsicOpts {
-    pub fn signer(&self) -> Result<PairSigner<subxt::ContractsTemplateRuntime, sr25519::Pair>> {
-        let pair =
-            sr25519::Pair::from_string(&self.suri, self.password.as_ref().map(String::as_ref))
-                .map_err(|_| anyhow::anyhow!("Secret string error"))?;
-        Ok(PairSigner::new(pair))
-    }
-}
+impl std::str::FromStr for ColorChoice {
+    type Err = Error;
 
-#[derive(Debug, StructOpt)]
-struct VerbosityFlags {
-    #[structopt(long)]
-    quiet: bool,
-    #[structopt(long)]
-    verbose: bool,
-}
-
-#[derive(Clone, Copy)]
-enum Verbosity {
-    Quiet,
-    Verbose,
-}
-
-impl TryFrom<&VerbosityFlags> for Option<Verbosity> {
-    type Error = Error;
-
-    fn try_from(value: &VerbosityFlags) -> Result<Self, Self::Error> {
-        match (value.quiet, value.verbose) {
-            (false, false) => Ok(None),
-            (true, false) => Ok(Some(Verbosity::Quiet)),
-            (false, true) => Ok(Some(Verbosity::Verbose)),
-            (true, true) => anyhow::bail!("Cannot pass both --quiet and --verbose flags"),
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => anyhow::bail!(
+                "Unknown color choice '{}', expected one of auto, always, never",
+                input
+            ),
         }
     }
 }
 
-#[derive(Debug, StructOpt)]
-struct UnstableOptions {
-    /// Use the original manifest (Cargo.toml), do not modify for build optimizations
-    #[structopt(long = "unstable-options", short = "Z", number_of_values = 1)]
-    options: Vec<String>,
-}
-
-#[derive(Clone, Default)]
-struct UnstableFlags {
-    original_manifest: bool,
-}
-
-impl TryFrom<&UnstableOptions> for UnstableFlags {
-    type Error = Error;
-
-    fn try_from(value: &UnstableOptions) -> Result<Self, Self::Error> {
-        let valid_flags = ["original-manifest"];
-        let invalid_flags = value
-            .options
-            .iter()
-            .filter(|o| !valid_flags.contains(&o.as_str()))
-            .collect::<Vec<_>>();
-        if !invalid_flags.is_empty() {
-            anyhow::bail!("Unknown unstable-options {:?}", invalid_flags)
+impl ColorChoice {
+    /// Returns whether to color the output.
+    ///
+    /// With `Auto`, `NO_COLOR` disables and `CLICOLOR_FORCE` enables coloring, otherwise the
+    /// output is colored if stdout is a terminal and `CLICOLOR` is not `0`.
+    fn should_colorize(self, env: impl Fn(&str) -> Option<String>, is_tty: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                if env("NO_COLOR").is_some() {
+                    false
+                } else if env("CLICOLOR_FORCE").map_or(false, |value| value != "0") {
+                    true
+                } else {
+                    is_tty && env("CLICOLOR").map_or(true, |value| value != "0")
+                }
+            }
         }
-        Ok(UnstableFlags {
-            original_manifest: value.options.contains(&"original-manifest".to_owned()),
-        })
     }
 }
 
@@ -165,51 +124,255 @@ enum Command {
         /// The optional target directory for the contract project
         #[structopt(short, long, parse(from_os_str))]
         target_dir: Option<PathBuf>,
+        /// The starter contract to create the project from: flipper, erc20 or empty
+        #[structopt(long, default_value = "flipper")]
+        template: cmd::new::Template,
+        /// Initialize a git repository in the project directory and stage the generated files
+        #[structopt(long)]
+        git: bool,
+        /// Create the project even if the directory already exists and is not empty, overwriting
+        /// files generated from the template
+        #[structopt(long)]
+        force: bool,
     },
     /// Compiles the smart contract
     #[structopt(name = "build")]
     Build {
+        /// Path to the Cargo.toml of the contract, defaults to the one in the current directory
+        #[structopt(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+        #[structopt(flatten)]
+        verbosity: VerbosityFlags,
+        #[structopt(flatten)]
+        unstable_options: UnstableOptions,
+        #[structopt(flatten)]
+        features: FeatureOptions,
+        /// Compile in the dev profile and skip the wasm optimization, the resulting wasm file is
+        /// named `<name>-debug.wasm`
+        #[structopt(long)]
+        debug: bool,
+        /// Stop once cargo produced the raw wasm file, skipping the post processing and the
+        /// optimization. Useful to inspect what the compiler emitted. Same as
+        /// --optimization-passes 0
+        #[structopt(long, conflicts_with = "optimization-passes")]
+        skip_optimization: bool,
+        /// The optimization level of wasm-opt, one of 0, 1, 2, 3, 4, s or z. Higher levels take
+        /// longer to shrink the wasm further, s and z optimize for size
+        #[structopt(long, short = "O", default_value = "3")]
+        optimization_passes: OptimizationPasses,
+        /// Post process and optimize the wasm even if it did not change since the last build
+        #[structopt(long)]
+        force: bool,
+        /// Directory to write the artifacts to, defaults to the cargo target directory
+        #[structopt(long, parse(from_os_str))]
+        target_dir: Option<PathBuf>,
+        /// Fail if the resulting wasm file is larger than the given number of bytes, e.g. the
+        /// maximum code size of the chain
+        #[structopt(long, value_name = "BYTES")]
+        max_code_size: Option<u64>,
+        /// Shell command to run after a successful build, e.g. to sign or upload the wasm.
+        /// Overrides the `post-build` of `[package.metadata.contract]` in the manifest
+        #[structopt(long, value_name = "CMD")]
+        post_build: Option<String>,
+        /// Rebuild the contract whenever one of its source files changes, until Ctrl-C is pressed
+        #[structopt(long)]
+        watch: bool,
+        /// Format of the compiler diagnostics, one of human or json. With json the messages of
+        /// cargo are forwarded to stdout, followed by a `contract-build-finished` record
+        /// describing the resulting wasm
+        #[structopt(long, default_value = "human", conflicts_with = "watch")]
+        message_format: MessageFormat,
+        /// The rustup toolchain to build with, e.g. `nightly-2020-10-06`, as with
+        /// `cargo +<toolchain>`. Defaults to the toolchain selected for the project
+        #[structopt(long, value_name = "NAME")]
+        toolchain: Option<String>,
+        #[structopt(flatten)]
+        lock: LockOptions,
+    },
+    /// Checks the smart contract for errors without producing a wasm binary
+    #[structopt(name = "check")]
+    Check {
+        /// Path to the Cargo.toml of the contract, defaults to the one in the current directory
+        #[structopt(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
         #[structopt(flatten)]
         verbosity: VerbosityFlags,
         #[structopt(flatten)]
         unstable_options: UnstableOptions,
+        #[structopt(flatten)]
+        features: FeatureOptions,
     },
     /// Compiles all of the composable smart contracts described in the schedule
     #[structopt(name = "composable-build")]
     ComposableBuild {
+        /// Path to the Cargo.toml of the contract, defaults to the one in the current directory
+        #[structopt(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
         #[structopt(flatten)]
         verbosity: VerbosityFlags,
         #[structopt(flatten)]
         unstable_options: UnstableOptions,
+        /// Only build the given components of the schedule, comma separated
+        #[structopt(long, use_delimiter = true)]
+        components: Vec<String>,
+        /// Number of components to build in parallel, defaults to the number of cores
+        #[structopt(long, short)]
+        jobs: Option<usize>,
     },
     /// Generate contract metadata artifacts
     #[structopt(name = "generate-metadata")]
     GenerateMetadata {
+        /// Path to the Cargo.toml of the contract, defaults to the one in the current directory
+        #[structopt(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
         #[structopt(flatten)]
         verbosity: VerbosityFlags,
         #[structopt(flatten)]
         unstable_options: UnstableOptions,
+        /// Directory to write the artifacts to, defaults to the cargo target directory
+        #[structopt(long, parse(from_os_str))]
+        target_dir: Option<PathBuf>,
+        /// Regenerate the metadata even if the contract did not change since it was generated
+        #[structopt(long)]
+        force: bool,
+        /// Bundle the raw wasm file produced by cargo, skipping the post processing and the
+        /// optimization
+        #[structopt(long)]
+        skip_optimization: bool,
+        /// Also write a flat, Solidity-like ABI of the constructors, messages and events as
+        /// `<bundle name>.abi.json`, for tools which do not understand the ink! metadata
+        #[structopt(long)]
+        abi: bool,
+        /// Directory to write the metadata and the bundle to, defaults to the target directory.
+        /// A relative path is resolved against the directory of the manifest
+        #[structopt(long, parse(from_os_str))]
+        output_dir: Option<PathBuf>,
+        /// File name of the metadata, defaults to `metadata.json`. The bundle is named after it
+        #[structopt(long)]
+        name: Option<String>,
+        /// Version of the metadata format to generate, for nodes expecting an older one. Defaults
+        /// to the latest supported version
+        #[structopt(long)]
+        metadata_version: Option<MetadataVersion>,
+    },
+    /// Remove the wasm and metadata artifacts of the smart contract
+    #[structopt(name = "clean")]
+    Clean {
+        /// Path to the Cargo.toml of the contract, defaults to the one in the current directory
+        #[structopt(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+        /// Also run `cargo clean`, removing the whole target directory
+        #[structopt(long)]
+        deep: bool,
+        #[structopt(flatten)]
+        verbosity: VerbosityFlags,
     },
     /// Test the smart contract off-chain
     #[structopt(name = "test")]
-    Test {},
-    /// Upload the smart contract code to the chain
+    Test {
+        /// Path to the Cargo.toml of the contract, defaults to the one in the current directory
+        #[structopt(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+        #[structopt(flatten)]
+        verbosity: VerbosityFlags,
+        #[structopt(flatten)]
+        features: FeatureOptions,
+    },
+    /// Rebuild the smart contract from source and check that it matches the given wasm file
+    #[structopt(name = "verify")]
+    Verify {
+        /// Path to the Cargo.toml of the contract, defaults to the one in the current directory
+        #[structopt(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+        /// Path to the wasm file to verify, e.g. the one which was deployed
+        #[structopt(long, parse(from_os_str))]
+        wasm: PathBuf,
+        #[structopt(flatten)]
+        verbosity: VerbosityFlags,
+        #[structopt(flatten)]
+        unstable_options: UnstableOptions,
+        #[structopt(flatten)]
+        features: FeatureOptions,
+    },
+    /// Break the size of the built wasm file down into its sections and largest functions
+    #[structopt(name = "size-analysis")]
+    SizeAnalysis {
+        /// Path to the Cargo.toml of the contract, defaults to the one in the current directory
+        #[structopt(long, parse(from_os_str))]
+        manifest_path: Option<PathBuf>,
+        /// The number of largest functions to list
+        #[structopt(long, default_value = "20", value_name = "N")]
+        top: usize,
+        /// Analyze the raw wasm file produced by cargo, which contains the names of the functions
+        /// but is not yet tree shaken and optimized
+        #[structopt(long)]
+        raw: bool,
+    },
+    /// Check that the toolchain prerequisites of building a contract are installed
+    #[structopt(name = "check-env")]
+    CheckEnv,
+    /// Print a completion script for the shell, to be redirected into its completions directory
+    #[structopt(name = "completions")]
+    Completions {
+        /// The shell to complete in: bash, zsh, fish or powershell
+        #[structopt(possible_values = &["bash", "zsh", "fish", "powershell"])]
+        shell: clap::Shell,
+    },
+    /// Upload the smart contract code to the chain, returning its code hash. Also available as
+    /// `upload`, the name of the extrinsic in newer versions of the contracts pallet
     #[cfg(feature = "extrinsics")]
-    #[structopt(name = "deploy")]
+    #[structopt(name = "deploy", visible_alias = "upload")]
     Deploy {
         #[structopt(flatten)]
         extrinsic_opts: ExtrinsicOpts,
-        /// Path to wasm contract code, defaults to ./target/<name>-pruned.wasm
+        /// Path to wasm contract code, or `-` to read it from stdin, defaults to the wasm built for
+        /// the current project
         #[structopt(parse(from_os_str))]
         wasm_path: Option<PathBuf>,
+        /// Path to a `.contract` bundle, as written by `generate-metadata`, to upload the code
+        /// contained in it instead of a wasm file
+        #[structopt(long, parse(from_os_str), conflicts_with = "wasm-path")]
+        bundle: Option<PathBuf>,
+        /// Perform all local checks and print the resulting code hash, without submitting
+        #[structopt(long)]
+        dry_run: bool,
+        /// After uploading, check that the code stored on chain matches the uploaded code
+        #[structopt(long, conflicts_with = "dry-run")]
+        verify: bool,
+        /// Abort unless the hash of the contract code matches, e.g. to guard against deploying
+        /// a stale build
+        #[structopt(long, parse(try_from_str = parse_code_hash))]
+        expect_hash: Option<H256>,
+        /// Append a json receipt of the deployment to the file, recording the time, node, signer,
+        /// code hash and block
+        #[structopt(long, parse(from_os_str))]
+        receipt: Option<PathBuf>,
+    },
+    /// Remove uploaded code which is not used by any contract, reclaiming its storage deposit.
+    /// Requires a contracts pallet providing the `remove_code` extrinsic
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "remove-code")]
+    RemoveCode {
+        #[structopt(flatten)]
+        extrinsic_opts: ExtrinsicOpts,
+        /// The hash of the code to remove
+        #[structopt(long, parse(try_from_str = parse_code_hash))]
+        code_hash: H256,
     },
     /// Upload all smart contracts selected in composable schedule to appointed by urls chains.
+    /// The url of each component is taken from the schedule, --url and --network are ignored
     #[cfg(feature = "extrinsics")]
     #[structopt(name = "composable-deploy")]
     ComposableDeploy {
-        /// Secret key URI for the account deploying the contract.
-        #[structopt(name = "suri", long, short)]
-        suri: String,
+        #[structopt(flatten)]
+        extrinsic_opts: ExtrinsicOpts,
+        /// Deploy a component to the given url instead of the one in the schedule, as
+        /// `<component>=<url>`. Can be passed multiple times
+        #[structopt(long = "url-override", parse(try_from_str = parse_url_override))]
+        url_overrides: Vec<(String, url::Url)>,
+        /// Only deploy the given components of the deploy schedule, comma separated
+        #[structopt(long, use_delimiter = true)]
+        components: Vec<String>,
     },
     /// Instantiate a deployed smart contract
     #[cfg(feature = "extrinsics")]
@@ -217,18 +380,152 @@ enum Command {
     Instantiate {
         #[structopt(flatten)]
         extrinsic_opts: ExtrinsicOpts,
-        /// Transfers an initial balance to the instantiated contract
+        /// Transfers an initial balance to the instantiated contract. Either an integer, or an
+        /// amount of the token like 1.5UNIT or 100mUNIT
         #[structopt(name = "endowment", long, default_value = "0")]
-        endowment: u128,
-        /// Maximum amount of gas to be used for this command
+        endowment: BalanceArg,
+        /// Maximum amount of gas to be used for this command, pass 0 to estimate it
+        #[structopt(name = "gas", long, default_value = "500000000")]
+        gas_limit: u64,
+        /// Estimate the gas required by a dry run of the instantiation, instead of using --gas
+        #[structopt(long)]
+        estimate_gas: bool,
+        /// Percentage added to the estimated gas as a safety margin
+        #[structopt(long, default_value = "10")]
+        gas_margin: u64,
+        /// The hash of the smart contract code already uploaded to the chain
+        #[structopt(
+            long,
+            parse(try_from_str = parse_code_hash),
+            required_unless_one = &["wasm-path", "bundle"],
+            conflicts_with_all = &["wasm-path", "bundle"]
+        )]
+        code_hash: Option<H256>,
+        /// Path to wasm contract code, or `-` to read it from stdin, to upload and instantiate in
+        /// a single extrinsic instead of passing --code-hash. Requires a contracts pallet
+        /// providing `instantiate_with_code`
+        #[structopt(long, parse(from_os_str))]
+        wasm_path: Option<PathBuf>,
+        /// Path to a `.contract` bundle, to upload and instantiate the code contained in it like
+        /// --wasm-path. The constructor is encoded with the metadata of the bundle
+        #[structopt(long, parse(from_os_str), conflicts_with = "wasm-path")]
+        bundle: Option<PathBuf>,
+        /// Hex encoded data to call a contract constructor. `@<path>` reads the raw bytes of a
+        /// file instead, `@hex:<path>` a hex encoded file
+        #[structopt(long)]
+        data: Option<HexData>,
+        /// Name of the constructor to call, looked up in the contract metadata. Defaults to the
+        /// only constructor of the contract if neither --data nor --message is passed
+        #[structopt(long, conflicts_with_all = &["data", "message"])]
+        constructor: Option<String>,
+        #[structopt(flatten)]
+        message_args: cmd::MessageArgs,
+        /// Hex encoded salt, used by newer contracts pallets to derive the contract address
+        #[structopt(long)]
+        salt: Option<HexData>,
+        /// Instantiate even if the endowment is below the existential deposit of the chain, in
+        /// which case the account of the contract is reaped
+        #[structopt(long)]
+        force: bool,
+        /// Append a json receipt of the instantiation to the file, recording the time, node,
+        /// signer, code hash, contract address, block and gas limit
+        #[structopt(long, parse(from_os_str))]
+        receipt: Option<PathBuf>,
+    },
+    /// Instantiate several contracts from uploaded code in a single `Utility::batch` extrinsic
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "instantiate-batch")]
+    InstantiateBatch {
+        #[structopt(flatten)]
+        extrinsic_opts: ExtrinsicOpts,
+        /// Number of contracts to instantiate
+        #[structopt(long)]
+        count: u32,
+        /// Transfers an initial balance to each instantiated contract. Either an integer, or an
+        /// amount of the token like 1.5UNIT or 100mUNIT
+        #[structopt(name = "endowment", long, default_value = "0")]
+        endowment: BalanceArg,
+        /// Maximum amount of gas to be used for each instantiation
         #[structopt(name = "gas", long, default_value = "500000000")]
         gas_limit: u64,
         /// The hash of the smart contract code already uploaded to the chain
         #[structopt(long, parse(try_from_str = parse_code_hash))]
         code_hash: H256,
-        /// Hex encoded data to call a contract constructor
+        /// Hex encoded data to call a contract constructor. `@<path>` reads the raw bytes of a
+        /// file instead, `@hex:<path>` a hex encoded file
         #[structopt(long)]
-        data: HexData,
+        data: Option<HexData>,
+        /// Name of the constructor to call, looked up in the contract metadata. Defaults to the
+        /// only constructor of the contract if neither --data nor --message is passed
+        #[structopt(long, conflicts_with_all = &["data", "message"])]
+        constructor: Option<String>,
+        #[structopt(flatten)]
+        message_args: cmd::MessageArgs,
+        /// Hex encoded salt prefix, the index of each instance is appended to it to derive
+        /// distinct contract addresses
+        #[structopt(long)]
+        salt: Option<HexData>,
+        /// Instantiate even if the endowment is below the existential deposit of the chain, in
+        /// which case the accounts of the contracts are reaped
+        #[structopt(long)]
+        force: bool,
+    },
+    /// Broadcast an extrinsic signed with --offline
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "submit")]
+    Submit {
+        /// Url of the RPC interface of a substrate node, ws(s):// or http(s)://
+        #[structopt(long, parse(try_from_str), default_value = "ws://localhost:9944")]
+        url: url::Url,
+        /// Do not verify the TLS certificate of a `wss://` node url
+        #[structopt(long)]
+        accept_invalid_certs: bool,
+        /// The hex encoded signed extrinsic
+        extrinsic: HexData,
+    },
+    /// Convert an account between its SS58 addresses and its hex encoded public key, e.g. to
+    /// prepare the hex encoded accounts of the gateway calls
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "account")]
+    Account {
+        /// SS58 address or 0x prefixed public key of the account
+        address: String,
+    },
+    /// Query the code and contract information stored on chain, without submitting anything
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "info")]
+    Info {
+        /// Url of the RPC interface of a substrate node, ws(s):// or http(s)://
+        #[structopt(long, parse(try_from_str), default_value = "ws://localhost:9944")]
+        url: url::Url,
+        /// Do not verify the TLS certificate of a `wss://` node url
+        #[structopt(long)]
+        accept_invalid_certs: bool,
+        /// The hash of the smart contract code to look up
+        #[structopt(long, parse(try_from_str = parse_code_hash), required_unless = "address")]
+        code_hash: Option<H256>,
+        /// The address of the contract to look up
+        #[structopt(long, parse(try_from_str = parse_account))]
+        address: Option<AccountId32>,
+    },
+    /// Dump the storage of a contract, decoding the values of its storage layout with the
+    /// contract metadata
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "storage")]
+    Storage {
+        /// Url of the RPC interface of a substrate node, ws(s):// or http(s)://
+        #[structopt(long, parse(try_from_str), default_value = "ws://localhost:9944")]
+        url: url::Url,
+        /// Do not verify the TLS certificate of a `wss://` node url
+        #[structopt(long)]
+        accept_invalid_certs: bool,
+        /// The address of the contract
+        #[structopt(long, parse(try_from_str = parse_account))]
+        address: AccountId32,
+        /// Path to the contract metadata. Defaults to the metadata the contract was instantiated
+        /// with from the current project, without it only the raw storage is dumped
+        #[structopt(long, parse(from_os_str))]
+        metadata: Option<PathBuf>,
     },
     /// Call for smart contract execution on Runtime Gateway
     #[cfg(feature = "extrinsics")]
@@ -245,18 +542,23 @@ enum Command {
         /// Execution Phase
         #[structopt(name = "phase", long, default_value = "0")]
         phase: u8,
-        /// Value of balance transfer optionally attached to the execution order
+        /// Value of balance transfer optionally attached to the execution order. Either an
+        /// integer, or an amount of the token like 1.5UNIT or 100mUNIT
         #[structopt(name = "value", long, default_value = "0")]
-        value: u128,
+        value: BalanceArg,
         /// Maximum amount of gas to be used for this command
         #[structopt(name = "gas", long, default_value = "500000000")]
         gas_limit: u64,
-        /// Path to wasm contract code, defaults to ./target/<name>-pruned.wasm
+        /// Path to wasm contract code, or `-` to read it from stdin, defaults to the wasm built for
+        /// the current project
         #[structopt(parse(from_os_str))]
         wasm_path: Option<PathBuf>,
-        /// Hex encoded data to call a contract constructor
+        /// Hex encoded data to call a contract message. `@<path>` reads the raw bytes of a file
+        /// instead, `@hex:<path>` a hex encoded file
         #[structopt(long, default_value = "00")]
         data: HexData,
+        #[structopt(flatten)]
+        message_args: cmd::MessageArgs,
     },
     /// Call for smart contract execution on Runtime Gateway
     #[cfg(feature = "extrinsics")]
@@ -264,8 +566,8 @@ enum Command {
     CallContractsGateway {
         #[structopt(flatten)]
         extrinsic_opts: ExtrinsicOpts,
-        /// Target chain destination
-        #[structopt(long, default_value = "00")]
+        /// Hex encoded 32 byte account id of the target contract
+        #[structopt(long)]
         target: HexData,
         /// Target chain destination
         #[structopt(name = "requester", long, short)]
@@ -273,18 +575,23 @@ enum Command {
         /// Execution Phase
         #[structopt(name = "phase", long, default_value = "0")]
         phase: u8,
-        /// Value of balance transfer optionally attached to the execution order
+        /// Value of balance transfer optionally attached to the execution order. Either an
+        /// integer, or an amount of the token like 1.5UNIT or 100mUNIT
         #[structopt(name = "value", long, default_value = "0")]
-        value: u128,
+        value: BalanceArg,
         /// Maximum amount of gas to be used for this command
         #[structopt(name = "gas", long, default_value = "3875000000")]
         gas_limit: u64,
-        /// Path to wasm contract code, defaults to ./target/<name>-pruned.wasm
+        /// Path to wasm contract code, or `-` to read it from stdin, defaults to the wasm built for
+        /// the current project
         #[structopt(parse(from_os_str))]
         wasm_path: Option<PathBuf>,
-        /// Hex encoded data to call a contract constructor
+        /// Hex encoded data to call a contract message. `@<path>` reads the raw bytes of a file
+        /// instead, `@hex:<path>` a hex encoded file
         #[structopt(long, default_value = "00")]
         data: HexData,
+        #[structopt(flatten)]
+        message_args: cmd::MessageArgs,
     },
     /// Call a regular smart contract execution via Contracts Pallet Call
     #[cfg(feature = "extrinsics")]
@@ -292,71 +599,325 @@ enum Command {
     CallContract {
         #[structopt(flatten)]
         extrinsic_opts: ExtrinsicOpts,
-        /// Target chain destination
-        #[structopt(long, default_value = "00")]
+        /// Hex encoded 32 byte account id of the target contract
+        #[structopt(long)]
         target: HexData,
-        /// Value of balance transfer optionally attached to the execution order
+        /// Value of balance transfer optionally attached to the execution order. Either an
+        /// integer, or an amount of the token like 1.5UNIT or 100mUNIT
         #[structopt(name = "value", long, default_value = "0")]
-        value: u128,
+        value: BalanceArg,
         /// Maximum amount of gas to be used for this command
         #[structopt(name = "gas", long, default_value = "3875000000")]
         gas_limit: u64,
-        /// Hex encoded data to call a contract constructor
+        /// Hex encoded data to call a contract message. `@<path>` reads the raw bytes of a file
+        /// instead, `@hex:<path>` a hex encoded file
         #[structopt(long, default_value = "00")]
         data: HexData,
+        #[structopt(flatten)]
+        message_args: cmd::MessageArgs,
+        /// Only dry run the call on the node and return its result, without submitting a
+        /// transaction. The default for messages which the metadata marks as read-only
+        #[structopt(long, conflicts_with = "execute")]
+        dry_run: bool,
+        /// Submit the call as an extrinsic, even if the message is read-only
+        #[structopt(long)]
+        execute: bool,
     },
 }
 
+impl Command {
+    /// Returns the verbosity the command was invoked with, `None` for the commands which do not
+    /// take `--quiet` or `--verbose`.
+    fn verbosity(&self) -> Result<Option<Verbosity>> {
+        match self {
+            Command::Build { verbosity, .. }
+            | Command::Check { verbosity, .. }
+            | Command::ComposableBuild { verbosity, .. }
+            | Command::GenerateMetadata { verbosity, .. }
+            | Command::Clean { verbosity, .. }
+            | Command::Test { verbosity, .. }
+            | Command::Verify { verbosity, .. } => verbosity.try_into(),
+            #[cfg(feature = "extrinsics")]
+            Command::Deploy { extrinsic_opts, .. }
+            | Command::RemoveCode { extrinsic_opts, .. }
+            | Command::Instantiate { extrinsic_opts, .. }
+            | Command::InstantiateBatch { extrinsic_opts, .. }
+            | Command::CallRuntimeGateway { extrinsic_opts, .. }
+            | Command::CallContractsGateway { extrinsic_opts, .. }
+            | Command::CallContract { extrinsic_opts, .. } => extrinsic_opts.verbosity(),
+            _ => Ok(None),
+        }
+    }
+
+    /// Passes `--quiet` to the command, overriding `--verbose`. Commands which do not take the
+    /// verbosity flags are left as they are.
+    fn make_quiet(&mut self) {
+        let flags = match self {
+            Command::Build { verbosity, .. }
+            | Command::Check { verbosity, .. }
+            | Command::ComposableBuild { verbosity, .. }
+            | Command::GenerateMetadata { verbosity, .. }
+            | Command::Clean { verbosity, .. }
+            | Command::Test { verbosity, .. }
+            | Command::Verify { verbosity, .. } => verbosity,
+            #[cfg(feature = "extrinsics")]
+            Command::Deploy { extrinsic_opts, .. }
+            | Command::RemoveCode { extrinsic_opts, .. }
+            | Command::Instantiate { extrinsic_opts, .. }
+            | Command::InstantiateBatch { extrinsic_opts, .. }
+            | Command::CallRuntimeGateway { extrinsic_opts, .. }
+            | Command::CallContractsGateway { extrinsic_opts, .. }
+            | Command::CallContract { extrinsic_opts, .. } => &mut extrinsic_opts.verbosity,
+            _ => return,
+        };
+        flags.quiet = true;
+        flags.verbose = false;
+    }
+}
+
 #[cfg(feature = "extrinsics")]
-fn parse_code_hash(input: &str) -> Result<H256> {
-    let bytes = hex::decode(input)?;
-    if bytes.len() != 32 {
-        anyhow::bail!("Code hash should be 32 bytes in length")
+fn parse_url_override(input: &str) -> Result<(String, url::Url)> {
+    let mut parts = input.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(component), Some(url)) if !component.is_empty() => {
+            Ok((component.to_string(), url::Url::parse(url)?))
+        }
+        _ => anyhow::bail!("Url override should be given as <component>=<url>"),
     }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&bytes);
-    Ok(H256(arr))
 }
 
+#[cfg(feature = "extrinsics")]
+fn parse_account(input: &str) -> Result<AccountId32> {
+    use sp_core::crypto::Ss58Codec;
+    AccountId32::from_ss58check(input)
+        .map_err(|err| anyhow::anyhow!("Invalid SS58 address: {:?}", err))
+}
+
+/// Exit code of a command failing for a reason not covered by the other exit codes.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code if the command line arguments could not be parsed.
+const EXIT_BAD_ARGUMENTS: i32 = 2;
+/// Exit code if compiling or testing the contract failed.
+const EXIT_BUILD_FAILURE: i32 = 3;
+/// Exit code if the node could not be reached.
+const EXIT_NETWORK_FAILURE: i32 = 4;
+
 fn main() {
     env_logger::init();
 
-    let Opts::Contract(args) = Opts::from_args();
-    match exec(args.cmd) {
-        Ok(msg) => println!("\t{}", msg),
-        Err(err) => eprintln!(
-            "{} {}",
-            "ERROR:".bright_red().bold(),
-            format!("{:?}", err).bright_red()
-        ),
+    let args = match config::apply(std::env::args().collect()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!(
+                "{} {}",
+                "ERROR:".bright_red().bold(),
+                format!("{:?}", err).bright_red()
+            );
+            std::process::exit(EXIT_BAD_ARGUMENTS)
+        }
+    };
+    let Opts::Contract(mut args) = match Opts::from_iter_safe(args) {
+        Ok(opts) => opts,
+        Err(err) if err.use_stderr() => {
+            eprintln!("{}", err.message);
+            std::process::exit(EXIT_BAD_ARGUMENTS)
+        }
+        // help and version information
+        Err(err) => err.exit(),
+    };
+    if let Command::Completions { shell } = &args.cmd {
+        // written as is, without the indentation of the results of the other commands
+        completions(*shell, &mut std::io::stdout());
+        return;
+    }
+    // removes the temporary files of a build when interrupted, Ctrl-C still exits without it
+    if let Err(err) = interrupt::install_handler() {
+        log::warn!("{:?}", err);
+    }
+    if args.output_json {
+        // stdout only carries the json object, status messages are left out entirely
+        args.cmd.make_quiet();
+    }
+    let colorize = !args.output_json
+        && args.color.should_colorize(
+            |var| std::env::var(var).ok(),
+            atty::is(atty::Stream::Stdout),
+        );
+    colored::control::set_override(colorize);
+    // conflicting verbosity flags are reported by the command itself
+    let quiet = matches!(args.cmd.verbosity(), Ok(Some(Verbosity::Quiet)));
+    // the result is the last of the json messages, which are not indented
+    let json_messages = matches!(
+        args.cmd,
+        Command::Build {
+            message_format: MessageFormat::Json,
+            ..
+        }
+    );
+    let result = exec(&args.cmd, args.output_json);
+    if args.output_json {
+        println!("{}", render_json(&result));
+    } else {
+        match &result {
+            // a quiet command succeeds silently, scripts rely on the exit code
+            Ok(msg) if json_messages => println!("{}", msg),
+            Ok(_) if quiet => (),
+            Ok(msg) => println!("\t{}", msg),
+            Err(err) => eprintln!(
+                "{} {}",
+                "ERROR:".bright_red().bold(),
+                format!("{:?}", err).bright_red()
+            ),
+        }
+    }
+    if let Err(err) = result {
+        std::process::exit(exit_code(&args.cmd, &err))
+    }
+}
+
+/// Writes the completion script for `cargo contract` in the shell.
+fn completions<W: std::io::Write>(shell: clap::Shell, out: &mut W) {
+    Opts::clap().gen_completions_to("cargo", shell, out);
+}
+
+/// Returns the exit code for the error of a failed command, so that scripts can branch on the
+/// category of the failure.
+fn exit_code(cmd: &Command, err: &Error) -> i32 {
+    if util::is_network_error(err) {
+        return EXIT_NETWORK_FAILURE;
+    }
+    match cmd {
+        Command::Build { .. }
+        | Command::Check { .. }
+        | Command::ComposableBuild { .. }
+        | Command::GenerateMetadata { .. }
+        | Command::Test { .. } => EXIT_BUILD_FAILURE,
+        _ => EXIT_FAILURE,
     }
 }
 
-fn exec(cmd: Command) -> Result<String> {
-    match &cmd {
-        Command::New { name, target_dir } => cmd::new::execute(name, target_dir.as_ref()),
+/// Renders the outcome of a command as `{ "result": ... }` or `{ "error": "..." }`.
+///
+/// Results which are themselves JSON objects, e.g. the result of `build`, are embedded as is.
+fn render_json(result: &Result<String>) -> serde_json::Value {
+    match result {
+        Ok(msg) => {
+            let result = match serde_json::from_str(msg) {
+                Ok(object @ serde_json::Value::Object(_)) => object,
+                _ => serde_json::Value::String(msg.clone()),
+            };
+            serde_json::json!({ "result": result })
+        }
+        Err(err) => serde_json::json!({ "error": format!("{:#}", err) }),
+    }
+}
+
+fn exec(cmd: &Command, output_json: bool) -> Result<String> {
+    match cmd {
+        Command::New {
+            name,
+            target_dir,
+            template,
+            git,
+            force,
+        } => cmd::new::execute(name, target_dir.as_ref(), *template, *git, *force),
         Command::Build {
+            manifest_path,
             verbosity,
             unstable_options,
+            features,
+            debug,
+            skip_optimization,
+            optimization_passes,
+            force,
+            target_dir,
+            max_code_size,
+            post_build,
+            watch,
+            message_format,
+            toolchain,
+            lock,
         } => {
-            let manifest_path = Default::default();
-            let dest_wasm = cmd::build::execute(
+            let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+            let optimization_passes = if *skip_optimization {
+                OptimizationPasses::Zero
+            } else {
+                *optimization_passes
+            };
+            if *watch {
+                return cmd::watch::execute(
+                    &manifest_path,
+                    verbosity.try_into()?,
+                    *debug,
+                    optimization_passes,
+                    target_dir.as_deref(),
+                    toolchain.as_deref(),
+                    lock.into(),
+                    unstable_options.try_into()?,
+                    &features.into(),
+                );
+            }
+            let json_messages = *message_format == MessageFormat::Json;
+            let verbosity = if json_messages {
+                Some(Verbosity::Quiet)
+            } else {
+                verbosity.try_into()?
+            };
+            let options = cmd::build::BuildOptions {
+                verbosity,
+                debug: *debug,
+                optimization_passes,
+                force: *force,
+                message_format: *message_format,
+                target_dir: target_dir.clone(),
+                max_code_size: *max_code_size,
+                post_build: post_build.clone(),
+                toolchain: toolchain.clone(),
+                lock: lock.into(),
+                unstable_options: unstable_options.try_into()?,
+                features: features.into(),
+            };
+            let result = cmd::build::execute(&manifest_path, options)?;
+            if output_json {
+                Ok(serde_json::to_string(&result)?)
+            } else if json_messages {
+                let mut record = serde_json::to_value(&result)?;
+                record["reason"] = "contract-build-finished".into();
+                Ok(serde_json::to_string(&record)?)
+            } else {
+                Ok(format!(
+                    "\nYour contract is ready. You can find it here:\n{}\nCode hash: {}",
+                    result.dest_wasm.display().to_string().bold(),
+                    result.code_hash
+                ))
+            }
+        }
+        Command::Check {
+            manifest_path,
+            verbosity,
+            unstable_options,
+            features,
+        } => {
+            let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+            cmd::check::execute(
                 &manifest_path,
                 verbosity.try_into()?,
                 unstable_options.try_into()?,
-            )?;
-            Ok(format!(
-                "\nYour contract is ready. You can find it here:\n{}",
-                dest_wasm.display().to_string().bold()
-            ))
+                &features.into(),
+            )
         }
         Command::ComposableBuild {
+            manifest_path,
             verbosity,
             unstable_options,
+            components,
+            jobs,
         } => {
-            let manifest_path = Default::default();
+            let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
             let dest_wasm = cmd::composable_build::execute(
                 &manifest_path,
+                components,
+                *jobs,
                 verbosity.try_into()?,
                 unstable_options.try_into()?,
             )?;
@@ -366,88 +927,363 @@ fn exec(cmd: Command) -> Result<String> {
             ))
         }
         Command::GenerateMetadata {
+            manifest_path,
             verbosity,
             unstable_options,
+            target_dir,
+            force,
+            skip_optimization,
+            abi,
+            output_dir,
+            name,
+            metadata_version,
         } => {
-            let metadata_file = cmd::metadata::execute(
-                Default::default(),
+            let metadata_result = cmd::metadata::execute(
+                ManifestPath::try_from(manifest_path.as_ref())?,
                 verbosity.try_into()?,
+                target_dir.as_deref(),
                 unstable_options.try_into()?,
+                *force,
+                *skip_optimization,
+                *abi,
+                output_dir.as_deref(),
+                name.as_deref(),
+                metadata_version.clone().unwrap_or_default(),
             )?;
-            Ok(format!(
-                "Your metadata file is ready.\nYou can find it here:\n{}",
-                metadata_file.display()
-            ))
+            let mut result = format!(
+                "Your metadata file is ready.\nYou can find it here:\n{}\n\
+                 The contract bundle, containing both the metadata and the wasm code, is here:\n{}",
+                metadata_result.metadata_file.display(),
+                metadata_result.bundle_file.display()
+            );
+            if let Some(abi_file) = metadata_result.abi_file {
+                result.push_str(&format!("\nThe ABI is here:\n{}", abi_file.display()));
+            }
+            Ok(result)
+        }
+        Command::Clean {
+            manifest_path,
+            deep,
+            verbosity,
+        } => {
+            let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+            cmd::clean::execute(&manifest_path, *deep, verbosity.try_into()?)
+        }
+        Command::Test {
+            manifest_path,
+            verbosity,
+            features,
+        } => {
+            let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+            cmd::test::execute(&manifest_path, verbosity.try_into()?, &features.into())
         }
-        Command::Test {} => Err(anyhow::anyhow!("Command unimplemented")),
+        Command::Verify {
+            manifest_path,
+            wasm,
+            verbosity,
+            unstable_options,
+            features,
+        } => {
+            let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+            let result = cmd::verify::execute(
+                &manifest_path,
+                wasm,
+                verbosity.try_into()?,
+                unstable_options.try_into()?,
+                &features.into(),
+            )?;
+            if !result.matches() {
+                anyhow::bail!("{}", result.mismatch_report())
+            }
+            if output_json {
+                Ok(serde_json::to_string(&result)?)
+            } else {
+                Ok(format!(
+                    "The wasm file {} matches the source, code hash: {}",
+                    wasm.display().to_string().bold(),
+                    result.actual_hash
+                ))
+            }
+        }
+        Command::SizeAnalysis {
+            manifest_path,
+            top,
+            raw,
+        } => {
+            let manifest_path = ManifestPath::try_from(manifest_path.as_ref())?;
+            let result = cmd::size_analysis::execute(&manifest_path, *raw, *top)?;
+            if output_json {
+                Ok(serde_json::to_string(&result)?)
+            } else {
+                Ok(result.to_string())
+            }
+        }
+        Command::CheckEnv => {
+            let result = cmd::check_env::execute()?;
+            if !result.is_ready() {
+                anyhow::bail!("{}", result.missing_report())
+            }
+            if output_json {
+                Ok(serde_json::to_string(&result)?)
+            } else {
+                Ok(result.to_string())
+            }
+        }
+        Command::Completions { .. } => unreachable!("completions are written by main"),
         #[cfg(feature = "extrinsics")]
         Command::Deploy {
             extrinsic_opts,
             wasm_path,
+            bundle,
+            dry_run,
+            verify,
+            expect_hash,
+            receipt,
         } => {
-            let code_hash = cmd::execute_deploy(extrinsic_opts, wasm_path.as_ref())?;
-            Ok(format!("Code hash: {:?}", code_hash))
+            let code = cmd::deploy::load_code(wasm_path.as_ref(), bundle.as_deref())?;
+            let result =
+                cmd::execute_deploy(extrinsic_opts, &code, *dry_run, *verify, *expect_hash)?;
+            if let (Some(receipt), Some(inclusion)) = (receipt, &result.inclusion) {
+                let written = Receipt::new(extrinsic_opts, result.code_hash, inclusion)
+                    .and_then(|receipt_record| receipt_record.append_to(receipt));
+                if let Err(err) = written {
+                    extrinsic_opts
+                        .reporter()?
+                        .warning(format_args!("The receipt could not be written: {:#}", err));
+                }
+            }
+            if output_json {
+                Ok(serde_json::to_string(&result)?)
+            } else if *dry_run {
+                Ok(format!(
+                    "Dry run, no transaction was sent. Code hash: {:?}",
+                    result.code_hash
+                ))
+            } else if let Some(extrinsic) = &result.extrinsic {
+                Ok(format!(
+                    "Signed offline, broadcast the extrinsic with `submit`. Code hash: {:?}\n{}",
+                    result.code_hash, extrinsic
+                ))
+            } else {
+                match result.inclusion {
+                    Some(inclusion) => Ok(format!(
+                        "Code hash: {:?}, included in {}",
+                        result.code_hash, inclusion
+                    )),
+                    None => Ok(format!("Code hash: {:?}", result.code_hash)),
+                }
+            }
         }
         #[cfg(feature = "extrinsics")]
-        Command::ComposableDeploy { suri } => {
-            let manifest_path = Default::default();
-            let crate_metadata = CrateMetadata::collect(&manifest_path)?;
-            println!(
-                "{}",
-                "Deploy composable components to appointed urls"
-                    .bright_blue()
-                    .bold(),
-            );
-            let composable_schedule = crate_metadata.clone().t3rn_composable_schedule
-                .expect("Failed to read composable metadata from JSON using serde. Make sure your Cargo.toml follows the composable metadata format");
-            match composable_schedule.deploy {
-                Some(deploy_schedule) => {
-                    for deploy in deploy_schedule {
-                        println!("Deploying: {:?}", deploy);
-                        let component_extrinsic_opts = ExtrinsicOpts {
-                            url: url::Url::parse(&deploy.url)?,
-                            suri: suri.to_string(),
-                            password: None,
-                        };
-                        let dest_wasm_path = cmd::composable_build::get_dest_wasm_path(
-                            deploy.compose.clone(),
-                            &crate_metadata.clone(),
-                        );
-                        let code_hash =
-                            cmd::execute_deploy(&component_extrinsic_opts, Some(&dest_wasm_path))?;
-                        println!(
-                            "{} - {} {:?}",
-                            deploy.compose.bright_blue().bold(),
-                            "successfully deployed byte code with hash: ".bright_blue(),
-                            code_hash
-                        );
-                    }
-                    Ok(format!(
-                        "All components successfully deployed for {:?}",
-                        suri
-                    ))
+        Command::RemoveCode {
+            extrinsic_opts,
+            code_hash,
+        } => {
+            let result = cmd::remove_code::execute(extrinsic_opts, *code_hash)?;
+            if output_json {
+                Ok(serde_json::to_string(&result)?)
+            } else if let Some(extrinsic) = &result.extrinsic {
+                Ok(format!(
+                    "Signed offline, broadcast the extrinsic with `submit`. Code hash: {:?}\n{}",
+                    result.code_hash, extrinsic
+                ))
+            } else {
+                match result.inclusion {
+                    Some(inclusion) => Ok(format!(
+                        "Removed code {:?}, included in {}",
+                        result.code_hash, inclusion
+                    )),
+                    None => Ok(format!("Removed code {:?}", result.code_hash)),
                 }
-                None => Err(anyhow::anyhow!(
-                    "Nothing to deploy. Empty deploy key of composable metadata."
-                )),
             }
         }
         #[cfg(feature = "extrinsics")]
+        Command::ComposableDeploy {
+            extrinsic_opts,
+            url_overrides,
+            components,
+        } => cmd::composable_deploy::execute(extrinsic_opts, url_overrides, components),
+        #[cfg(feature = "extrinsics")]
         Command::Instantiate {
             extrinsic_opts,
             endowment,
             code_hash,
+            wasm_path,
+            bundle,
+            gas_limit,
+            estimate_gas,
+            gas_margin,
+            data,
+            constructor,
+            message_args,
+            salt,
+            force,
+            receipt,
+        } => {
+            let gas_limit = if *estimate_gas || *gas_limit == 0 {
+                None
+            } else {
+                Some(*gas_limit)
+            };
+            let code = match (code_hash, wasm_path) {
+                (Some(code_hash), _) => cmd::Code::Existing(*code_hash),
+                (None, wasm_path) => cmd::Code::Upload(
+                    cmd::deploy::load_code(wasm_path.as_ref(), bundle.as_deref())?.into(),
+                ),
+            };
+            let result = cmd::execute_instantiate(
+                extrinsic_opts,
+                extrinsic_opts.balance(endowment)?,
+                gas_limit,
+                *gas_margin,
+                code,
+                message_args.constructor_data(
+                    constructor.as_deref(),
+                    data.as_ref(),
+                    bundle.as_deref(),
+                )?,
+                salt.clone(),
+                *force,
+            )?;
+            if let Some(contract) = &result.contract {
+                let registered = message_args.register_contract(
+                    contract,
+                    result.code_hash.as_bytes(),
+                    constructor.as_deref(),
+                    data.as_ref(),
+                    bundle.as_deref(),
+                );
+                if let Err(err) = registered {
+                    extrinsic_opts.reporter()?.warning(format_args!(
+                        "The metadata of the contract could not be registered: {:#}",
+                        err
+                    ));
+                }
+            }
+            if let (Some(receipt), Some(contract), Some(inclusion)) =
+                (receipt, &result.contract, &result.inclusion)
+            {
+                let written = Receipt::new(extrinsic_opts, result.code_hash, inclusion).and_then(
+                    |receipt_record| {
+                        receipt_record
+                            .with_contract(extrinsic_opts, contract, result.gas_limit)
+                            .append_to(receipt)
+                    },
+                );
+                if let Err(err) = written {
+                    extrinsic_opts
+                        .reporter()?
+                        .warning(format_args!("The receipt could not be written: {:#}", err));
+                }
+            }
+            if output_json {
+                return Ok(serde_json::to_string(&result)?);
+            }
+            match (result.contract, result.inclusion) {
+                (Some(contract_account), Some(inclusion)) => Ok(format!(
+                    "Contract account: {}, included in {}",
+                    extrinsic_opts.ss58_address_or_default(&contract_account),
+                    inclusion
+                )),
+                (Some(contract_account), None) => Ok(format!(
+                    "Contract account: {}",
+                    extrinsic_opts.ss58_address_or_default(&contract_account)
+                )),
+                (None, _) => Ok(format!(
+                    "Signed offline, broadcast the extrinsic with `submit`\n{}",
+                    result.extrinsic.unwrap_or_default()
+                )),
+            }
+        }
+        #[cfg(feature = "extrinsics")]
+        Command::InstantiateBatch {
+            extrinsic_opts,
+            count,
+            endowment,
             gas_limit,
+            code_hash,
             data,
+            constructor,
+            message_args,
+            salt,
+            force,
         } => {
-            let contract_account = cmd::execute_instantiate(
+            let result = cmd::execute_instantiate_batch(
                 extrinsic_opts,
-                *endowment,
+                extrinsic_opts.balance(endowment)?,
                 *gas_limit,
                 *code_hash,
-                data.clone(),
+                message_args.constructor_data(constructor.as_deref(), data.as_ref(), None)?,
+                salt.clone(),
+                *count,
+                *force,
             )?;
-            Ok(format!("Contract account: {:?}", contract_account))
+            if output_json {
+                return Ok(serde_json::to_string(&result)?);
+            }
+            let mut output = format!(
+                "Instantiated {} of {} contracts, included in {}",
+                result.contracts.len(),
+                count,
+                result.inclusion
+            );
+            for contract in &result.contracts {
+                output.push_str(&format!(
+                    "\n  {}",
+                    extrinsic_opts.ss58_address_or_default(contract)
+                ));
+            }
+            if let Some(failure) = &result.failure {
+                output.push_str(&format!(
+                    "\nInstantiation {} failed: {}. The {} instantiations after it were skipped",
+                    failure.index, failure.error, failure.skipped
+                ));
+            }
+            Ok(output)
+        }
+        #[cfg(feature = "extrinsics")]
+        Command::Submit {
+            url,
+            accept_invalid_certs,
+            extrinsic,
+        } => {
+            let hash = cmd::offline::submit(url, *accept_invalid_certs, extrinsic)?;
+            Ok(format!("Extrinsic submitted, hash: {:?}", hash))
+        }
+        #[cfg(feature = "extrinsics")]
+        Command::Account { address } => {
+            let result = cmd::account::execute(address)?;
+            if output_json {
+                Ok(serde_json::to_string(&result)?)
+            } else {
+                Ok(result.to_string())
+            }
+        }
+        #[cfg(feature = "extrinsics")]
+        Command::Info {
+            url,
+            accept_invalid_certs,
+            code_hash,
+            address,
+        } => cmd::info::execute(url, *accept_invalid_certs, *code_hash, address.clone()),
+        #[cfg(feature = "extrinsics")]
+        Command::Storage {
+            url,
+            accept_invalid_certs,
+            address,
+            metadata,
+        } => {
+            let metadata = match metadata {
+                Some(metadata) => Some(metadata.clone()),
+                None => registry::registered_metadata_path(AsRef::<[u8]>::as_ref(address))?,
+            };
+            let result =
+                cmd::storage::execute(url, *accept_invalid_certs, address, metadata.as_deref())?;
+            if output_json {
+                Ok(serde_json::to_string(&result)?)
+            } else {
+                Ok(result.to_string())
+            }
         }
         #[cfg(feature = "extrinsics")]
         Command::CallRuntimeGateway {
@@ -459,27 +1295,42 @@ fn exec(cmd: Command) -> Result<String> {
             value,
             gas_limit,
             data,
+            message_args,
         } => {
-            let code = cmd::deploy::load_contract_code(wasm_path.as_ref())?;
-
-            let pair_target = sr25519::Pair::from_string(target, None)
-                .map_err(|_| anyhow::anyhow!("Target account read string error"))?;
+            let code = cmd::deploy::load_code(wasm_path.as_ref(), None)?;
 
-            let pair_requester = sr25519::Pair::from_string(requester, None)
-                .map_err(|_| anyhow::anyhow!("Requester account read string error"))?;
+            let target = extrinsic_opts
+                .account_id(target)
+                .context("Target account read string error")?;
+            let requester = extrinsic_opts
+                .account_id(requester)
+                .context("Requester account read string error")?;
 
             let res = cmd::execute_call(
                 extrinsic_opts,
-                AccountId32::from(pair_requester.public()),
-                AccountId32::from(pair_target.public()),
+                requester,
+                target,
                 *phase,
                 &code,
-                *value,
+                extrinsic_opts.balance(value)?,
                 *gas_limit,
-                data.clone(),
+                message_args.input_data(cmd::MessageKind::Message, Some(data))?,
             )?;
 
-            Ok(format!("CallRuntimeGateway result: {:?}", res))
+            if output_json {
+                let result = serde_json::json!({
+                    "execution_stamp": format!("{:?}", res.execution_stamp),
+                    "block_hash": res.inclusion.block_hash,
+                    "extrinsic_index": res.inclusion.extrinsic_index,
+                    "finality": res.inclusion.finality,
+                });
+                Ok(result.to_string())
+            } else {
+                Ok(format!(
+                    "CallRuntimeGateway result: {:?}, included in {}",
+                    res.execution_stamp, res.inclusion
+                ))
+            }
         }
         #[cfg(feature = "extrinsics")]
         Command::CallContractsGateway {
@@ -491,35 +1342,32 @@ fn exec(cmd: Command) -> Result<String> {
             value,
             gas_limit,
             data,
+            message_args,
         } => {
-            let code = match cmd::deploy::load_contract_code(wasm_path.as_ref()) {
+            let code = match cmd::deploy::load_code(wasm_path.as_ref(), None) {
                 Ok(loaded_code) => loaded_code,
                 Err(_) => {
-                    println!(
-                        "Correct code not found. Proceeding with a direct contract call at target_dest"
+                    extrinsic_opts.reporter()?.info(
+                        "Correct code not found. Proceeding with a direct contract call at target_dest",
                     );
                     vec![]
                 }
             };
-            let pair_requester = sr25519::Pair::from_string(requester, None)
-                .map_err(|_| anyhow::anyhow!("Requester account read string error"))?;
-            println!(
-                ".clone().0.as_slice() {:?} {:?} ",
-                target,
-                target.clone().0.as_slice()
-            );
+            let requester = extrinsic_opts
+                .account_id(requester)
+                .context("Requester account read string error")?;
             let res = cmd::execute_contract_call(
                 extrinsic_opts,
-                AccountId32::from(pair_requester.public()),
-                AccountId32::from(sr25519::Public::from_slice(target.0.as_slice())),
+                requester,
+                cmd::account::account_from_hex("target", target)?,
                 *phase,
                 &code,
-                *value,
+                extrinsic_opts.balance(value)?,
                 *gas_limit,
-                data.clone(),
+                message_args.input_data(cmd::MessageKind::Message, Some(data))?,
             )?;
 
-            Ok(format!("CallRuntimeGateway result: {:?}", res))
+            Ok(format!("CallContractsGateway result: {:?}", res))
         }
         #[cfg(feature = "extrinsics")]
         Command::CallContract {
@@ -528,16 +1376,247 @@ fn exec(cmd: Command) -> Result<String> {
             value,
             gas_limit,
             data,
+            message_args,
+            dry_run,
+            execute,
         } => {
+            let target = cmd::account::account_from_hex("target", target)?;
+            let metadata_path = message_args.contract_metadata_path(&target)?;
+            let mode = match (dry_run, execute) {
+                (true, _) => cmd::CallMode::DryRun,
+                (_, true) => cmd::CallMode::Execute,
+                _ => cmd::CallMode::Auto,
+            };
             let res = cmd::call_regular_contract(
                 extrinsic_opts,
-                AccountId32::from(sr25519::Public::from_slice(target.0.as_slice())),
-                *value,
+                target,
+                extrinsic_opts.balance(value)?,
                 *gas_limit,
-                data.clone(),
+                message_args.call_data(metadata_path.as_deref(), data)?,
+                metadata_path.as_deref(),
+                mode,
             )?;
 
-            Ok(format!("Call regular contract result: {:?}", res))
+            match res.inclusion {
+                Some(inclusion) => Ok(format!(
+                    "Call regular contract result: {}, included in {}",
+                    res.output, inclusion
+                )),
+                None => Ok(format!(
+                    "Call regular contract result (dry run): {}",
+                    res.output
+                )),
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "extrinsics")]
+    use super::parse_url_override;
+    use super::render_json;
+
+    #[test]
+    fn color_choice_honors_environment() {
+        use super::ColorChoice;
+
+        let no_env = |_: &str| None;
+        assert!(ColorChoice::Auto.should_colorize(no_env, true));
+        assert!(!ColorChoice::Auto.should_colorize(no_env, false));
+        assert!(ColorChoice::Always.should_colorize(no_env, false));
+        assert!(!ColorChoice::Never.should_colorize(no_env, true));
+
+        let no_color = |var: &str| {
+            Some(var)
+                .filter(|var| *var == "NO_COLOR")
+                .map(|_| String::new())
+        };
+        assert!(!ColorChoice::Auto.should_colorize(no_color, true));
+        assert!(ColorChoice::Always.should_colorize(no_color, true));
+        let clicolor_off = |var: &str| {
+            Some(var)
+                .filter(|var| *var == "CLICOLOR")
+                .map(|_| "0".into())
+        };
+        assert!(!ColorChoice::Auto.should_colorize(clicolor_off, true));
+        let force = |var: &str| {
+            Some(var)
+                .filter(|var| *var == "CLICOLOR_FORCE")
+                .map(|_| "1".into())
+        };
+        assert!(ColorChoice::Auto.should_colorize(force, false));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+
+    #[test]
+    fn completions_cover_subcommands() {
+        use super::{clap::Shell, Opts};
+        use structopt::StructOpt;
+
+        for shell in &[Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut script = Vec::new();
+            super::completions(*shell, &mut script);
+            let script = String::from_utf8(script).expect("completions are utf-8");
+            assert!(script.contains("generate-metadata"), "{:?}", shell);
+        }
+
+        let err = Opts::from_iter_safe(&["cargo", "contract", "completions", "tcsh"]).unwrap_err();
+        assert_eq!(err.kind, structopt::clap::ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn verbosity_of_command_is_visible_to_main() {
+        use super::{Opts, Verbosity};
+        use structopt::StructOpt;
+
+        let verbosity = |args: &[&str]| {
+            let Opts::Contract(args) = Opts::from_iter(["cargo", "contract"].iter().chain(args));
+            args.cmd.verbosity()
+        };
+        assert!(matches!(
+            verbosity(&["build", "--quiet"]),
+            Ok(Some(Verbosity::Quiet))
+        ));
+        assert!(matches!(
+            verbosity(&["test", "--verbose"]),
+            Ok(Some(Verbosity::Verbose))
+        ));
+        assert!(matches!(verbosity(&["clean"]), Ok(None)));
+        assert!(matches!(verbosity(&["new", "flipper"]), Ok(None)));
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn output_json_makes_every_command_quiet() {
+        use super::{Opts, Verbosity};
+        use structopt::StructOpt;
+
+        let quiet_verbosity = |args: &[&str]| {
+            let Opts::Contract(mut args) =
+                Opts::from_iter(["cargo", "contract"].iter().chain(args));
+            args.cmd.make_quiet();
+            args.cmd.verbosity()
+        };
+        assert!(matches!(
+            quiet_verbosity(&["verify", "--wasm", "contract.wasm", "--verbose"]),
+            Ok(Some(Verbosity::Quiet))
+        ));
+        assert!(matches!(
+            quiet_verbosity(&[
+                "remove-code",
+                "--suri",
+                "//Alice",
+                "--code-hash",
+                &"0".repeat(64)
+            ]),
+            Ok(Some(Verbosity::Quiet))
+        ));
+        assert!(matches!(quiet_verbosity(&["new", "flipper"]), Ok(None)));
+    }
+
+    #[test]
+    fn json_message_format_cannot_be_watched() {
+        use super::{Command, MessageFormat, Opts};
+        use structopt::StructOpt;
+
+        let Opts::Contract(args) =
+            Opts::from_iter(&["cargo", "contract", "build", "--message-format", "json"]);
+        assert!(matches!(
+            args.cmd,
+            Command::Build {
+                message_format: MessageFormat::Json,
+                ..
+            }
+        ));
+        assert!(Opts::from_iter_safe(&[
+            "cargo",
+            "contract",
+            "build",
+            "--message-format",
+            "json",
+            "--watch"
+        ])
+        .is_err());
+        assert!(
+            Opts::from_iter_safe(&["cargo", "contract", "build", "--message-format", "xml"])
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn upload_is_an_alias_of_deploy() {
+        use super::{Command, Opts};
+        use structopt::StructOpt;
+
+        let Opts::Contract(args) =
+            Opts::from_iter(&["cargo", "contract", "upload", "contract.wasm", "--dry-run"]);
+        match args.cmd {
+            Command::Deploy {
+                wasm_path, dry_run, ..
+            } => {
+                assert_eq!(wasm_path, Some(std::path::PathBuf::from("contract.wasm")));
+                assert!(dry_run);
+            }
+            cmd => panic!("expected the deploy command, got {:?}", cmd),
+        }
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn instantiate_requires_either_code_hash_or_wasm_path() {
+        use super::Opts;
+        use structopt::StructOpt;
+
+        let instantiate = |args: &[&str]| {
+            let base = ["cargo", "contract", "instantiate", "--data", "00"];
+            Opts::from_iter_safe(base.iter().chain(args))
+        };
+        let code_hash = format!("0x{}", "11".repeat(32));
+        assert!(instantiate(&["--code-hash", &code_hash]).is_ok());
+        assert!(instantiate(&["--wasm-path", "contract.wasm"]).is_ok());
+        assert!(instantiate(&["--bundle", "flipper.contract"]).is_ok());
+        assert!(instantiate(&[]).is_err());
+        // the constructor is encoded from the metadata, it cannot be combined with raw data
+        assert!(instantiate(&["--code-hash", &code_hash, "--constructor", "new"]).is_err());
+        assert!(instantiate(&["--code-hash", &code_hash, "--wasm-path", "contract.wasm"]).is_err());
+        assert!(instantiate(&["--code-hash", &code_hash, "--bundle", "flipper.contract"]).is_err());
+        assert!(instantiate(&[
+            "--wasm-path",
+            "contract.wasm",
+            "--bundle",
+            "flipper.contract"
+        ])
+        .is_err());
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn url_override_is_split_at_first_equals_sign() {
+        let (component, url) = parse_url_override("flipper=ws://localhost:9944/?a=b").unwrap();
+        assert_eq!(component, "flipper");
+        assert_eq!(url.as_str(), "ws://localhost:9944/?a=b");
+        assert!(parse_url_override("ws://localhost:9944").is_err());
+        assert!(parse_url_override("=ws://localhost:9944").is_err());
+    }
+
+    #[test]
+    fn render_json_wraps_message_as_result() {
+        let json = render_json(&Ok("Created contract flipper".to_string()));
+        assert_eq!(json.to_string(), r#"{"result":"Created contract flipper"}"#);
+    }
+
+    #[test]
+    fn render_json_embeds_json_object_result() {
+        let json = render_json(&Ok(r#"{"optimized_size":42}"#.to_string()));
+        assert_eq!(json.to_string(), r#"{"result":{"optimized_size":42}}"#);
+    }
+
+    #[test]
+    fn render_json_renders_error_with_causes() {
+        let err = anyhow::anyhow!("inner").context("outer");
+        let json = render_json(&Err(err));
+        assert_eq!(json.to_string(), r#"{"error":"outer: inner"}"#);
+    }
+}
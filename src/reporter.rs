@@ -0,0 +1,149 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    fmt::Display,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use colored::Colorize;
+
+use crate::Verbosity;
+
+/// Prints the status messages of a command, honoring the `Verbosity` it was invoked with.
+///
+/// All messages go to stderr, so that stdout only carries the result of the command and stays
+/// machine readable. With `Verbosity::Quiet` nothing but errors is printed, messages reported via
+/// [`Reporter::verbose`] only show up with `Verbosity::Verbose`.
+#[derive(Clone, Copy, Default)]
+pub struct Reporter {
+    verbosity: Option<Verbosity>,
+}
+
+impl Reporter {
+    pub fn new(verbosity: Option<Verbosity>) -> Self {
+        Self { verbosity }
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        matches!(self.verbosity, Some(Verbosity::Quiet))
+    }
+
+    pub fn is_verbose(&self) -> bool {
+        matches!(self.verbosity, Some(Verbosity::Verbose))
+    }
+
+    /// Announces step `step` of `total`, e.g. ` [1/3] Building cargo project`.
+    pub fn step(&self, step: usize, total: usize, name: impl Display) {
+        self.info(format_args!(
+            " {} {}",
+            format!("[{}/{}]", step, total).bold(),
+            name.to_string().bright_green().bold()
+        ));
+    }
+
+    /// Prints a status message to stderr, unless quiet.
+    pub fn info(&self, msg: impl Display) {
+        if !self.is_quiet() {
+            eprintln!("{}", msg);
+        }
+    }
+
+    /// Prints a message to stderr only if verbose, e.g. the details of a submitted call.
+    pub fn verbose(&self, msg: impl Display) {
+        if self.is_verbose() {
+            eprintln!("{}", msg);
+        }
+    }
+
+    /// Prints a warning to stderr, unless quiet.
+    pub fn warning(&self, msg: impl Display) {
+        if !self.is_quiet() {
+            eprintln!("{} {}", "warning:".yellow().bold(), msg);
+        }
+    }
+
+    /// Prints an error which does not abort the command, e.g. a failed rebuild in watch mode.
+    ///
+    /// Errors are printed even if quiet.
+    pub fn error(&self, msg: impl Display) {
+        eprintln!("{} {}", "error:".red().bold(), msg);
+    }
+
+    /// Shows a spinner with the elapsed time on stderr until the returned [`Spinner`] is dropped,
+    /// e.g. while waiting for an extrinsic to be included in a block.
+    ///
+    /// Nothing is shown if quiet or if stderr is not a terminal, so that logs are not cluttered.
+    pub fn spinner(&self, msg: impl Display) -> Spinner {
+        if self.is_quiet() || !atty::is(atty::Stream::Stderr) {
+            return Spinner { running: None };
+        }
+        Spinner::start(msg.to_string())
+    }
+}
+
+/// A spinner shown on stderr, see [`Reporter::spinner`]. Its line is cleared when dropped.
+pub struct Spinner {
+    running: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl Spinner {
+    const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn start(msg: String) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let started = Instant::now();
+                for frame in Self::FRAMES.iter().cycle() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    eprint!(
+                        "\r{} {} {:.1}s",
+                        frame.to_string().bright_blue().bold(),
+                        msg,
+                        started.elapsed().as_secs_f64()
+                    );
+                    let _ = std::io::stderr().flush();
+                    thread::sleep(Self::INTERVAL);
+                }
+            })
+        };
+        Spinner {
+            running: Some((stop, handle)),
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        if let Some((stop, handle)) = self.running.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+            // return to the start of the line and erase it
+            eprint!("\r\x1B[2K");
+        }
+    }
+}
@@ -17,7 +17,11 @@
 use crate::Verbosity;
 use anyhow::{Context, Result};
 use rustc_version::Channel;
-use std::{ffi::OsStr, path::Path, process::Command};
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+    process::Command,
+};
 
 /// Check whether the current rust channel is valid: `nightly` is recommended.
 pub fn assert_channel() -> Result<()> {
@@ -35,15 +39,93 @@ pub fn assert_channel() -> Result<()> {
     }
 }
 
-/// Run cargo with the supplied args
+/// The environment variables which determine the toolchain of the cargo and rustc invocations.
+const TOOLCHAIN_VARS: [&str; 2] = ["RUSTUP_TOOLCHAIN", "CARGO"];
+
+/// Restores the toolchain selected before [`select_toolchain`] when dropped.
+pub(crate) struct ToolchainGuard {
+    previous: Vec<(&'static str, Option<OsString>)>,
+}
+
+impl Drop for ToolchainGuard {
+    fn drop(&mut self) {
+        for (var, value) in &self.previous {
+            match value {
+                Some(value) => std::env::set_var(var, value),
+                None => std::env::remove_var(var),
+            }
+        }
+    }
+}
+
+/// Selects the rustup `toolchain` for all cargo and rustc invocations of this process until the
+/// returned guard is dropped, which is the same as invoking them with `+<toolchain>`.
 ///
-/// If successful, returns the stdout bytes
-pub(crate) fn invoke_cargo<I, S, P>(
+/// The toolchain is selected with `RUSTUP_TOOLCHAIN`, since cargo is also invoked by `xargo_lib`
+/// which does not expose its command. `CARGO`, which points to the cargo binary of the toolchain
+/// `cargo contract` was invoked with, is cleared so that the rustup proxy is used instead.
+pub(crate) fn select_toolchain(toolchain: &str) -> Result<ToolchainGuard> {
+    let installed = Command::new("rustc")
+        .arg(format!("+{}", toolchain))
+        .arg("--version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if !installed {
+        anyhow::bail!(
+            "The toolchain '{}' is not installed. Install it with `rustup toolchain install {}`",
+            toolchain,
+            toolchain
+        );
+    }
+    let guard = ToolchainGuard {
+        previous: TOOLCHAIN_VARS
+            .iter()
+            .map(|var| (*var, std::env::var_os(var)))
+            .collect(),
+    };
+    std::env::set_var("RUSTUP_TOOLCHAIN", toolchain);
+    std::env::remove_var("CARGO");
+    Ok(guard)
+}
+
+/// Returns true if the io error is caused by a failed or lost connection.
+pub fn is_connection_error(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::ConnectionRefused
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::NotConnected
+            | std::io::ErrorKind::TimedOut
+    )
+}
+
+/// Returns true if the error is caused by the transport to the node, rather than by the node
+/// rejecting the request.
+///
+/// RPC errors in general are not network errors, only those caused by a failed connection.
+pub fn is_network_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        #[cfg(feature = "extrinsics")]
+        {
+            if let Some(subxt::Error::Io(_)) = cause.downcast_ref::<subxt::Error>() {
+                return true;
+            }
+        }
+        cause
+            .downcast_ref::<std::io::Error>()
+            .map_or(false, is_connection_error)
+    })
+}
+
+/// Construct a cargo command with the supplied args
+fn cargo_cmd<I, S, P>(
     command: &str,
     args: I,
     working_dir: Option<P>,
     verbosity: Option<Verbosity>,
-) -> Result<Vec<u8>>
+) -> Command
 where
     I: IntoIterator<Item = S> + std::fmt::Debug,
     S: AsRef<OsStr>,
@@ -63,6 +145,24 @@ where
         Some(Verbosity::Verbose) => cmd.arg("--verbose"),
         None => &mut cmd,
     };
+    cmd
+}
+
+/// Run cargo with the supplied args
+///
+/// If successful, returns the stdout bytes
+pub(crate) fn invoke_cargo<I, S, P>(
+    command: &str,
+    args: I,
+    working_dir: Option<P>,
+    verbosity: Option<Verbosity>,
+) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S> + std::fmt::Debug,
+    S: AsRef<OsStr>,
+    P: AsRef<Path>,
+{
+    let mut cmd = cargo_cmd(command, args, working_dir, verbosity);
 
     log::info!("invoking cargo: {:?}", cmd);
 
@@ -84,10 +184,265 @@ where
     }
 }
 
+/// Run cargo with the supplied args, forwarding the output of the child process as it is produced
+///
+/// If `Verbosity::Quiet` is supplied the stdout of the child process is discarded.
+pub(crate) fn invoke_cargo_streaming<I, S, P>(
+    command: &str,
+    args: I,
+    working_dir: Option<P>,
+    verbosity: Option<Verbosity>,
+) -> Result<()>
+where
+    I: IntoIterator<Item = S> + std::fmt::Debug,
+    S: AsRef<OsStr>,
+    P: AsRef<Path>,
+{
+    let mut cmd = cargo_cmd(command, args, working_dir, verbosity);
+    if let Some(Verbosity::Quiet) = verbosity {
+        cmd.stdout(std::process::Stdio::null());
+    }
+
+    log::info!("invoking cargo: {:?}", cmd);
+
+    let status = cmd
+        .status()
+        .context(format!("Error executing `{:?}`", cmd))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        anyhow::bail!("`{:?}` failed with exit code: {:?}", cmd, status.code());
+    }
+}
+
+/// Runs `f` with the stderr of this process redirected to a temporary file, and returns the result
+/// of `f` together with the captured output.
+///
+/// The redirection is inherited by child processes, so this captures the output of processes
+/// spawned by libraries which do not expose their `Command`, like the cargo invocation of
+/// `xargo_lib::build`.
+#[cfg(unix)]
+pub(crate) fn capture_stderr<T>(f: impl FnOnce() -> T) -> Result<(T, String)> {
+    capture_fd(libc::STDERR_FILENO, "stderr", f)
+}
+
+/// Runs `f` with the stdout of this process redirected to a temporary file, see
+/// [`capture_stderr`].
+#[cfg(unix)]
+pub(crate) fn capture_stdout<T>(f: impl FnOnce() -> T) -> Result<(T, String)> {
+    capture_fd(libc::STDOUT_FILENO, "stdout", f)
+}
+
+#[cfg(unix)]
+fn capture_fd<T>(fd: libc::c_int, name: &str, f: impl FnOnce() -> T) -> Result<(T, String)> {
+    use std::{
+        io::{self, Read, Seek, SeekFrom, Write},
+        os::unix::io::AsRawFd,
+    };
+
+    let flush = || {
+        if fd == libc::STDOUT_FILENO {
+            io::stdout().flush()
+        } else {
+            io::stderr().flush()
+        }
+    };
+    let mut capture =
+        tempfile::tempfile().context(format!("Creating a file to capture {}", name))?;
+    flush()?;
+    // safe since only the file descriptor is duplicated and replaced, and it is restored below
+    // before returning
+    let saved = unsafe { libc::dup(fd) };
+    if saved < 0 {
+        anyhow::bail!("Duplicating {}: {}", name, io::Error::last_os_error())
+    }
+    if unsafe { libc::dup2(capture.as_raw_fd(), fd) } < 0 {
+        let err = io::Error::last_os_error();
+        unsafe { libc::close(saved) };
+        anyhow::bail!("Redirecting {}: {}", name, err)
+    }
+
+    let result = f();
+
+    let _ = flush();
+    let restored = unsafe { libc::dup2(saved, fd) };
+    unsafe { libc::close(saved) };
+    if restored < 0 {
+        anyhow::bail!("Restoring {}: {}", name, io::Error::last_os_error())
+    }
+
+    let mut output = Vec::new();
+    capture.seek(SeekFrom::Start(0))?;
+    capture.read_to_end(&mut output)?;
+    Ok((result, String::from_utf8_lossy(&output).into_owned()))
+}
+
+/// Runs `f`, stderr can only be captured on unix so the output is forwarded as it is produced.
+#[cfg(not(unix))]
+pub(crate) fn capture_stderr<T>(f: impl FnOnce() -> T) -> Result<(T, String)> {
+    Ok((f(), String::new()))
+}
+
+/// Runs `f`, stdout can only be captured on unix so the output is forwarded as it is produced.
+#[cfg(not(unix))]
+pub(crate) fn capture_stdout<T>(f: impl FnOnce() -> T) -> Result<(T, String)> {
+    Ok((f(), String::new()))
+}
+
+/// Formats a size in bytes in a human readable form, using binary (KiB, MiB) units.
+pub(crate) fn format_size(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+    let size = bytes as f64;
+    if size >= MIB {
+        format!("{:.1}MiB", size / MIB)
+    } else if size >= KIB {
+        format!("{:.1}KiB", size / KIB)
+    } else {
+        format!("{}B", bytes)
+    }
+}
+
+/// Returns the Levenshtein edit distance between two strings, i.e. the minimal number of single
+/// character insertions, deletions or substitutions required to turn one into the other.
+pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut distances = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_diagonal + if a_char == *b_char { 0 } else { 1 };
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution
+                .min(distances[j] + 1)
+                .min(previous_diagonal + 1);
+        }
+    }
+    distances[b.len()]
+}
+
+/// Returns the candidate closest to `input`, if it is within a third of the input's length.
+pub(crate) fn closest_match<'a>(input: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|candidate| (levenshtein(input, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= std::cmp::max(1, input.chars().count() / 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
 #[cfg(test)]
 pub mod tests {
     use std::path::Path;
 
+    #[test]
+    fn unknown_toolchain_suggests_installing_it() {
+        let result = super::select_toolchain("nightly-1970-01-01");
+        let err = result
+            .err()
+            .expect("selecting a missing toolchain must fail");
+        assert!(err
+            .to_string()
+            .contains("rustup toolchain install nightly-1970-01-01"));
+        assert_ne!(
+            std::env::var("RUSTUP_TOOLCHAIN").ok().as_deref(),
+            Some("nightly-1970-01-01")
+        );
+    }
+
+    #[test]
+    fn connection_errors_are_network_errors() {
+        let err = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert!(super::is_network_error(
+            &anyhow::Error::from(err).context("Connecting")
+        ));
+    }
+
+    #[test]
+    fn dispatch_errors_are_not_network_errors() {
+        let err = anyhow::anyhow!("Extrinsic failed: Contracts::OutOfGas");
+        assert!(!super::is_network_error(&err));
+    }
+
+    #[test]
+    fn other_io_errors_are_not_network_errors() {
+        let err = std::io::Error::from(std::io::ErrorKind::InvalidData);
+        assert!(!super::is_network_error(&anyhow::Error::from(err)));
+    }
+
+    #[cfg(feature = "extrinsics")]
+    #[test]
+    fn only_subxt_io_errors_are_network_errors() {
+        let err = subxt::Error::Other("Contract not found".into());
+        assert!(!super::is_network_error(&anyhow::Error::from(err)));
+        let err = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert!(super::is_network_error(&anyhow::Error::from(
+            subxt::Error::Io(err)
+        )));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn capture_stderr_captures_child_processes() {
+        let (status, output) = super::capture_stderr(|| {
+            std::process::Command::new("sh")
+                .args(&["-c", "echo 'error[E0425]: cannot find value' >&2"])
+                .status()
+        })
+        .expect("capturing stderr failed");
+        assert!(status.expect("sh failed to run").success());
+        assert_eq!(output, "error[E0425]: cannot find value\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn capture_stdout_captures_child_processes() {
+        let (status, output) = super::capture_stdout(|| {
+            std::process::Command::new("sh")
+                .args(&["-c", "echo '{\"reason\":\"build-finished\"}'"])
+                .status()
+        })
+        .expect("capturing stdout failed");
+        assert!(status.expect("sh failed to run").success());
+        assert_eq!(output, "{\"reason\":\"build-finished\"}\n");
+    }
+
+    #[test]
+    fn format_size_uses_binary_units() {
+        assert_eq!(super::format_size(512), "512B");
+        assert_eq!(super::format_size(1024), "1.0KiB");
+        assert_eq!(super::format_size(123_289), "120.4KiB");
+        assert_eq!(super::format_size(3 * 1024 * 1024 / 2), "1.5MiB");
+    }
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(super::levenshtein("", ""), 0);
+        assert_eq!(super::levenshtein("flag", "flag"), 0);
+        assert_eq!(super::levenshtein("", "flag"), 4);
+        assert_eq!(super::levenshtein("kitten", "sitting"), 3);
+        assert_eq!(
+            super::levenshtein("orignal-manifest", "original-manifest"),
+            1
+        );
+    }
+
+    #[test]
+    fn closest_match_ignores_far_candidates() {
+        let candidates = ["original-manifest", "other"];
+        assert_eq!(
+            super::closest_match("original-manifest", &candidates),
+            Some("original-manifest")
+        );
+        assert_eq!(
+            super::closest_match("orignal-manifest", &candidates),
+            Some("original-manifest")
+        );
+        assert_eq!(super::closest_match("optimize", &candidates), None);
+    }
+
     pub fn with_tmp_dir<F>(f: F)
     where
         F: FnOnce(&Path) -> anyhow::Result<()>,
@@ -100,4 +455,22 @@ pub mod tests {
         // catch test panics in order to clean up temp dir which will be very large
         f(tmp_dir.path()).expect("Error executing test with tmp dir")
     }
+
+    /// Creates a contract from the default template in `path`, returning its manifest.
+    pub fn new_project(path: &Path) -> crate::workspace::ManifestPath {
+        crate::cmd::new::execute("new_project", Some(path), Default::default(), false, false)
+            .expect("new project creation failed");
+        crate::workspace::ManifestPath::new(path.join("new_project").join("Cargo.toml"))
+            .expect("new project manifest is invalid")
+    }
+
+    /// Returns the options of an extrinsic signed by `//Alice` and submitted to the node at `url`,
+    /// amended by the further command line `args`.
+    #[cfg(feature = "extrinsics")]
+    pub fn extrinsic_opts(url: &str, args: &[&str]) -> crate::ExtrinsicOpts {
+        use structopt::StructOpt;
+
+        let base = ["extrinsic-opts", "--url", url, "--suri", "//Alice"];
+        crate::ExtrinsicOpts::from_iter(base.iter().chain(args))
+    }
 }
@@ -76,6 +76,35 @@ impl TryFrom<&PathBuf> for ManifestPath {
     }
 }
 
+impl<P> TryFrom<Option<P>> for ManifestPath
+where
+    P: AsRef<Path>,
+{
+    type Error = anyhow::Error;
+
+    /// Resolves a user supplied manifest path, defaulting to the `Cargo.toml` in the current
+    /// directory. The supplied path must point to an existing `Cargo.toml` file.
+    fn try_from(value: Option<P>) -> Result<Self, Self::Error> {
+        let path = match value {
+            Some(path) => path,
+            None => return Ok(Default::default()),
+        };
+        let path = path.as_ref();
+        if path.is_dir() {
+            anyhow::bail!(
+                "The manifest path '{}' is a directory, it should point to a {} file",
+                path.display(),
+                MANIFEST_FILE
+            )
+        }
+        if !path.exists() {
+            anyhow::bail!("The manifest path '{}' does not exist", path.display())
+        }
+        // use the absolute path, since commands may be invoked from the manifest directory
+        ManifestPath::new(path.canonicalize()?)
+    }
+}
+
 impl Default for ManifestPath {
     fn default() -> ManifestPath {
         ManifestPath::new(MANIFEST_FILE).expect("it's a valid manifest file")
@@ -92,8 +121,6 @@ impl AsRef<Path> for ManifestPath {
 pub struct Manifest {
     path: ManifestPath,
     toml: value::Table,
-    /// True if a metadata package should be generated for this manifest
-    metadata_package: bool,
 }
 
 impl Manifest {
@@ -111,7 +138,6 @@ impl Manifest {
         Ok(Manifest {
             path: manifest_path,
             toml,
-            metadata_package: false,
         })
     }
 
@@ -196,6 +222,8 @@ impl Manifest {
     }
 
     /// Adds a metadata package to the manifest workspace for generating metadata
+    ///
+    /// The package itself is generated by [`Manifest::write_metadata_package`].
     pub fn with_metadata_package(&mut self) -> Result<&mut Self> {
         let workspace = self
             .toml
@@ -212,20 +240,18 @@ impl Manifest {
         if members.contains(&LEGACY_METADATA_PACKAGE_PATH.into()) {
             // warn user if they have legacy metadata generation artifacts
             use colored::Colorize;
-            println!(
-                "{} {} {} {}",
-                "warning:".yellow().bold(),
+            crate::Reporter::default().warning(format_args!(
+                "{} {} {}",
                 "please remove".bold(),
                 LEGACY_METADATA_PACKAGE_PATH.bold(),
                 "from the `[workspace]` section in the `Cargo.toml`, \
                 and delete that directory. These are now auto-generated."
                     .bold()
-            );
+            ));
         } else {
             members.push(METADATA_PACKAGE_PATH.into());
         }
 
-        self.metadata_package = true;
         Ok(self)
     }
 
@@ -273,14 +299,13 @@ impl Manifest {
                     to_absolute(format!("[{}]/path", table_section), existing_path)
                 }
                 None => {
-                    let default_path = PathBuf::from(default);
-                    if !default_path.exists() {
+                    let path = abs_dir.join(default);
+                    if !path.exists() {
                         anyhow::bail!(
                             "No path specified, and the default `{}` was not found",
                             default
                         )
                     }
-                    let path = abs_dir.join(default_path);
                     log::debug!("Adding default path '{}'", path.display());
                     table.insert(
                         "path".into(),
@@ -343,36 +368,6 @@ impl Manifest {
             fs::create_dir_all(dir).context(format!("Creating directory '{}'", dir.display()))?;
         }
 
-        if self.metadata_package {
-            let dir = if let Some(manifest_dir) = manifest_path.directory() {
-                manifest_dir.join(METADATA_PACKAGE_PATH)
-            } else {
-                METADATA_PACKAGE_PATH.into()
-            };
-
-            fs::create_dir_all(&dir).context(format!("Creating directory '{}'", dir.display()))?;
-
-            let name = self
-                .toml
-                .get("lib")
-                .ok_or(anyhow::anyhow!("lib section not found"))?
-                .get("name")
-                .ok_or(anyhow::anyhow!("[lib] name field not found"))?
-                .as_str()
-                .ok_or(anyhow::anyhow!("[lib] name should be a string"))?;
-
-            let ink_metadata = self
-                .toml
-                .get("dependencies")
-                .ok_or(anyhow::anyhow!("[dependencies] section not found"))?
-                .get("ink_metadata")
-                .ok_or(anyhow::anyhow!("{} dependency not found", name))?
-                .as_table()
-                .ok_or(anyhow::anyhow!("{} dependency should be a table", name))?;
-
-            metadata::generate_package(dir, name, ink_metadata.clone())?;
-        }
-
         let updated_toml = toml::to_string(&self.toml)?;
         log::debug!(
             "Writing updated manifest to '{}'",
@@ -381,6 +376,47 @@ impl Manifest {
         fs::write(manifest_path, updated_toml)?;
         Ok(())
     }
+
+    /// Generates the metadata package for the contract of this manifest in the `.ink` directory
+    /// next to the `workspace_manifest`.
+    ///
+    /// `contract_dir` is the directory of the contract relative to the workspace root, which is
+    /// empty if the contract is the workspace root package itself.
+    pub(super) fn write_metadata_package(
+        &self,
+        workspace_manifest: &ManifestPath,
+        contract_dir: &Path,
+    ) -> Result<()> {
+        let dir = if let Some(manifest_dir) = workspace_manifest.directory() {
+            manifest_dir.join(METADATA_PACKAGE_PATH)
+        } else {
+            METADATA_PACKAGE_PATH.into()
+        };
+
+        fs::create_dir_all(&dir).context(format!("Creating directory '{}'", dir.display()))?;
+
+        let name = self
+            .toml
+            .get("lib")
+            .ok_or(anyhow::anyhow!("lib section not found"))?
+            .get("name")
+            .ok_or(anyhow::anyhow!("[lib] name field not found"))?
+            .as_str()
+            .ok_or(anyhow::anyhow!("[lib] name should be a string"))?;
+
+        let ink_metadata = self
+            .toml
+            .get("dependencies")
+            .ok_or(anyhow::anyhow!("[dependencies] section not found"))?
+            .get("ink_metadata")
+            .ok_or(anyhow::anyhow!("{} dependency not found", name))?
+            .as_table()
+            .ok_or(anyhow::anyhow!("{} dependency should be a table", name))?;
+
+        // the metadata package is two levels below the workspace root
+        let contract_path = Path::new("../..").join(contract_dir);
+        metadata::generate_package(dir, name, &contract_path, ink_metadata.clone())
+    }
 }
 
 fn crate_type_exists(crate_type: &str, crate_types: &value::Array) -> bool {
@@ -388,3 +424,57 @@ fn crate_type_exists(crate_type: &str, crate_types: &value::Array) -> bool {
         .iter()
         .any(|v| v.as_str().map_or(false, |s| s == crate_type))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn manifest_path_defaults_to_current_dir_manifest() {
+        let manifest_path = ManifestPath::try_from(None::<PathBuf>).unwrap();
+        assert_eq!(manifest_path.as_ref(), Path::new(MANIFEST_FILE));
+    }
+
+    #[test]
+    fn manifest_path_rejects_directory() {
+        with_tmp_dir(|path| {
+            let result = ManifestPath::try_from(Some(path));
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                format!(
+                    "The manifest path '{}' is a directory, it should point to a Cargo.toml file",
+                    path.display()
+                )
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn manifest_path_rejects_missing_file() {
+        with_tmp_dir(|path| {
+            let manifest = path.join(MANIFEST_FILE);
+            let result = ManifestPath::try_from(Some(&manifest));
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                format!("The manifest path '{}' does not exist", manifest.display())
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn manifest_path_rejects_other_file_names() {
+        with_tmp_dir(|path| {
+            let file = path.join("Other.toml");
+            fs::write(&file, "")?;
+            let result = ManifestPath::try_from(Some(&file));
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                "Manifest file must be a Cargo.toml"
+            );
+            Ok(())
+        })
+    }
+}
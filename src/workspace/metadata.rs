@@ -24,10 +24,12 @@ use toml::value;
 /// # Note
 ///
 /// `ink!` dependencies are copied from the containing contract workspace to ensure the same
-/// versions are utilized.
+/// versions are utilized. The contract is depended upon by its `contract_path` relative to
+/// `target_dir`.
 pub(super) fn generate_package<P: AsRef<Path>>(
     target_dir: P,
     contract_package_name: &str,
+    contract_path: &Path,
     mut ink_metadata_dependency: value::Table,
 ) -> Result<()> {
     let dir = target_dir.as_ref();
@@ -54,6 +56,10 @@ pub(super) fn generate_package<P: AsRef<Path>>(
         .as_table_mut()
         .expect("contract dependency is a table specified in the template");
     contract.insert("package".into(), contract_package_name.into());
+    contract.insert(
+        "path".into(),
+        contract_path.to_string_lossy().as_ref().into(),
+    );
 
     // make ink_metadata dependency use default features
     ink_metadata_dependency.remove("default-features");
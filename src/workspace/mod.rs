@@ -24,11 +24,13 @@ pub use self::{
     profile::Profile,
 };
 
+use crate::interrupt::CleanupGuard;
 use anyhow::Result;
 use cargo_metadata::{Metadata as CargoMetadata, Package, PackageId};
 
 use std::{
     collections::HashMap,
+    fs,
     path::{Path, PathBuf},
 };
 
@@ -38,10 +40,17 @@ use std::{
 ///
 /// This allows custom amendments to be made to the manifest files without editing the originals
 /// directly.
+///
+/// The root package may be a member of a larger workspace, in which case the workspace manifest
+/// is either the manifest of another member or a virtual manifest without a package.
 pub struct Workspace {
     workspace_root: PathBuf,
     root_package: PackageId,
     members: HashMap<PackageId, (Package, Manifest)>,
+    /// The workspace manifest, if it is a virtual manifest without a package.
+    virtual_manifest: Option<Manifest>,
+    /// True if a metadata package should be generated for the root package.
+    metadata_gen_package: bool,
 }
 
 impl Workspace {
@@ -70,10 +79,21 @@ impl Workspace {
             anyhow::bail!("The root package should be a workspace member")
         }
 
+        let workspace_root = metadata.workspace_root.clone();
+        let is_member_manifest =
+            |manifest: &Manifest| manifest.path().directory() == Some(workspace_root.as_path());
+        let virtual_manifest = if members.values().any(|(_, m)| is_member_manifest(m)) {
+            None
+        } else {
+            Some(Manifest::new(&workspace_root.join("Cargo.toml"))?)
+        };
+
         Ok(Workspace {
-            workspace_root: metadata.workspace_root.clone(),
+            workspace_root,
             root_package: root_package.clone(),
             members,
+            virtual_manifest,
+            metadata_gen_package: false,
         })
     }
 
@@ -97,30 +117,32 @@ impl Workspace {
     }
 
     /// Amend the workspace manifest using the supplied function.
+    ///
+    /// This is the manifest at the workspace root, which is the root package manifest unless the
+    /// root package is a member of a larger workspace. Settings which cargo only reads from the
+    /// workspace root, such as `[profile]` sections, should be amended here.
     pub fn with_workspace_manifest<F>(&mut self, f: F) -> Result<&mut Self>
     where
         F: FnOnce(&mut Manifest) -> Result<()>,
     {
         let workspace_root = self.workspace_root.clone();
-        let workspace_manifest = self
-            .members
-            .iter_mut()
-            .find_map(|(_, (_, manifest))| {
-                if manifest.path().directory() == Some(&workspace_root) {
-                    Some(manifest)
-                } else {
-                    None
-                }
-            })
-            .ok_or(anyhow::anyhow!(
-                "The workspace root package should be a workspace member"
-            ))?;
+        let member_manifest = self.members.iter_mut().find_map(|(_, (_, manifest))| {
+            if manifest.path().directory() == Some(&workspace_root) {
+                Some(manifest)
+            } else {
+                None
+            }
+        });
+        let workspace_manifest = member_manifest
+            .or(self.virtual_manifest.as_mut())
+            .expect("The workspace manifest is either a member or virtual; qed");
         f(workspace_manifest)?;
         Ok(self)
     }
 
     /// Generates a package to invoke for generating contract metadata
     pub(super) fn with_metadata_gen_package(&mut self) -> Result<&mut Self> {
+        self.metadata_gen_package = true;
         self.with_workspace_manifest(|manifest| {
             manifest.with_metadata_package()?;
             Ok(())
@@ -128,7 +150,8 @@ impl Workspace {
     }
 
     /// Writes the amended manifests to the `target` directory, retaining the workspace directory
-    /// structure, but only with the `Cargo.toml` files.
+    /// structure, but only with the `Cargo.toml` files and the `Cargo.lock` of the workspace, so
+    /// that the dependency versions locked in the original workspace are built.
     ///
     /// Relative paths will be rewritten to absolute paths from the original workspace root, except
     /// intra-workspace relative dependency paths which will be preserved.
@@ -152,11 +175,38 @@ impl Workspace {
 
             new_manifest_paths.push((package_id.clone(), new_manifest));
         }
+
+        let workspace_manifest = ManifestPath::new(target.as_ref().join("Cargo.toml"))?;
+        if let Some(manifest) = self.virtual_manifest.as_mut() {
+            manifest.rewrite_relative_paths(&exclude_member_package_names)?;
+            manifest.write(&workspace_manifest)?;
+        }
+
+        if self.metadata_gen_package {
+            let (root_package, root_manifest) = self
+                .members
+                .get(&self.root_package)
+                .expect("The root package should be a workspace member");
+            let contract_dir = root_package
+                .manifest_path
+                .parent()
+                .expect("The manifest path is a file path so has a parent; qed")
+                .strip_prefix(&self.workspace_root)?;
+            root_manifest.write_metadata_package(&workspace_manifest, contract_dir)?;
+        }
+
+        let lock_file = self.workspace_root.join("Cargo.lock");
+        if lock_file.exists() {
+            fs::copy(&lock_file, target.as_ref().join("Cargo.lock"))?;
+        }
         Ok(new_manifest_paths)
     }
 
     /// Copy the workspace with amended manifest files to a temporary directory, executing the
     /// supplied function with the root manifest path before the directory is cleaned up.
+    ///
+    /// The original manifests are never written to. The temporary directory is removed when the
+    /// function returns, also if it fails or panics, so a failed build leaves no trace.
     pub fn using_temp<F>(&mut self, f: F) -> Result<()>
     where
         F: FnOnce(&ManifestPath) -> Result<()>,
@@ -165,6 +215,7 @@ impl Workspace {
             .prefix("cargo-contract_")
             .tempdir()?;
         log::debug!("Using temp workspace at '{}'", tmp_dir.path().display());
+        let _cleanup = CleanupGuard::new(tmp_dir.path());
         let new_paths = self.write(&tmp_dir)?;
         let root_manifest_path = new_paths
             .iter()
@@ -179,3 +230,133 @@ impl Workspace {
         f(root_manifest_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Profile, Workspace};
+    use crate::util::tests::with_tmp_dir;
+    use cargo_metadata::MetadataCommand;
+    use std::{fs, path::PathBuf};
+    use toml::value;
+
+    const WORKSPACE_MANIFEST: &str = "[workspace]\nmembers = [\"contract\"]\n";
+    const CONTRACT_MANIFEST: &str = r#"[package]
+name = "contract"
+version = "0.1.0"
+edition = "2018"
+
+[lib]
+crate-type = ["cdylib", "rlib"]
+"#;
+
+    fn read_toml(path: &std::path::Path) -> anyhow::Result<value::Table> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    #[test]
+    fn workspace_member_is_amended_with_profile_in_virtual_manifest() {
+        with_tmp_dir(|path| {
+            let workspace = path.join("workspace");
+            let contract = workspace.join("contract");
+            fs::create_dir_all(contract.join("src"))?;
+            fs::write(workspace.join("Cargo.toml"), WORKSPACE_MANIFEST)?;
+            fs::write(contract.join("Cargo.toml"), CONTRACT_MANIFEST)?;
+            fs::write(contract.join("src").join("lib.rs"), "")?;
+
+            let metadata = MetadataCommand::new()
+                .manifest_path(contract.join("Cargo.toml"))
+                .exec()?;
+            let root_package = metadata
+                .resolve
+                .as_ref()
+                .and_then(|resolve| resolve.root.clone())
+                .expect("the contract member is the root package");
+            let tmp = path.join("tmp");
+            Workspace::new(&metadata, &root_package)?
+                .with_root_package_manifest(|manifest| {
+                    manifest.with_removed_crate_type("rlib")?;
+                    Ok(())
+                })?
+                .with_workspace_manifest(|manifest| {
+                    manifest.with_profile_release_defaults(Profile::default_contract_release())?;
+                    Ok(())
+                })?
+                .write(&tmp)?;
+
+            let workspace_toml = read_toml(&tmp.join("Cargo.toml"))?;
+            assert_eq!(
+                workspace_toml["profile"]["release"]["lto"].as_str(),
+                Some("fat")
+            );
+            let contract_toml = read_toml(&tmp.join("contract").join("Cargo.toml"))?;
+            assert!(contract_toml.get("profile").is_none());
+            assert_eq!(
+                contract_toml["lib"]["crate-type"],
+                value::Value::Array(vec!["cdylib".into()])
+            );
+            // the lock file written by `cargo metadata` is copied along
+            assert_eq!(
+                fs::read_to_string(tmp.join("Cargo.lock"))?,
+                fs::read_to_string(workspace.join("Cargo.lock"))?
+            );
+
+            // the original manifests are left untouched
+            assert_eq!(
+                fs::read_to_string(workspace.join("Cargo.toml"))?,
+                WORKSPACE_MANIFEST
+            );
+            assert_eq!(
+                fs::read_to_string(contract.join("Cargo.toml"))?,
+                CONTRACT_MANIFEST
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn temp_workspace_is_removed_when_build_fails_or_panics() {
+        with_tmp_dir(|path| {
+            fs::create_dir_all(path.join("src"))?;
+            fs::write(path.join("Cargo.toml"), CONTRACT_MANIFEST)?;
+            fs::write(path.join("src").join("lib.rs"), "")?;
+            let metadata = MetadataCommand::new()
+                .manifest_path(path.join("Cargo.toml"))
+                .exec()?;
+            let root_package = metadata
+                .resolve
+                .as_ref()
+                .and_then(|resolve| resolve.root.clone())
+                .expect("the contract is the root package");
+
+            let mut temp_manifest = PathBuf::new();
+            let result = Workspace::new(&metadata, &root_package)?
+                .with_root_package_manifest(|manifest| {
+                    manifest.with_removed_crate_type("rlib")?;
+                    Ok(())
+                })?
+                .using_temp(|manifest_path| {
+                    temp_manifest = manifest_path.as_ref().to_path_buf();
+                    anyhow::bail!("build failed")
+                });
+            assert!(result.is_err());
+            assert!(!temp_manifest.as_os_str().is_empty());
+            assert!(!temp_manifest.exists());
+
+            let mut workspace = Workspace::new(&metadata, &root_package)?;
+            let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                workspace.using_temp(|manifest_path| {
+                    temp_manifest = manifest_path.as_ref().to_path_buf();
+                    panic!("build panicked")
+                })
+            }));
+            assert!(panicked.is_err());
+            assert!(!temp_manifest.exists());
+
+            assert_eq!(
+                fs::read_to_string(path.join("Cargo.toml"))?,
+                CONTRACT_MANIFEST
+            );
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,26 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+#[ink::contract(version = "0.1.0")]
+mod {{name}} {
+
+    /// Defines the storage of your contract.
+    /// Add new fields to the below struct in order
+    /// to add new static storage fields to your contract.
+    #[ink(storage)]
+    struct {{camel_name}} {}
+
+    impl {{camel_name}} {
+        /// Constructor that initializes the contract.
+        #[ink(constructor)]
+        fn new() -> Self {
+            Self {}
+        }
+
+        /// A message that can be called on instantiated contracts.
+        /// Replace it with the messages of your contract.
+        #[ink(message)]
+        fn noop(&self) {}
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::process::Command;
+
+fn cargo_contract(args: &[&str]) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_cargo-t3rn-contract"));
+    cmd.arg("contract").args(args);
+    cmd
+}
+
+#[test]
+fn failing_command_exits_with_non_zero_status() {
+    let tmp_dir = tempfile::tempdir().expect("temporary directory creation failed");
+    let status = cargo_contract(&["new", "hyphenated-name"])
+        .current_dir(tmp_dir.path())
+        .status()
+        .expect("failed to run cargo-contract");
+
+    assert_eq!(status.code(), Some(1));
+}
+
+#[test]
+fn bad_arguments_exit_with_distinct_status() {
+    let status = cargo_contract(&["build", "--no-such-flag"])
+        .status()
+        .expect("failed to run cargo-contract");
+
+    assert_eq!(status.code(), Some(2));
+}